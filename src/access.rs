@@ -0,0 +1,179 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::call::ActorError;
+
+// ###################################################### //
+// ################### ACCESS CONTROL ################### //
+// ###################################################### //
+
+/// Who someone is on the team, which decides what they may ask the actors to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Runs the VIP (e.g. John himself): may do everything a role can do.
+    Coordinator,
+    /// Helps grade: may register and grade students and push them through Brightspace.
+    Ta,
+    /// May only see summary statistics and their own grade (their `--user` is their career ID).
+    Student,
+}
+
+/// Everything a handle can be asked to do, grouped by what it changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    RegisterStudent,
+    AssignGrade,
+    ReportToBrightspace,
+    EditBrightspace,
+    ReportToAdmin,
+    /// Replacing Admin's names or grades directly. No role may do this: grades reach Admin through John,
+    /// and Booster only through an approval (see `AdminHandle::approve_booster()`).
+    WriteGradebook,
+    ReadGradebook,
+    ReadStatistics,
+    /// Looking up one student's grade in the StudentPortal. Students may, but only for their own career ID.
+    ViewStudentRecord,
+    Boost,
+    ApproveBooster,
+    /// Putting a set of grade changes in front of Admin's approvers.
+    ProposeGradeChanges,
+    /// Accepting or rejecting a pending proposal.
+    DecideProposal,
+    /// Archiving the current term and starting the next one.
+    RollOverTerm,
+    /// Taking back a change to Admin's gradebook, or making an undone one again (see `history.rs`).
+    UndoRedo,
+    /// Overwriting one roster so it matches another actor's (see `reconcile.rs`).
+    Reconcile,
+    /// Capturing an actor's whole state, or putting a captured state back (see `snapshot.rs`).
+    Snapshot,
+    Wire,
+}
+
+impl Role {
+    /// Whether someone with this role may perform `op`.
+    pub fn may(self, op: Operation) -> bool {
+        use Operation::*;
+        match self {
+            Role::Coordinator => op != WriteGradebook,
+            Role::Ta => matches!(
+                op,
+                RegisterStudent
+                    | AssignGrade
+                    | ReportToBrightspace
+                    | EditBrightspace
+                    | ReportToAdmin
+                    | ReadGradebook
+                    | ReadStatistics
+                    | ViewStudentRecord
+            ),
+            Role::Student => matches!(op, ReadStatistics | ViewStudentRecord),
+        }
+    }
+
+    /// Whether this role may do everything `other` may, and more.
+    fn outranks(self, other: Role) -> bool {
+        let rank = |role| match role {
+            Role::Student => 0,
+            Role::Ta => 1,
+            Role::Coordinator => 2,
+        };
+        rank(self) > rank(other)
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "coordinator" => Ok(Role::Coordinator),
+            "ta" => Ok(Role::Ta),
+            "student" => Ok(Role::Student),
+            _ => Err(format!(
+                "unknown role `{}`, expected coordinator, ta or student",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Coordinator => write!(f, "coordinator"),
+            Role::Ta => write!(f, "TA"),
+            Role::Student => write!(f, "student"),
+        }
+    }
+}
+
+/// A person using a handle, e.g. `Caller::new("alice", Role::Ta)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Caller {
+    pub name: String,
+    pub role: Role,
+}
+
+impl Caller {
+    pub fn new(name: impl Into<String>, role: Role) -> Self {
+        Caller {
+            name: name.into(),
+            role,
+        }
+    }
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.role)
+    }
+}
+
+/// Who a handle acting for `current` acts for once it is asked to act as `requested`.
+///  - Note: `acting_as()` can only take rights away, so a handle that already has a caller keeps them unless
+///    `requested` has a lower role; otherwise a TA (or a student asking as someone else) could name anyone.
+pub(crate) fn narrow(current: Option<&Caller>, requested: Caller) -> Caller {
+    match current {
+        Some(current) if *current != requested && !current.role.outranks(requested.role) => {
+            eprintln!(
+                "[ACCESS]: {} cannot act as {}, staying {}",
+                current, requested, current
+            );
+            current.clone()
+        }
+        _ => requested,
+    }
+}
+
+/// Checks that `caller` may ask `actor` to perform `op`, and logs the attempt on stderr if not.
+///  - Note: `None` is the code that started the actor (e.g. `main` or another actor); a handle only gets a
+///    `Caller` once someone scopes it with `acting_as()`, and from then on every call is checked.
+pub(crate) fn authorize(
+    caller: Option<&Caller>,
+    actor: &'static str,
+    op: Operation,
+) -> Result<(), ActorError> {
+    match caller {
+        None => Ok(()),
+        Some(caller) if caller.role.may(op) => Ok(()),
+        Some(caller) => {
+            eprintln!("[ACCESS]: denied {} asking {} for {:?}", caller, actor, op);
+            Err(ActorError::Denied {
+                actor,
+                caller: caller.to_string(),
+                operation: op,
+            })
+        }
+    }
+}
@@ -1,4 +1,123 @@
-use tokio::sync::{mpsc, oneshot};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation};
+use crate::call::{self, ActorError};
+use crate::course::{Course, CourseKey, Courses, Roster, Scope, Term};
+use crate::deadline::{LateReport, LateWork};
+use crate::events::{self, AdminEvent, EventFilter, Subscription, student_of};
+use crate::feedback::{Comment, FeedbackReport};
+use crate::grade::Grade;
+use crate::history::{EntryId, History, Revision};
+use crate::proposal::{self, GradeChange, Outcome, Proposal, ProposalBook, ProposalId};
+use crate::rubric::{RubricReport, RubricScore};
+use crate::timeline::{GradeHistory, GradeSource, Timelines};
+use crate::trace::trace;
+use crate::wiring::ActorId;
+
+const ACTOR: &str = "Admin";
+
+/// How often Admin throws away expired proposals when nobody is sending it messages.
+const EXPIRY_SWEEP: Duration = Duration::from_secs(5);
+
+/// Grades below this count as failing.
+pub const PASSING_GRADE: f64 = 60.0;
+
+/// Summary numbers for the whole gradebook (`None` when there are no scores to summarize).
+///  - Note: `count` and everything after it only cover scored grades, the other states are counted on their own.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GradeStatistics {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub failing: usize,
+    pub ungraded: usize,
+    pub incomplete: usize,
+    pub excused: usize,
+}
+
+impl GradeStatistics {
+    /// Summarizes `grades`, e.g. one course's or a whole term's.
+    pub fn of(grades: &[Grade]) -> Self {
+        let mut sorted = Grade::scores(grades);
+        sorted.sort_by(f64::total_cmp);
+        let count_of = |state: Grade| grades.iter().filter(|g| **g == state).count();
+
+        let count = sorted.len();
+        let median = match count {
+            0 => None,
+            n if n % 2 == 1 => Some(sorted[n / 2]),
+            n => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2.0),
+        };
+        GradeStatistics {
+            count,
+            mean: (count > 0).then(|| sorted.iter().sum::<f64>() / count as f64),
+            median,
+            min: sorted.first().copied(),
+            max: sorted.last().copied(),
+            failing: sorted.iter().filter(|g| **g < PASSING_GRADE).count(),
+            ungraded: count_of(Grade::Ungraded),
+            incomplete: count_of(Grade::Incomplete),
+            excused: count_of(Grade::Excused),
+        }
+    }
+}
+
+/// Admin's names, grades and feedback as subscribers see them, every course of the current term on its own
+/// (see `AdminHandle::watch_gradebook()`).
+///  - Note: every roster has a grade for each of its names (ungraded if Admin has none yet), and no late
+///    penalties or rubric scores.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GradebookView {
+    pub rosters: BTreeMap<CourseKey, Roster>,
+}
+
+impl GradebookView {
+    fn of(courses: &Courses) -> Self {
+        let rosters = courses
+            .rosters()
+            .filter(|(key, _)| key.term == *courses.term())
+            .map(|(key, roster)| {
+                let mut grades = roster.grades.clone();
+                grades.resize(roster.names.len(), Grade::Ungraded);
+                let view = Roster {
+                    names: roster.names.clone(),
+                    grades,
+                    comments: roster.comments.clone(),
+                    ..Roster::default()
+                };
+                (key.clone(), view)
+            })
+            .collect();
+        GradebookView { rosters }
+    }
+
+    /// Every student with their grade, course by course; a student in two courses shows up twice.
+    pub fn students(&self) -> impl Iterator<Item = (&CourseKey, &str, Grade)> {
+        self.rosters.iter().flat_map(|(key, roster)| {
+            let names = roster.names.iter().map(String::as_str);
+            names
+                .zip(roster.grades.iter().copied())
+                .map(move |(name, grade)| (key, name, grade))
+        })
+    }
+}
+
+/// Everything Admin holds at one moment (see `AdminHandle::snapshot()`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AdminSnapshot {
+    pub courses: Courses,
+    pub proposals: ProposalBook,
+    pub proposal_ttl: Duration,
+    pub history: History,
+    pub timelines: Timelines,
+}
 
 // ##################################################### //
 // ################### ACTOR BACKEND ################### //
@@ -7,62 +126,633 @@ use tokio::sync::{mpsc, oneshot};
 struct Admin {
     receiver: mpsc::Receiver<AdminMessage>,
 
-    underlings: Vec<String>,
-    underling_grades: Vec<f64>,
+    courses: Courses, // Every course's names and grades, kept term by term; old terms are archived, never dropped
+    proposals: ProposalBook, // Grade changes (e.g. from Booster) waiting for an approver
+    proposal_ttl: Duration,
+    history: History, // Every change to `courses` that can still be undone (or redone)
+    names_entry: Option<NameDump>, // A name dump that has not had its grades yet
+    timelines: Timelines, // Every grade every student has had, and who gave it to them
+    watchers: watch::Sender<GradebookView>, // Every subscriber sees the latest gradebook through this
+    events: broadcast::Sender<AdminEvent>, // ...and every change to it, one event at a time, through this
 }
 
 #[derive(Debug)]
 enum AdminMessage {
     ProcessStudentDump {
+        scope: Scope,
         students: Vec<String>,
     },
     ProcessGradeDump {
-        grades: Vec<f64>,
+        scope: Scope,
+        grades: Vec<Grade>,
+        edited: BTreeSet<String>, // Students whose grade was changed in Brightspace, not sent by John
+    },
+    ProcessLateDump {
+        scope: Scope,
+        late: BTreeMap<String, LateWork>,
+    },
+    ProcessRubricDump {
+        scope: Scope,
+        rubrics: BTreeMap<String, RubricScore>,
+    },
+    ProcessCommentDump {
+        scope: Scope,
+        comments: BTreeMap<String, Vec<Comment>>,
     },
     CountNumberFailingStudents {
+        scope: Scope,
         reply_to: oneshot::Sender<usize>,
     },
     GetAllStudentGrades {
-        reply_to: oneshot::Sender<Vec<f64>>,
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<Grade>>,
     },
     GetAllStudentNames {
+        scope: Scope,
         reply_to: oneshot::Sender<Vec<String>>,
     },
+    GetFailingStudents {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<(String, Grade)>>,
+    },
+    GetLateWork {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<LateReport>>,
+    },
+    GetRubricScores {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<RubricReport>>,
+    },
+    GetFeedback {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<FeedbackReport>>,
+    },
+    ComputeStatistics {
+        scope: Scope,
+        reply_to: oneshot::Sender<GradeStatistics>,
+    },
+    GetCourses {
+        reply_to: oneshot::Sender<Courses>,
+    },
+    LoadCourses {
+        courses: Courses,
+    },
+    RollOver {
+        term: Term,
+        reply_to: oneshot::Sender<Result<Term, String>>,
+    },
+    ProposeGradeChanges {
+        proposed_by: String,
+        description: String,
+        changes: Vec<GradeChange>,
+        reply_to: oneshot::Sender<ProposalId>,
+    },
+    GetProposals {
+        reply_to: oneshot::Sender<ProposalBook>,
+    },
+    AcceptProposal {
+        id: ProposalId,
+        by: String,
+        reply_to: oneshot::Sender<Result<(), String>>,
+    },
+    RejectProposal {
+        id: ProposalId,
+        by: String,
+        reason: String,
+        reply_to: oneshot::Sender<Result<(), String>>,
+    },
+    LoadProposals {
+        book: ProposalBook,
+    },
+    SetProposalTtl {
+        ttl: Duration,
+    },
+    WatchGradebook {
+        reply_to: oneshot::Sender<watch::Receiver<GradebookView>>,
+    },
+    Subscribe {
+        reply_to: oneshot::Sender<(broadcast::Receiver<AdminEvent>, GradebookView)>,
+    },
+    Undo {
+        reply_to: oneshot::Sender<Result<Revision, String>>,
+    },
+    Redo {
+        reply_to: oneshot::Sender<Result<Revision, String>>,
+    },
+    CorrectRoster {
+        scope: Scope,
+        roster: Roster,
+        description: String,
+    },
+    GetHistory {
+        reply_to: oneshot::Sender<History>,
+    },
+    GetGradeHistory {
+        scope: Scope,
+        student: String,
+        reply_to: oneshot::Sender<GradeHistory>,
+    },
+    GetTimelines {
+        reply_to: oneshot::Sender<Timelines>,
+    },
+    LoadTimelines {
+        timelines: Timelines,
+    },
+    LoadHistory {
+        history: History,
+    },
+    SetHistoryLimit {
+        limit: usize,
+    },
+    Snapshot {
+        reply_to: oneshot::Sender<AdminSnapshot>,
+    },
+    Restore {
+        snapshot: AdminSnapshot,
+    },
+}
+
+/// A name dump waiting for the grades Brightspace sends right after it, so the two are one change.
+#[derive(Debug)]
+struct NameDump {
+    key: CourseKey,
+    entry: EntryId,  // Its entry in the history
+    before: Courses, // The gradebook before it, for the timelines
+}
+
+/// What a grade dump for `key` did, for the history: one student's change by name, or just how many.
+fn describe_grades(key: &CourseKey, before: Option<&Roster>, after: &Roster) -> String {
+    let old = |i: usize| {
+        before
+            .and_then(|roster| roster.grades.get(i))
+            .copied()
+            .unwrap_or(Grade::Ungraded)
+    };
+    let changed: Vec<usize> = (0..after.grades.len())
+        .filter(|i| old(*i) != after.grades[*i])
+        .collect();
+    match changed[..] {
+        [i] if i < after.names.len() => format!(
+            "{}'s grade {} -> {} in {}",
+            after.names[i],
+            old(i),
+            after.grades[i],
+            key
+        ),
+        _ => format!("{} grade(s) from Brightspace in {}", changed.len(), key),
+    }
 }
 
 impl Admin {
     fn new(receiver: mpsc::Receiver<AdminMessage>) -> Self {
         Admin {
-            receiver: receiver,
-            underlings: Vec::new(),
-            underling_grades: Vec::new(),
+            receiver,
+            courses: Courses::default(),
+            proposals: ProposalBook::default(),
+            proposal_ttl: proposal::DEFAULT_PROPOSAL_TTL,
+            history: History::default(),
+            names_entry: None,
+            timelines: Timelines::default(),
+            watchers: watch::Sender::new(GradebookView::default()),
+            events: broadcast::Sender::new(events::EVENT_BUFFER),
+        }
+    }
+
+    /// Tells every subscriber about the gradebook, if it changed since they last heard.
+    fn publish(&self) {
+        let mut changes = Vec::new();
+        let view = GradebookView::of(&self.courses);
+        self.watchers.send_if_modified(|current| {
+            if *current == view {
+                return false;
+            }
+            changes = events::diff(current, &view);
+            *current = view;
+            true
+        });
+
+        for event in changes {
+            trace!("[ACTOR] Admin publishing event: {}", event);
+            // Note: `send()` only fails when nobody is subscribed, and then there is nobody to tell
+            let _ = self.events.send(event);
+        }
+    }
+
+    fn expire_proposals(&mut self) {
+        let expired = self.proposals.expire();
+        if expired > 0 {
+            trace!("[ACTOR] Admin dropped {} expired proposal(s)", expired);
+        }
+    }
+
+    /// Applies every change in `proposal` or, if any of them no longer matches the gradebook, none of them.
+    ///  - Note: a change is about one course, a student in several courses keeps their grade in the others.
+    fn apply(&mut self, proposal: &Proposal) -> Result<(), String> {
+        for change in &proposal.changes {
+            if self.courses.is_archived(&change.course.term) {
+                return Err(format!("{} is archived", change.course.term));
+            }
+            let current = self.courses.roster(&change.course).and_then(|roster| {
+                let index = roster.names.iter().position(|n| *n == change.name)?;
+                Some(roster.grades.get(index).copied().unwrap_or_default())
+            });
+            match current {
+                None => {
+                    return Err(format!("{} is no longer in {}", change.name, change.course));
+                }
+                Some(current) if current != change.from => {
+                    return Err(format!(
+                        "{} has {} in {} now, the proposal expected {}",
+                        change.name, current, change.course, change.from
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Note: nothing has been changed until every change was checked, so either all apply or none do
+        for change in &proposal.changes {
+            let Ok(roster) = self.courses.roster_mut(&Scope::of(&change.course)) else {
+                continue;
+            };
+            roster.grades.resize(roster.names.len(), Grade::Ungraded);
+            if let Some(index) = roster.names.iter().position(|n| *n == change.name) {
+                roster.grades[index] = change.to;
+            }
         }
+        Ok(())
+    }
+
+    /// Everything one kind of detail behind the grades (e.g. `Roster::late`) holds in the courses `scope` covers,
+    /// with the course, the student's name as Admin has it, and their grade.
+    fn behind_grades<'a, T: Clone + 'a>(
+        &'a self,
+        scope: &'a Scope,
+        details: fn(&Roster) -> &BTreeMap<String, T>,
+    ) -> impl Iterator<Item = (CourseKey, String, Grade, T)> + 'a {
+        let current = self.courses.term();
+        self.courses
+            .rosters()
+            .filter(move |(key, _)| scope.includes(key, current))
+            .flat_map(move |(key, roster)| {
+                details(roster).iter().filter_map(move |(student, detail)| {
+                    // Note: Admin's names may have career IDs appended, the details are by name alone
+                    let index = roster.names.iter().position(|n| student_of(n) == student)?;
+                    let grade = roster.grades.get(index).copied().unwrap_or_default();
+                    Some((
+                        key.clone(),
+                        roster.names[index].clone(),
+                        grade,
+                        detail.clone(),
+                    ))
+                })
+            })
     }
 
     async fn handle_message(&mut self, msg: AdminMessage) {
-        println!(
+        trace!(
             "[Actor] Admin is running handle_message() with new AdminMessage: {:?}",
             msg
         );
+        self.expire_proposals();
+        // Note: only the message right after a name dump can be that dump's grades; anything else means the
+        //       grades are not coming, and the names go in the timelines on their own
+        let names_entry = match (&msg, self.names_entry.take()) {
+            (AdminMessage::ProcessGradeDump { .. }, dump) => dump,
+            (_, Some(dump)) => {
+                self.timelines
+                    .record(&dump.before, &self.courses, &GradeSource::John);
+                None
+            }
+            (_, None) => None,
+        };
+
         match msg {
-            AdminMessage::ProcessStudentDump { students } => self.underlings = students,
-            AdminMessage::ProcessGradeDump { grades } => self.underling_grades = grades,
-            AdminMessage::CountNumberFailingStudents { reply_to } => {
+            AdminMessage::ProcessStudentDump { scope, students } => {
+                let before = self.courses.clone();
+                let count = students.len();
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => roster.names = students,
+                    Err(term) => trace!("[ACTOR] Admin ignoring names for archived term {}", term),
+                }
+                let key = self.courses.key(&scope);
+                let description = format!("{} name(s) from Brightspace in {}", count, key);
+                self.history.record(description, &before, &self.courses);
+                let newest = self.history.newest().map(|entry| entry.id);
+                // Note: a dump that changed nothing was not recorded, so the newest entry would be an older change
+                if let Some(entry) = newest.filter(|_| before != self.courses) {
+                    self.names_entry = Some(NameDump { key, entry, before });
+                }
+            }
+            AdminMessage::ProcessGradeDump {
+                scope,
+                grades,
+                edited,
+            } => {
+                let key = self.courses.key(&scope);
+                let newest = self.history.newest().map(|entry| entry.id);
+                // Note: names and then grades is how Brightspace reports a course, so they are one change
+                let names = match names_entry {
+                    Some(dump) if dump.key == key && newest == Some(dump.entry) => Some(dump),
+                    Some(other) => {
+                        self.timelines
+                            .record(&other.before, &self.courses, &GradeSource::John);
+                        None
+                    }
+                    None => None,
+                };
+
+                let before = self.courses.clone();
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => roster.grades = grades,
+                    Err(term) => trace!("[ACTOR] Admin ignoring grades for archived term {}", term),
+                }
+                match (&names, self.courses.roster(&key)) {
+                    (Some(_), Some(after)) => {
+                        let description = format!(
+                            "{} name(s) and their grades from Brightspace in {}",
+                            after.names.len(),
+                            key
+                        );
+                        self.history.amend(description, &self.courses);
+                    }
+                    (None, Some(after)) => {
+                        let description = describe_grades(&key, before.roster(&key), after);
+                        self.history.record(description, &before, &self.courses);
+                    }
+                    (_, None) => {}
+                }
+                let before = names.map_or(before, |dump| dump.before);
+                self.timelines
+                    .record_sync(&before, &self.courses, &key, &edited);
+            }
+            // Note: not an entry in the history of its own, the penalties only explain the grades that came before
+            AdminMessage::ProcessLateDump { scope, late } => {
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => roster.late = late,
+                    Err(term) => trace!(
+                        "[ACTOR] Admin ignoring late work for archived term {}",
+                        term
+                    ),
+                }
+            }
+            // Note: nor are rubric scores, they explain the grades the same way
+            AdminMessage::ProcessRubricDump { scope, rubrics } => {
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => roster.rubrics = rubrics,
+                    Err(term) => trace!(
+                        "[ACTOR] Admin ignoring rubric scores for archived term {}",
+                        term
+                    ),
+                }
+            }
+            // Note: nor is feedback, but the portal shows it, so subscribers hear about it
+            AdminMessage::ProcessCommentDump { scope, comments } => {
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => roster.comments = comments,
+                    Err(term) => {
+                        trace!("[ACTOR] Admin ignoring feedback for archived term {}", term)
+                    }
+                }
+            }
+            AdminMessage::CountNumberFailingStudents { scope, reply_to } => {
                 let count_failed = self
-                    .underling_grades
+                    .courses
+                    .view(&scope)
+                    .grades
                     .iter()
-                    .filter(|grade| **grade < 60.0)
+                    .filter(|grade| grade.is_failing())
                     .count();
 
                 let _ = reply_to.send(count_failed);
             }
-            AdminMessage::GetAllStudentNames { reply_to } => {
-                let _ = reply_to.send(self.underlings.clone());
+            AdminMessage::GetAllStudentNames { scope, reply_to } => {
+                let _ = reply_to.send(self.courses.view(&scope).names);
             }
 
-            AdminMessage::GetAllStudentGrades { reply_to } => {
-                let _ = reply_to.send(self.underling_grades.clone());
+            AdminMessage::GetAllStudentGrades { scope, reply_to } => {
+                let _ = reply_to.send(self.courses.view(&scope).grades);
+            }
+            AdminMessage::GetFailingStudents { scope, reply_to } => {
+                let view = self.courses.view(&scope);
+                let failing = view
+                    .names
+                    .into_iter()
+                    .zip(view.grades)
+                    .filter(|(_, grade)| grade.is_failing())
+                    .collect();
+
+                let _ = reply_to.send(failing);
+            }
+            AdminMessage::GetLateWork { scope, reply_to } => {
+                let reports = self
+                    .behind_grades(&scope, |roster| &roster.late)
+                    .map(|(course, student, grade, work)| LateReport {
+                        course,
+                        student,
+                        grade,
+                        work,
+                    })
+                    .collect();
+                let _ = reply_to.send(reports);
+            }
+            AdminMessage::GetRubricScores { scope, reply_to } => {
+                let reports = self
+                    .behind_grades(&scope, |roster| &roster.rubrics)
+                    .map(|(course, student, grade, score)| RubricReport {
+                        course,
+                        student,
+                        grade,
+                        score,
+                    })
+                    .collect();
+                let _ = reply_to.send(reports);
+            }
+            AdminMessage::GetFeedback { scope, reply_to } => {
+                let reports = self
+                    .behind_grades(&scope, |roster| &roster.comments)
+                    .map(|(course, student, grade, comments)| FeedbackReport {
+                        course,
+                        student,
+                        grade,
+                        comments,
+                    })
+                    .collect();
+                let _ = reply_to.send(reports);
+            }
+            AdminMessage::ComputeStatistics { scope, reply_to } => {
+                let grades = self.courses.view(&scope).grades;
+                let _ = reply_to.send(GradeStatistics::of(&grades));
             }
+            AdminMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(self.courses.clone());
+            }
+            AdminMessage::LoadCourses { courses } => self.courses = courses,
+            AdminMessage::RollOver { term, reply_to } => {
+                let result = self.courses.roll_over(term);
+                if let Ok(previous) = &result {
+                    trace!(
+                        "[ACTOR] Admin archived term {} and started {}",
+                        previous,
+                        self.courses.term()
+                    );
+                    // Note: every change so far was to a term that can no longer change, so none can be undone
+                    self.history.clear();
+                }
+                let _ = reply_to.send(result);
+            }
+            AdminMessage::ProposeGradeChanges {
+                proposed_by,
+                description,
+                changes,
+                reply_to,
+            } => {
+                let id =
+                    self.proposals
+                        .submit(proposed_by, description, changes, self.proposal_ttl);
+                trace!("[ACTOR] Admin is holding proposal #{} for approval", id);
+                let _ = reply_to.send(id);
+            }
+            AdminMessage::GetProposals { reply_to } => {
+                let _ = reply_to.send(self.proposals.clone());
+            }
+            AdminMessage::AcceptProposal { id, by, reply_to } => {
+                // Note: the sweep only runs every few seconds, a proposal past its time must not slip in before it
+                self.expire_proposals();
+                let result = self.proposals.take(id).and_then(|proposal| {
+                    let before = self.courses.clone();
+                    match self.apply(&proposal) {
+                        Ok(()) => {
+                            trace!("[ACTOR] Admin applied proposal #{} accepted by {}", id, by);
+                            let description = format!(
+                                "proposal #{} ({}), accepted by {}",
+                                id, proposal.description, by
+                            );
+                            self.history.record(description, &before, &self.courses);
+                            let source = GradeSource::Proposal {
+                                id,
+                                proposed_by: proposal.proposed_by.clone(),
+                            };
+                            self.timelines.record(&before, &self.courses, &source);
+                            self.proposals.record(proposal, Outcome::Accepted { by });
+                            Ok(())
+                        }
+                        Err(reason) => {
+                            // Note: a stale proposal can never apply cleanly, so it is rejected rather than left pending
+                            let reason = format!("stale: {}", reason);
+                            self.proposals.record(
+                                proposal,
+                                Outcome::Rejected {
+                                    by: ACTOR.to_string(),
+                                    reason: reason.clone(),
+                                },
+                            );
+                            Err(reason)
+                        }
+                    }
+                });
+                let _ = reply_to.send(result);
+            }
+            AdminMessage::RejectProposal {
+                id,
+                by,
+                reason,
+                reply_to,
+            } => {
+                self.expire_proposals();
+                let result = self.proposals.take(id).map(|proposal| {
+                    trace!(
+                        "[ACTOR] Admin discarding proposal #{} rejected by {}",
+                        id, by
+                    );
+                    self.proposals
+                        .record(proposal, Outcome::Rejected { by, reason });
+                });
+                let _ = reply_to.send(result);
+            }
+            AdminMessage::LoadProposals { book } => self.proposals = book,
+            AdminMessage::SetProposalTtl { ttl } => self.proposal_ttl = ttl,
+            AdminMessage::Undo { reply_to } => {
+                let before = self.courses.clone();
+                let result = self.history.undo(&mut self.courses);
+                self.timelines
+                    .record(&before, &self.courses, &GradeSource::Undo);
+                if let Ok(revision) = &result {
+                    trace!("[ACTOR] Admin undid: {}", revision.description);
+                }
+                let _ = reply_to.send(result);
+            }
+            AdminMessage::Redo { reply_to } => {
+                let before = self.courses.clone();
+                let result = self.history.redo(&mut self.courses);
+                self.timelines
+                    .record(&before, &self.courses, &GradeSource::Redo);
+                if let Ok(revision) = &result {
+                    trace!("[ACTOR] Admin redid: {}", revision.description);
+                }
+                let _ = reply_to.send(result);
+            }
+            AdminMessage::CorrectRoster {
+                scope,
+                roster,
+                description,
+            } => {
+                let before = self.courses.clone();
+                match self.courses.roster_mut(&scope) {
+                    Ok(current) => current.correct(roster),
+                    Err(term) => trace!("[ACTOR] Admin cannot correct archived term {}", term),
+                }
+                self.history.record(description, &before, &self.courses);
+                self.timelines
+                    .record(&before, &self.courses, &GradeSource::Reconciliation);
+            }
+            AdminMessage::GetHistory { reply_to } => {
+                let _ = reply_to.send(self.history.clone());
+            }
+            AdminMessage::LoadHistory { history } => self.history = history,
+            AdminMessage::GetGradeHistory {
+                scope,
+                student,
+                reply_to,
+            } => {
+                let history = self
+                    .timelines
+                    .history(&student, &scope, self.courses.term());
+                let _ = reply_to.send(history);
+            }
+            AdminMessage::GetTimelines { reply_to } => {
+                let _ = reply_to.send(self.timelines.clone());
+            }
+            AdminMessage::LoadTimelines { timelines } => self.timelines = timelines,
+            AdminMessage::SetHistoryLimit { limit } => self.history.set_limit(limit),
+            AdminMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(AdminSnapshot {
+                    courses: self.courses.clone(),
+                    proposals: self.proposals.clone(),
+                    proposal_ttl: self.proposal_ttl,
+                    history: self.history.clone(),
+                    timelines: self.timelines.clone(),
+                });
+            }
+            AdminMessage::Restore { snapshot } => {
+                self.courses = snapshot.courses;
+                self.proposals = snapshot.proposals;
+                self.proposal_ttl = snapshot.proposal_ttl;
+                self.history = snapshot.history;
+                self.timelines = snapshot.timelines;
+            }
+            AdminMessage::WatchGradebook { reply_to } => {
+                let _ = reply_to.send(self.watchers.subscribe());
+            }
+            AdminMessage::Subscribe { reply_to } => {
+                // Note: nothing can change between these two, so the snapshot plus the events is the whole story
+                let snapshot = self.watchers.borrow().clone();
+                let _ = reply_to.send((self.events.subscribe(), snapshot));
+            }
+        }
+        // Note: names still waiting for their grades would look ungraded, so subscribers hear about both at once
+        if self.names_entry.is_none() {
+            self.publish();
         }
     }
 }
@@ -73,62 +763,912 @@ impl Admin {
 
 #[derive(Clone, Debug)]
 pub struct AdminHandle {
+    id: ActorId,
     sender: mpsc::Sender<AdminMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
+    scope: Scope,
 }
 
 async fn run_admin_actor(mut actor: Admin) {
-    println!("[run_admin_actor()]: is blocking until a AdminMessage is received...");
-    while let Some(msg) = actor.receiver.recv().await {
-        println!(
-            "\n[run_admin_actor()]: received a new AdminMessage and calling handle_message()..."
-        );
-        actor.handle_message(msg).await;
+    trace!("[run_admin_actor()]: is blocking until a AdminMessage is received...");
+    let mut sweep = tokio::time::interval(EXPIRY_SWEEP);
+    loop {
+        // Note: `select!` waits for whichever happens first, a new message or the next expiry sweep
+        tokio::select! {
+            msg = actor.receiver.recv() => {
+                let Some(msg) = msg else { break };
+                trace!(
+                    "\n[run_admin_actor()]: received a new AdminMessage and calling handle_message()..."
+                );
+                actor.handle_message(msg).await;
+            }
+            _ = sweep.tick() => actor.expire_proposals(),
+        }
+    }
+}
+
+// Note: a stepped Admin never sweeps on its own, proposals only expire once it is released
+#[cfg(test)]
+impl crate::harness::Steppable for Admin {
+    fn next_message(&mut self, wait: Duration) -> crate::harness::Step<'_> {
+        Box::pin(async move {
+            let msg = tokio::time::timeout(wait, self.receiver.recv())
+                .await
+                .ok()??;
+            let seen = format!("{:?}", msg);
+            self.handle_message(msg).await;
+            Some(seen)
+        })
+    }
+
+    fn run(self: Box<Self>) {
+        tokio::spawn(run_admin_actor(*self));
+    }
+}
+
+/// What a mock Admin answers (see `AdminHandle::mocked()`).
+///  - Note: the mock ignores scopes, every query is answered from `roster`; writes are only recorded.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AdminScript {
+    pub roster: crate::course::Roster,
+    pub proposals: ProposalBook,
+    /// The id every new proposal gets.
+    pub proposal_id: ProposalId,
+    /// If set, accepting, rejecting and rolling over are all refused with this reason.
+    pub refusal: Option<String>,
+}
+
+#[cfg(test)]
+impl AdminScript {
+    fn answer(&mut self, msg: AdminMessage) {
+        let refused = || self.refusal.clone().map_or(Ok(()), Err);
+        match msg {
+            AdminMessage::CountNumberFailingStudents { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.grades.iter().filter(|g| g.is_failing()).count());
+            }
+            AdminMessage::GetAllStudentGrades { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.grades.clone());
+            }
+            AdminMessage::GetAllStudentNames { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.names.clone());
+            }
+            AdminMessage::GetFailingStudents { reply_to, .. } => {
+                let roster = self
+                    .roster
+                    .names
+                    .iter()
+                    .cloned()
+                    .zip(self.roster.grades.clone());
+                let _ = reply_to.send(roster.filter(|(_, g)| g.is_failing()).collect());
+            }
+            AdminMessage::ComputeStatistics { reply_to, .. } => {
+                let _ = reply_to.send(GradeStatistics::of(&self.roster.grades));
+            }
+            AdminMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(crate::harness::courses_of(self.roster.clone()));
+            }
+            AdminMessage::RollOver { term, reply_to } => {
+                let _ = reply_to.send(refused().map(|()| term));
+            }
+            AdminMessage::ProposeGradeChanges { reply_to, .. } => {
+                let _ = reply_to.send(self.proposal_id);
+            }
+            AdminMessage::GetProposals { reply_to } => {
+                let _ = reply_to.send(self.proposals.clone());
+            }
+            AdminMessage::AcceptProposal { reply_to, .. }
+            | AdminMessage::RejectProposal { reply_to, .. } => {
+                let _ = reply_to.send(refused());
+            }
+            // Note: a mock has no gradebook to watch, so watching and subscribing fail as if Admin had stopped
+            AdminMessage::WatchGradebook { .. } | AdminMessage::Subscribe { .. } => {}
+            AdminMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(AdminSnapshot {
+                    courses: crate::harness::courses_of(self.roster.clone()),
+                    proposals: self.proposals.clone(),
+                    proposal_ttl: proposal::DEFAULT_PROPOSAL_TTL,
+                    history: History::default(),
+                    timelines: Timelines::default(),
+                });
+            }
+            AdminMessage::Undo { reply_to } | AdminMessage::Redo { reply_to } => {
+                let _ = reply_to.send(Err(self
+                    .refusal
+                    .clone()
+                    .unwrap_or_else(|| "the mock has no history".to_string())));
+            }
+            AdminMessage::GetHistory { reply_to } => {
+                let _ = reply_to.send(History::default());
+            }
+            AdminMessage::GetTimelines { reply_to } => {
+                let _ = reply_to.send(Timelines::default());
+            }
+            AdminMessage::GetGradeHistory {
+                student, reply_to, ..
+            } => {
+                let history =
+                    Timelines::default().history(&student, &Scope::default(), &Term::default());
+                let _ = reply_to.send(history);
+            }
+            AdminMessage::GetLateWork { reply_to, .. } => {
+                let _ = reply_to.send(Vec::new());
+            }
+            AdminMessage::GetRubricScores { reply_to, .. } => {
+                let _ = reply_to.send(Vec::new());
+            }
+            AdminMessage::GetFeedback { reply_to, .. } => {
+                let _ = reply_to.send(Vec::new());
+            }
+            AdminMessage::ProcessStudentDump { .. }
+            | AdminMessage::ProcessGradeDump { .. }
+            | AdminMessage::ProcessLateDump { .. }
+            | AdminMessage::ProcessRubricDump { .. }
+            | AdminMessage::ProcessCommentDump { .. }
+            | AdminMessage::Restore { .. }
+            | AdminMessage::LoadCourses { .. }
+            | AdminMessage::LoadProposals { .. }
+            | AdminMessage::SetProposalTtl { .. }
+            | AdminMessage::LoadHistory { .. }
+            | AdminMessage::CorrectRoster { .. }
+            | AdminMessage::LoadTimelines { .. }
+            | AdminMessage::SetHistoryLimit { .. } => {}
+        }
     }
 }
 
 impl AdminHandle {
     pub async fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(8);
+        AdminHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
         let actor = Admin::new(receiver);
         tokio::spawn(run_admin_actor(actor));
 
-        AdminHandle { sender: sender }
+        AdminHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        }
+    }
+
+    /// Same as `with_mailbox()`, but Admin only handles a message when the test steps it (see `harness::Stepper`).
+    #[cfg(test)]
+    pub(crate) fn stepped(capacity: usize) -> (Self, crate::harness::Stepper) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = AdminHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        (handle, crate::harness::Stepper::new(Admin::new(receiver)))
+    }
+
+    /// Same as `with_mailbox()`, but the handle talks to a mock Admin that answers from `script` (see `harness::Mock`).
+    #[cfg(test)]
+    pub(crate) fn mocked(
+        capacity: usize,
+        script: AdminScript,
+    ) -> (Self, crate::harness::Mock<AdminScript>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = AdminHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        let mock = crate::harness::Mock::spawn(receiver, script, AdminScript::answer);
+        (handle, mock)
+    }
+
+    /// Which Admin actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        AdminHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        AdminHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        AdminHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `course` only; without it, questions are about
+    /// every course at once and writes go to `DEFAULT_COURSE`.
+    pub fn in_course(&self, course: Course) -> Self {
+        AdminHandle {
+            scope: Scope {
+                course: Some(course),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `term` (current or archived) instead of the current term.
+    pub fn in_term(&self, term: Term) -> Self {
+        AdminHandle {
+            scope: Scope {
+                term: Some(term),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    pub async fn count_number_of_failing_students(&self) -> Result<usize, ActorError> {
+        self.authorize(Operation::ReadStatistics)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::CountNumberFailingStudents {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    pub async fn get_all_student_names(&self) -> Result<Vec<String>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetAllStudentNames {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
     }
 
-    pub async fn submit_student_names(&self, students: Vec<String>) {
-        let msg = AdminMessage::ProcessStudentDump { students };
-        let _ = self.sender.send(msg).await;
+    pub async fn get_all_student_grades(&self) -> Result<Vec<Grade>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetAllStudentGrades {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
     }
 
-    pub async fn submit_student_grades(&self, grades: Vec<f64>) {
-        let msg = AdminMessage::ProcessGradeDump { grades };
-        let _ = self.sender.send(msg).await;
+    /// Every student with a score below `PASSING_GRADE`, with their grade (ungraded, incomplete and
+    /// excused students are never failing).
+    pub async fn get_failing_students(&self) -> Result<Vec<(String, Grade)>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetFailingStudents {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
     }
 
-    pub async fn count_number_of_failing_students(&self) -> usize {
+    pub async fn get_statistics(&self) -> Result<GradeStatistics, ActorError> {
+        self.authorize(Operation::ReadStatistics)?;
+        let deadline = self.deadline();
         let (tx, rx) = oneshot::channel();
 
-        let msg = AdminMessage::CountNumberFailingStudents { reply_to: tx };
-        let _ = self.sender.send(msg).await;
+        let msg = AdminMessage::ComputeStatistics {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
 
-        rx.await.unwrap_or(0)
+        call::recv(ACTOR, rx, deadline).await
+    }
+    /// Who to record as having done something through this handle.
+    fn caller_name(&self) -> String {
+        self.caller
+            .as_ref()
+            .map(|caller| caller.to_string())
+            .unwrap_or_else(|| "the owner".to_string())
     }
 
-    pub async fn get_all_student_names(&self) -> Vec<String> {
+    /// Puts `changes` in front of Admin's approvers; nothing changes until someone calls `accept_proposal()`.
+    pub async fn propose_grade_changes(
+        &self,
+        proposed_by: String,
+        description: String,
+        changes: Vec<GradeChange>,
+    ) -> Result<ProposalId, ActorError> {
+        self.authorize(Operation::ProposeGradeChanges)?;
+        let deadline = self.deadline();
         let (tx, rx) = oneshot::channel();
 
-        let msg = AdminMessage::GetAllStudentNames { reply_to: tx };
-        let _ = self.sender.send(msg).await;
+        let msg = AdminMessage::ProposeGradeChanges {
+            proposed_by,
+            description,
+            changes,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
 
-        rx.await.unwrap_or_default()
+        call::recv(ACTOR, rx, deadline).await
     }
 
-    pub async fn get_all_student_grades(&self) -> Vec<f64> {
+    /// Every proposal Admin is still waiting on, plus every one already accepted, rejected or expired.
+    pub async fn get_proposals(&self) -> Result<ProposalBook, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
         let (tx, rx) = oneshot::channel();
 
-        let msg = AdminMessage::GetAllStudentGrades { reply_to: tx };
-        let _ = self.sender.send(msg).await;
+        let msg = AdminMessage::GetProposals { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Applies every change in proposal `id` at once.
+    ///  - Returns `ActorError::Refused` if the proposal is unknown, already decided, expired, or stale
+    ///    (a grade it changes is no longer what it was when it was proposed).
+    pub async fn accept_proposal(&self, id: ProposalId) -> Result<(), ActorError> {
+        self.authorize(Operation::DecideProposal)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::AcceptProposal {
+            id,
+            by: self.caller_name(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Throws proposal `id` away without changing any grade, recording `reason`.
+    pub async fn reject_proposal(&self, id: ProposalId, reason: String) -> Result<(), ActorError> {
+        self.authorize(Operation::DecideProposal)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::RejectProposal {
+            id,
+            by: self.caller_name(),
+            reason,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Replaces Admin's proposals with `book`, e.g. when loading a saved gradebook.
+    pub async fn load_proposals(&self, book: ProposalBook) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::LoadProposals { book };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Subscribes to Admin's gradebook: the receiver always holds the latest names and grades,
+    /// and `changed()` wakes up whenever they change.
+    pub async fn watch_gradebook(&self) -> Result<watch::Receiver<GradebookView>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::WatchGradebook { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Subscribes to every change in Admin's gradebook that passes `filter`, from now on.
+    pub async fn subscribe(&self, filter: EventFilter) -> Result<Subscription, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::Subscribe { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        let (receiver, gradebook) = call::recv(ACTOR, rx, deadline).await?;
+        Ok(Subscription::new(receiver, filter, gradebook))
+    }
+
+    /// Every roster Admin holds, in every course and term, and which terms are archived.
+    pub async fn get_courses(&self) -> Result<Courses, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetCourses { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Replaces every roster Admin holds with `courses`, e.g. when loading a saved gradebook.
+    pub async fn load_courses(&self, courses: Courses) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::LoadCourses { courses };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Archives the current term, so its grades can still be read but never changed, and starts `term`.
+    ///  - Returns the term that was archived, or `ActorError::Refused` if `term` is already current or archived.
+    pub async fn roll_over(&self, term: Term) -> Result<Term, ActorError> {
+        self.authorize(Operation::RollOverTerm)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::RollOver { term, reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// How long new proposals wait for a decision before they expire.
+    pub async fn set_proposal_ttl(&self, ttl: Duration) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = AdminMessage::SetProposalTtl { ttl };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Takes back the newest change to the gradebook (a dump, an accepted proposal, ...), returning the rosters
+    /// it put back so they can be pushed to Brightspace too (see `Revision`).
+    ///  - Returns `ActorError::Refused` if there is nothing left to undo.
+    pub async fn undo(&self) -> Result<Revision, ActorError> {
+        self.authorize(Operation::UndoRedo)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::Undo { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Makes the most recently undone change again, as long as nothing new has changed the gradebook since.
+    ///  - Returns `ActorError::Refused` if there is nothing to redo.
+    pub async fn redo(&self) -> Result<Revision, ActorError> {
+        self.authorize(Operation::UndoRedo)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::Redo { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Replaces the roster of the course this handle is scoped to with `roster`, as one change in the history
+    /// called `description` (see `reconcile::fix()`).
+    pub async fn correct_roster(
+        &self,
+        roster: Roster,
+        description: String,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::Reconcile)?;
+        let msg = AdminMessage::CorrectRoster {
+            scope: self.scope.clone(),
+            roster,
+            description,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Every change that can be undone, oldest first, and every one that can be redone.
+    pub async fn history(&self) -> Result<History, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetHistory { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every late penalty behind a grade in the courses this handle is about: the grade the student has now, and
+    /// what their work scored before the penalty.
+    pub async fn get_late_work(&self) -> Result<Vec<LateReport>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetLateWork {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every rubric score behind a grade in the courses this handle is about, criterion by criterion, e.g. to give
+    /// students feedback.
+    pub async fn get_rubric_scores(&self) -> Result<Vec<RubricReport>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetRubricScores {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// The feedback on every student's grade in the courses this handle is about, oldest comment first.
+    pub async fn get_feedback(&self) -> Result<Vec<FeedbackReport>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetFeedback {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every grade `student` (with or without their career ID) has had in the courses this handle is about,
+    /// oldest first, who gave it to them, and how their scores are trending.
+    pub async fn grade_history(&self, student: &str) -> Result<GradeHistory, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetGradeHistory {
+            scope: self.scope.clone(),
+            student: student.to_string(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every student's grade timeline, e.g. to save them.
+    pub async fn timelines(&self) -> Result<Timelines, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::GetTimelines { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Replaces every student's grade timeline with `timelines`, e.g. when loading a saved gradebook.
+    pub async fn load_timelines(&self, timelines: Timelines) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::LoadTimelines { timelines };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Replaces Admin's history with `history`, e.g. when loading a saved gradebook.
+    pub async fn load_history(&self, history: History) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::LoadHistory { history };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// How many changes Admin keeps for `undo()` (`history::DEFAULT_HISTORY_LIMIT` unless set).
+    pub async fn set_history_limit(&self, limit: usize) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = AdminMessage::SetHistoryLimit { limit };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Everything Admin holds right now: every course, every proposal how long proposals last, and its history.
+    pub async fn snapshot(&self) -> Result<AdminSnapshot, ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AdminMessage::Snapshot { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Puts Admin's gradebook, proposals and history back the way `snapshot` found them.
+    pub async fn restore(&self, snapshot: AdminSnapshot) -> Result<(), ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let msg = AdminMessage::Restore { snapshot };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+}
+
+/// A view of an Admin that can only be asked questions, never change the gradebook.
+///  - Get one with `admin_handle.read_only()` and hand it to anything that only needs to look.
+#[derive(Clone, Debug)]
+pub struct AdminReader {
+    admin: AdminHandle,
+}
+
+impl AdminHandle {
+    pub fn read_only(&self) -> AdminReader {
+        AdminReader {
+            admin: self.clone(),
+        }
+    }
+
+    /// Lets a Booster change this Admin's grades: the returned approval is the only way to hand an Admin
+    /// to `BoosterHandle::set_admin()`.
+    pub fn approve_booster(&self) -> Result<BoosterApproval, ActorError> {
+        self.authorize(Operation::ApproveBooster)?;
+        let approved_by = self.caller_name();
+        trace!(
+            "[ACCESS]: {} approved a Booster for {}",
+            approved_by, self.id
+        );
+
+        // Note: the approval carries an unscoped handle, since the whole point is to let Booster write
+        Ok(BoosterApproval {
+            admin: AdminHandle {
+                caller: None,
+                ..self.clone()
+            },
+            approved_by,
+        })
+    }
+}
+
+impl AdminReader {
+    pub fn id(&self) -> ActorId {
+        self.admin.id()
+    }
+
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        self.admin.acting_as(caller).read_only()
+    }
+
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        self.admin.with_timeout(timeout).read_only()
+    }
+
+    pub fn in_course(&self, course: Course) -> Self {
+        self.admin.in_course(course).read_only()
+    }
+
+    pub fn in_term(&self, term: Term) -> Self {
+        self.admin.in_term(term).read_only()
+    }
+
+    pub async fn count_number_of_failing_students(&self) -> Result<usize, ActorError> {
+        self.admin.count_number_of_failing_students().await
+    }
+
+    pub async fn get_all_student_names(&self) -> Result<Vec<String>, ActorError> {
+        self.admin.get_all_student_names().await
+    }
+
+    pub async fn get_all_student_grades(&self) -> Result<Vec<Grade>, ActorError> {
+        self.admin.get_all_student_grades().await
+    }
+
+    pub async fn get_failing_students(&self) -> Result<Vec<(String, Grade)>, ActorError> {
+        self.admin.get_failing_students().await
+    }
+
+    pub async fn get_statistics(&self) -> Result<GradeStatistics, ActorError> {
+        self.admin.get_statistics().await
+    }
+
+    pub async fn watch_gradebook(&self) -> Result<watch::Receiver<GradebookView>, ActorError> {
+        self.admin.watch_gradebook().await
+    }
+
+    pub async fn subscribe(&self, filter: EventFilter) -> Result<Subscription, ActorError> {
+        self.admin.subscribe(filter).await
+    }
+
+    pub async fn get_courses(&self) -> Result<Courses, ActorError> {
+        self.admin.get_courses().await
+    }
+}
+
+/// Proof that an Admin agreed to let a Booster change its grades (see `AdminHandle::approve_booster()`).
+#[derive(Clone, Debug)]
+pub struct BoosterApproval {
+    admin: AdminHandle,
+    approved_by: String,
+}
+
+impl BoosterApproval {
+    pub fn approved_by(&self) -> &str {
+        &self.approved_by
+    }
+
+    pub(crate) fn into_admin(self) -> AdminHandle {
+        self.admin
+    }
+}
+
+/// The only way names and grades get into an Admin: Brightspace passes on what John (and every other
+/// coordinator) recorded through one of these (see `AdminHandle::feed()` and `BrightspaceHandle::set_admin()`).
+///  - Note: nobody else holds one, so an `AdminHandle` (even a clone of the one that started Admin) can ask and
+///    decide, but never replace the gradebook.
+#[derive(Clone, Debug)]
+pub struct GradebookFeed {
+    admin: AdminHandle,
+}
+
+impl AdminHandle {
+    /// A feed for the Brightspace that passes John's grades on to this Admin.
+    ///  - Returns `ActorError::Denied` for every role (see `Operation::WriteGradebook`), only the code that
+    ///    started Admin (e.g. the topology's wiring) can make one.
+    pub fn feed(&self) -> Result<GradebookFeed, ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        Ok(GradebookFeed {
+            admin: self.clone(),
+        })
+    }
+}
+
+impl GradebookFeed {
+    pub fn id(&self) -> ActorId {
+        self.admin.id()
+    }
+
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        GradebookFeed {
+            admin: self.admin.with_deadline(deadline),
+        }
+    }
+
+    pub fn in_course(&self, course: Course) -> Self {
+        GradebookFeed {
+            admin: self.admin.in_course(course),
+        }
+    }
+
+    pub fn in_term(&self, term: Term) -> Self {
+        GradebookFeed {
+            admin: self.admin.in_term(term),
+        }
+    }
+
+    pub async fn submit_student_names(&self, students: Vec<String>) -> Result<(), ActorError> {
+        self.admin.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::ProcessStudentDump {
+            scope: self.admin.scope.clone(),
+            students,
+        };
+        call::send(ACTOR, &self.admin.sender, msg, self.admin.deadline()).await
+    }
+
+    pub async fn submit_student_grades(&self, grades: Vec<Grade>) -> Result<(), ActorError> {
+        self.submit_edited_grades(grades, BTreeSet::new()).await
+    }
+
+    /// Like `submit_student_grades()`, but the grades of the students in `edited` were changed in Brightspace
+    /// itself rather than sent by John, which is what Admin's timelines will say gave them.
+    pub async fn submit_edited_grades(
+        &self,
+        grades: Vec<Grade>,
+        edited: BTreeSet<String>,
+    ) -> Result<(), ActorError> {
+        self.admin.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::ProcessGradeDump {
+            scope: self.admin.scope.clone(),
+            grades,
+            edited,
+        };
+        call::send(ACTOR, &self.admin.sender, msg, self.admin.deadline()).await
+    }
+
+    /// Tells Admin which grades in this feed's course are marked down for late work, and what they were before.
+    pub async fn submit_late_work(
+        &self,
+        late: BTreeMap<String, LateWork>,
+    ) -> Result<(), ActorError> {
+        self.admin.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::ProcessLateDump {
+            scope: self.admin.scope.clone(),
+            late,
+        };
+        call::send(ACTOR, &self.admin.sender, msg, self.admin.deadline()).await
+    }
+
+    /// Tells Admin which grades in this feed's course came from a rubric, and how each criterion was scored.
+    pub async fn submit_rubric_scores(
+        &self,
+        rubrics: BTreeMap<String, RubricScore>,
+    ) -> Result<(), ActorError> {
+        self.admin.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::ProcessRubricDump {
+            scope: self.admin.scope.clone(),
+            rubrics,
+        };
+        call::send(ACTOR, &self.admin.sender, msg, self.admin.deadline()).await
+    }
 
-        rx.await.unwrap_or_default()
+    /// Tells Admin what feedback the students in this feed's course have on their grades.
+    pub async fn submit_comments(
+        &self,
+        comments: BTreeMap<String, Vec<Comment>>,
+    ) -> Result<(), ActorError> {
+        self.admin.authorize(Operation::WriteGradebook)?;
+        let msg = AdminMessage::ProcessCommentDump {
+            scope: self.admin.scope.clone(),
+            comments,
+        };
+        call::send(ACTOR, &self.admin.sender, msg, self.admin.deadline()).await
     }
 }
@@ -0,0 +1,701 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation};
+use crate::admin::{AdminReader, PASSING_GRADE};
+use crate::call::{self, ActorError};
+use crate::course::CourseKey;
+use crate::events::{self, AdminEvent, EventFilter, EventKind, Notice, Subscription};
+use crate::grade::Grade;
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
+
+const ACTOR: &str = "Alerter";
+
+/// How long a webhook sink waits for its receiver before giving up on an alert.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How many alerts the Alerter remembers, and how many can wait for slow notifiers; older ones are dropped.
+pub(crate) const MAX_ALERTS: usize = 1000;
+
+/// When the Alerter flags a student, e.g. from the `[alerts]` table of a topology file.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AlertRules {
+    /// A student is flagged when their grade falls below any of these.
+    pub thresholds: Vec<f64>,
+    /// A student is flagged when their grade drops by at least this many points at once.
+    pub sharp_drop: f64,
+}
+
+impl Default for AlertRules {
+    fn default() -> Self {
+        AlertRules {
+            thresholds: vec![PASSING_GRADE, 70.0],
+            sharp_drop: 15.0,
+        }
+    }
+}
+
+/// Why a student was flagged.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum AlertReason {
+    /// Nobody has graded the student (yet).
+    NoGrade,
+    /// Graded as incomplete: the student still owes work.
+    Incomplete,
+    /// Fell below `threshold` (the lowest one they are under).
+    Below { threshold: f64 },
+    /// Dropped from `from` in a single change.
+    SharpDrop { from: f64 },
+}
+
+impl fmt::Display for AlertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertReason::NoGrade => write!(f, "no grade yet"),
+            AlertReason::Incomplete => write!(f, "incomplete"),
+            AlertReason::Below { threshold } => write!(f, "below {}", threshold),
+            AlertReason::SharpDrop { from } => write!(f, "dropped sharply from {}", from),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Alert {
+    pub course: CourseKey,
+    pub student: String,
+    pub grade: Grade,
+    pub reason: AlertReason,
+    pub raised_at: SystemTime,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} has {} in {} ({})",
+            self.student, self.grade, self.course, self.reason
+        )
+    }
+}
+
+impl AlertRules {
+    /// The threshold `grade` is furthest under, if any.
+    fn lowest_crossed(&self, grade: f64) -> Option<f64> {
+        self.thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| grade < *threshold)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Why a student going `from` one grade `to` another should be flagged, if at all.
+    fn check(&self, from: Grade, to: Grade) -> Vec<AlertReason> {
+        let score = match to {
+            Grade::Ungraded if from != Grade::Ungraded => return vec![AlertReason::NoGrade],
+            Grade::Incomplete if from != Grade::Incomplete => return vec![AlertReason::Incomplete],
+            Grade::Scored(score) => score,
+            _ => return Vec::new(),
+        };
+
+        let mut reasons = Vec::new();
+        if let Some(threshold) = self.lowest_crossed(score) {
+            // Note: only flag crossing a threshold, not every change that stays under it
+            let was_above = from.score().is_none_or(|from| from >= threshold);
+            if was_above {
+                reasons.push(AlertReason::Below { threshold });
+            }
+        }
+        if let Some(from) = from.score()
+            && from - score >= self.sharp_drop
+        {
+            reasons.push(AlertReason::SharpDrop { from });
+        }
+        reasons
+    }
+
+    /// Why a student arriving in the gradebook with `grade` should be flagged (arriving ungraded counts).
+    fn check_new(&self, grade: Grade) -> Vec<AlertReason> {
+        match grade {
+            Grade::Ungraded => vec![AlertReason::NoGrade],
+            grade => self.check(Grade::Ungraded, grade),
+        }
+    }
+
+    /// Why a student with `grade` is at risk right now, if they are.
+    fn standing(&self, grade: Grade) -> Option<AlertReason> {
+        match grade {
+            Grade::Ungraded => Some(AlertReason::NoGrade),
+            Grade::Incomplete => Some(AlertReason::Incomplete),
+            Grade::Excused => None,
+            Grade::Scored(score) => Some(AlertReason::Below {
+                threshold: self.lowest_crossed(score)?,
+            }),
+        }
+    }
+}
+
+// ###################################################### //
+// ##################### NOTIFIERS ###################### //
+// ###################################################### //
+
+/// What `Notifier::notify()` hands back: a future, boxed so any notifier fits in a `Box<dyn Notifier>`.
+pub type Notifying<'a> = Pin<Box<dyn Future<Output = std::io::Result<()>> + Send + 'a>>;
+
+/// Somewhere alerts go. Implement this to send them anywhere else.
+pub trait Notifier: Send {
+    /// Short description for logs, e.g. `file alerts.jsonl`.
+    fn describe(&self) -> String;
+
+    fn notify<'a>(&'a mut self, alert: &'a Alert) -> Notifying<'a>;
+}
+
+impl fmt::Debug for dyn Notifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Notifier({})", self.describe())
+    }
+}
+
+/// Prints every alert on stderr.
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn describe(&self) -> String {
+        "log".to_string()
+    }
+
+    fn notify<'a>(&'a mut self, alert: &'a Alert) -> Notifying<'a> {
+        Box::pin(async move {
+            eprintln!("[ALERT]: {}", alert);
+            Ok(())
+        })
+    }
+}
+
+/// Appends every alert to a file as one line of JSON.
+#[derive(Debug)]
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileNotifier { path: path.into() }
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn describe(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    fn notify<'a>(&'a mut self, alert: &'a Alert) -> Notifying<'a> {
+        Box::pin(async move {
+            let mut line = serde_json::to_string(alert)?;
+            line.push('\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            // Note: a tokio file writes in the background, so the alert is only on disk once this returns
+            file.flush().await
+        })
+    }
+}
+
+/// A plain `http://host:port/path` URL, checked when it is parsed.
+///  - Note: only plain HTTP is supported, webhooks are meant for a receiver on the same machine or network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookUrl {
+    host: String,
+    path: String,
+}
+
+impl FromStr for WebhookUrl {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("webhook URL `{}` must start with http://", url))?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(format!("webhook URL `{}` has no host", url));
+        }
+        Ok(WebhookUrl {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}{}", self.host, self.path)
+    }
+}
+
+/// POSTs every alert as JSON to a `WebhookUrl`, like a webhook would.
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    url: WebhookUrl,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: WebhookUrl) -> Self {
+        WebhookNotifier { url }
+    }
+
+    async fn post(&self, body: String) -> std::io::Result<()> {
+        let WebhookUrl { host, path } = &self.url;
+        let mut stream = tokio::net::TcpStream::connect(host).await?;
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            host,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let status_line = String::from_utf8_lossy(&response);
+        let status = status_line.split_whitespace().nth(1).unwrap_or("");
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(std::io::Error::other(format!(
+                "webhook answered `{}`",
+                status_line.lines().next().unwrap_or("")
+            )))
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn describe(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn notify<'a>(&'a mut self, alert: &'a Alert) -> Notifying<'a> {
+        Box::pin(async move {
+            let body = serde_json::to_string(alert)?;
+            tokio::time::timeout(WEBHOOK_TIMEOUT, self.post(body))
+                .await
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))?
+        })
+    }
+}
+
+/// A notifier named in a topology file: `log`, `file:<path>` or `webhook:<http url>`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum NotifierSpec {
+    Log,
+    File(PathBuf),
+    Webhook(WebhookUrl),
+}
+
+impl NotifierSpec {
+    pub fn open(&self) -> Box<dyn Notifier> {
+        match self {
+            NotifierSpec::Log => Box::new(LogNotifier),
+            NotifierSpec::File(path) => Box::new(FileNotifier::new(path.clone())),
+            NotifierSpec::Webhook(url) => Box::new(WebhookNotifier::new(url.clone())),
+        }
+    }
+}
+
+impl FromStr for NotifierSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "log" => Ok(NotifierSpec::Log),
+            Some(("file", path)) if !path.is_empty() => Ok(NotifierSpec::File(path.into())),
+            Some(("webhook", url)) => Ok(NotifierSpec::Webhook(url.parse()?)),
+            _ => Err(format!(
+                "unknown notifier `{}`, expected log, file:<path> or webhook:http://<host:port>/<path>",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for NotifierSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// ##################################################### //
+// ################### ACTOR BACKEND ################### //
+// ##################################################### //
+
+struct Alerter {
+    receiver: mpsc::Receiver<AlerterMessage>,
+
+    rules: AlertRules,
+    outbox: mpsc::Sender<Outgoing>, // To the task that runs the notifiers (see `run_notifiers()`)
+    subscription: Option<Subscription>,
+    grades: BTreeMap<(CourseKey, String), Grade>, // Latest grade of every student in every course, keyed without career IDs
+    raised: VecDeque<Alert>,                      // The last `MAX_ALERTS` alerts, oldest first
+}
+
+/// What the Alerter hands the task that runs its notifiers, in the order it happened.
+enum Outgoing {
+    Alert(Alert),
+    AddNotifier(Box<dyn Notifier>),
+    /// Answered once every alert before it was sent.
+    Flush(oneshot::Sender<()>),
+}
+
+#[derive(Debug)]
+enum AlerterMessage {
+    WatchAdmin {
+        subscription: Subscription,
+    },
+    SetRules {
+        rules: AlertRules,
+    },
+    AddNotifier {
+        notifier: Box<dyn Notifier>,
+    },
+    GetAlerts {
+        reply_to: oneshot::Sender<Vec<Alert>>,
+    },
+    Flush {
+        reply_to: oneshot::Sender<()>,
+    },
+    GetAtRisk {
+        reply_to: oneshot::Sender<Vec<(CourseKey, String, Grade, AlertReason)>>,
+    },
+}
+
+impl Alerter {
+    fn new(receiver: mpsc::Receiver<AlerterMessage>) -> Self {
+        let (outbox, outgoing) = mpsc::channel(MAX_ALERTS);
+        tokio::spawn(run_notifiers(outgoing));
+        Alerter {
+            receiver,
+            rules: AlertRules::default(),
+            outbox,
+            subscription: None,
+            grades: BTreeMap::new(),
+            raised: VecDeque::new(),
+        }
+    }
+
+    fn handle_notice(&mut self, notice: Notice) {
+        match notice {
+            Notice::Event(AdminEvent::StudentAdded {
+                course,
+                name,
+                grade,
+            }) => {
+                let reasons = self.rules.check_new(grade);
+                self.update(course, name, grade, reasons);
+            }
+            Notice::Event(AdminEvent::GradeChanged {
+                course,
+                name,
+                from,
+                to,
+            }) => {
+                let reasons = self.rules.check(from, to);
+                self.update(course, name, to, reasons);
+            }
+            Notice::Event(AdminEvent::StudentRemoved { course, name }) => {
+                let student = events::student_of(&name).to_string();
+                self.grades.remove(&(course, student));
+            }
+            Notice::Event(_) => {}
+            Notice::Lagged { missed } => eprintln!(
+                "[ALERT]: Alerter fell behind Admin and missed {} change(s), some alerts may be missing",
+                missed
+            ),
+        }
+    }
+
+    fn update(&mut self, course: CourseKey, name: String, grade: Grade, reasons: Vec<AlertReason>) {
+        let student = events::student_of(&name).to_string();
+        self.grades.insert((course.clone(), student), grade);
+        for reason in reasons {
+            let alert = Alert {
+                course: course.clone(),
+                student: name.clone(),
+                grade,
+                reason,
+                raised_at: SystemTime::now(),
+            };
+            self.raise(alert);
+        }
+    }
+
+    fn raise(&mut self, alert: Alert) {
+        trace!("[ACTOR] Alerter raising: {}", alert);
+        // Note: a slow notifier must not hold up the next event, so an alert it cannot take in time is dropped
+        if let Err(e) = self.outbox.try_send(Outgoing::Alert(alert.clone())) {
+            eprintln!(
+                "[ALERT]: notifiers are behind, not sending {}: {}",
+                alert, e
+            );
+        }
+        if self.raised.len() == MAX_ALERTS {
+            self.raised.pop_front();
+        }
+        self.raised.push_back(alert);
+    }
+
+    /// Handles every event that is already waiting, so answers reflect everything Admin has published.
+    fn catch_up(&mut self) {
+        while let Some(notice) = self.subscription.as_mut().and_then(|s| s.try_recv()) {
+            self.handle_notice(notice);
+        }
+    }
+
+    async fn handle_message(&mut self, msg: AlerterMessage) {
+        trace!(
+            "[Actor] Alerter is running handle_message() with new AlerterMessage: {:?}",
+            msg
+        );
+        match msg {
+            AlerterMessage::WatchAdmin { subscription } => {
+                let gradebook = subscription.gradebook();
+                // Note: students already in the gradebook are the baseline, only changes from here on raise alerts
+                self.grades = gradebook
+                    .students()
+                    .map(|(course, name, grade)| {
+                        let student = events::student_of(name).to_string();
+                        ((course.clone(), student), grade)
+                    })
+                    .collect();
+                self.subscription = Some(subscription);
+            }
+            AlerterMessage::SetRules { rules } => self.rules = rules,
+            AlerterMessage::AddNotifier { notifier } => {
+                let _ = self.outbox.send(Outgoing::AddNotifier(notifier)).await;
+            }
+            AlerterMessage::Flush { reply_to } => {
+                self.catch_up();
+                // Note: the notifiers answer once they sent everything raised so far, the Alerter moves on
+                let _ = self.outbox.send(Outgoing::Flush(reply_to)).await;
+            }
+            AlerterMessage::GetAlerts { reply_to } => {
+                self.catch_up();
+                let _ = reply_to.send(self.raised.iter().cloned().collect());
+            }
+            AlerterMessage::GetAtRisk { reply_to } => {
+                self.catch_up();
+                let at_risk = self
+                    .grades
+                    .iter()
+                    .filter_map(|((course, name), grade)| {
+                        let reason = self.rules.standing(*grade)?;
+                        Some((course.clone(), name.clone(), *grade, reason))
+                    })
+                    .collect();
+                let _ = reply_to.send(at_risk);
+            }
+        }
+    }
+}
+
+/// Sends every alert to every notifier, off the Alerter's own task so a slow one only delays other alerts.
+async fn run_notifiers(mut outgoing: mpsc::Receiver<Outgoing>) {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    while let Some(next) = outgoing.recv().await {
+        match next {
+            Outgoing::Alert(alert) => {
+                for notifier in &mut notifiers {
+                    if let Err(e) = notifier.notify(&alert).await {
+                        eprintln!("[ALERT]: could not notify {}: {}", notifier.describe(), e);
+                    }
+                }
+            }
+            Outgoing::AddNotifier(notifier) => notifiers.push(notifier),
+            Outgoing::Flush(reply_to) => {
+                let _ = reply_to.send(());
+            }
+        }
+    }
+}
+
+/// Waits for Admin's next event, or forever if this Alerter is not watching an Admin (any more).
+async fn next_notice(subscription: &mut Option<Subscription>) -> Option<Notice> {
+    subscription.as_mut()?.recv().await
+}
+
+async fn run_alerter_actor(mut actor: Alerter) {
+    loop {
+        tokio::select! {
+            msg = actor.receiver.recv() => {
+                let Some(msg) = msg else { break };
+                actor.handle_message(msg).await;
+            }
+            Some(notice) = next_notice(&mut actor.subscription) => actor.handle_notice(notice),
+        }
+    }
+}
+
+// ###################################################### //
+// ################### ACTOR FRONTEND ################### //
+// ###################################################### //
+
+#[derive(Clone, Debug)]
+pub struct AlerterHandle {
+    id: ActorId,
+    sender: mpsc::Sender<AlerterMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
+}
+
+impl AlerterHandle {
+    pub async fn new() -> Self {
+        AlerterHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let actor = Alerter::new(receiver);
+        tokio::spawn(run_alerter_actor(actor));
+
+        AlerterHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+        }
+    }
+
+    /// Which Alerter actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        AlerterHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        AlerterHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        AlerterHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    /// Starts watching `admin`'s grade changes; students already in its gradebook raise no alerts.
+    pub async fn watch_admin(&self, admin: &AdminReader) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let link = wiring::connect(self.id, admin.id())?;
+        let filter = EventFilter::default().kinds([
+            EventKind::StudentAdded,
+            EventKind::GradeChanged,
+            EventKind::StudentRemoved,
+        ]);
+        let subscription = admin.subscribe(filter).await?;
+        let msg = AlerterMessage::WatchAdmin { subscription };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    pub async fn set_rules(&self, rules: AlertRules) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = AlerterMessage::SetRules { rules };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Sends every alert from now on to `notifier` as well.
+    pub async fn add_notifier(&self, notifier: Box<dyn Notifier>) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = AlerterMessage::AddNotifier { notifier };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Waits until every change Admin has published so far was checked and every alert it raised was sent.
+    pub async fn flush(&self) -> Result<(), ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AlerterMessage::Flush { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// The last `MAX_ALERTS` (1000) alerts raised since this Alerter started watching, oldest first.
+    pub async fn get_alerts(&self) -> Result<Vec<Alert>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AlerterMessage::GetAlerts { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every student who is ungraded, incomplete or under a threshold right now, course by course, with their
+    /// grade and why.
+    pub async fn get_at_risk(
+        &self,
+    ) -> Result<Vec<(CourseKey, String, Grade, AlertReason)>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AlerterMessage::GetAtRisk { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+}
@@ -0,0 +1,584 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation};
+use crate::call::{self, ActorError};
+use crate::course::Course;
+use crate::events::student_of;
+use crate::grade::Grade;
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
+use crate::*;
+
+const ACTOR: &str = "Attendance";
+
+/// How a student turned up to one lab meeting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttendanceStatus {
+    Present,
+    Late,
+    Absent,
+    /// Excused meetings do not count towards participation at all.
+    Excused,
+}
+
+impl fmt::Display for AttendanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttendanceStatus::Present => write!(f, "present"),
+            AttendanceStatus::Late => write!(f, "late"),
+            AttendanceStatus::Absent => write!(f, "absent"),
+            AttendanceStatus::Excused => write!(f, "excused"),
+        }
+    }
+}
+
+impl FromStr for AttendanceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "present" => Ok(AttendanceStatus::Present),
+            "late" => Ok(AttendanceStatus::Late),
+            "absent" => Ok(AttendanceStatus::Absent),
+            "excused" => Ok(AttendanceStatus::Excused),
+            _ => Err(format!(
+                "unknown attendance `{}`, expected present, late, absent or excused",
+                s
+            )),
+        }
+    }
+}
+
+/// One student's attendance at a meeting, written `NAME=STATUS` on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mark {
+    pub name: String,
+    pub status: AttendanceStatus,
+}
+
+impl Mark {
+    pub fn new(name: impl Into<String>, status: AttendanceStatus) -> Self {
+        Mark {
+            name: name.into(),
+            status,
+        }
+    }
+}
+
+/// Parses `NAME=STATUS`, e.g. `Aarya Patel=late`.
+impl FromStr for Mark {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('=') {
+            Some((name, status)) if !name.trim().is_empty() => {
+                Ok(Mark::new(name.trim(), status.parse()?))
+            }
+            _ => Err(format!("`{}` is not a mark, expected NAME=STATUS", s)),
+        }
+    }
+}
+
+/// The `[attendance]` table: how much of a meeting's credit each status earns, and the assessment the
+/// participation score is recorded under in John.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticipationPolicy {
+    pub assessment: String,
+    /// Credit for a meeting, in percent.
+    pub present: f64,
+    pub late: f64,
+    pub absent: f64,
+}
+
+impl Default for ParticipationPolicy {
+    fn default() -> Self {
+        ParticipationPolicy {
+            assessment: "participation".to_string(),
+            present: 100.0,
+            late: 50.0,
+            absent: 0.0,
+        }
+    }
+}
+
+impl ParticipationPolicy {
+    /// The first problem with the policy, e.g. a credit above 100 percent.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.assessment.trim().is_empty() {
+            return Err("`assessment` needs a name, e.g. participation".to_string());
+        }
+        let credits = [
+            ("present", self.present),
+            ("late", self.late),
+            ("absent", self.absent),
+        ];
+        for (field, credit) in credits {
+            if !(0.0..=100.0).contains(&credit) {
+                return Err(format!("`{}` must be a percentage from 0 to 100", field));
+            }
+        }
+        Ok(())
+    }
+
+    /// What a meeting marked `status` is worth, or `None` if it does not count.
+    fn credit(&self, status: AttendanceStatus) -> Option<f64> {
+        match status {
+            AttendanceStatus::Present => Some(self.present),
+            AttendanceStatus::Late => Some(self.late),
+            AttendanceStatus::Absent => Some(self.absent),
+            AttendanceStatus::Excused => None,
+        }
+    }
+}
+
+/// One lab meeting, and how every student marked at it turned up, by name (without any career ID).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Meeting {
+    pub held_at: SystemTime,
+    pub marks: BTreeMap<String, AttendanceStatus>,
+}
+
+/// One student's attendance in one course, and the participation score it adds up to.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Participation {
+    pub course: Course,
+    pub student: String,
+    pub present: usize,
+    pub late: usize,
+    pub absent: usize,
+    pub excused: usize,
+    /// The average credit of every meeting that counts; excused if every meeting the student was marked at was.
+    pub grade: Grade,
+    /// The last meeting the student was marked at, which John takes as the time the work was handed in.
+    pub last_meeting: SystemTime,
+}
+
+// ##################################################### //
+// ################### ACTOR BACKEND ################### //
+// ##################################################### //
+
+struct Attendance {
+    id: ActorId,
+    receiver: mpsc::Receiver<AttendanceMessage>,
+
+    meetings: BTreeMap<Course, Vec<Meeting>>, // Every meeting of the current term, oldest first, by course
+    policy: ParticipationPolicy,
+    john: Option<JohnHandle>, // The coordinator participation scores are submitted to
+}
+
+#[derive(Debug)]
+enum AttendanceMessage {
+    RecordMeeting {
+        course: Course,
+        held_at: SystemTime,
+        marks: Vec<Mark>,
+        reply_to: oneshot::Sender<usize>,
+    },
+    MarkStudent {
+        course: Course,
+        meeting: usize,
+        mark: Mark,
+        reply_to: oneshot::Sender<Result<(), String>>,
+    },
+    GetParticipation {
+        course: Option<Course>,
+        reply_to: oneshot::Sender<Vec<Participation>>,
+    },
+    SubmitParticipation {
+        course: Option<Course>,
+        deadline: Instant,
+        reply_to: oneshot::Sender<Result<Vec<Participation>, ActorError>>,
+    },
+    SetPolicy {
+        policy: ParticipationPolicy,
+    },
+    SetJohn {
+        john_handle: JohnHandle,
+    },
+    GetMeetings {
+        reply_to: oneshot::Sender<BTreeMap<Course, Vec<Meeting>>>,
+    },
+    LoadMeetings {
+        meetings: BTreeMap<Course, Vec<Meeting>>,
+    },
+    RollOver,
+}
+
+impl Attendance {
+    fn new(id: ActorId, receiver: mpsc::Receiver<AttendanceMessage>) -> Self {
+        Attendance {
+            id,
+            receiver,
+            meetings: BTreeMap::new(),
+            policy: ParticipationPolicy::default(),
+            john: None,
+        }
+    }
+
+    /// Every marked student's participation in `course` (or in every course), course by course.
+    fn participation(&self, course: Option<&Course>) -> Vec<Participation> {
+        let mut scores = Vec::new();
+        let courses = self.meetings.iter();
+        for (key, meetings) in courses.filter(|(key, _)| course.is_none_or(|c| c == *key)) {
+            let mut students: BTreeMap<&str, Participation> = BTreeMap::new();
+            let mut credits: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+            for meeting in meetings {
+                for (student, status) in &meeting.marks {
+                    let p = students.entry(student).or_insert_with(|| Participation {
+                        course: key.clone(),
+                        student: student.clone(),
+                        present: 0,
+                        late: 0,
+                        absent: 0,
+                        excused: 0,
+                        grade: Grade::Excused,
+                        last_meeting: meeting.held_at,
+                    });
+                    match status {
+                        AttendanceStatus::Present => p.present += 1,
+                        AttendanceStatus::Late => p.late += 1,
+                        AttendanceStatus::Absent => p.absent += 1,
+                        AttendanceStatus::Excused => p.excused += 1,
+                    }
+                    p.last_meeting = p.last_meeting.max(meeting.held_at);
+                    if let Some(credit) = self.policy.credit(*status) {
+                        credits.entry(student).or_default().push(credit);
+                    }
+                }
+            }
+            for (student, mut p) in students {
+                if let Some(credits) = credits.get(student) {
+                    p.grade = Grade::Scored(credits.iter().sum::<f64>() / credits.len() as f64);
+                }
+                scores.push(p);
+            }
+        }
+        scores
+    }
+
+    async fn handle_message(&mut self, msg: AttendanceMessage) {
+        trace!(
+            "[Actor] Attendance is running handle_message() with new AttendanceMessage: {:?}",
+            msg
+        );
+        match msg {
+            AttendanceMessage::RecordMeeting {
+                course,
+                held_at,
+                marks,
+                reply_to,
+            } => {
+                let meetings = self.meetings.entry(course).or_default();
+                meetings.push(Meeting {
+                    held_at,
+                    marks: marks
+                        .into_iter()
+                        .map(|m| (student_of(&m.name).to_string(), m.status))
+                        .collect(),
+                });
+                // Note: meetings are numbered from 1 in each course, the way coordinators count them
+                let _ = reply_to.send(meetings.len());
+            }
+            AttendanceMessage::MarkStudent {
+                course,
+                meeting,
+                mark,
+                reply_to,
+            } => {
+                let result = match self
+                    .meetings
+                    .get_mut(&course)
+                    .and_then(|meetings| meetings.get_mut(meeting.checked_sub(1)?))
+                {
+                    Some(m) => {
+                        m.marks
+                            .insert(student_of(&mark.name).to_string(), mark.status);
+                        Ok(())
+                    }
+                    None => Err(format!("{} has no meeting #{}", course, meeting)),
+                };
+                let _ = reply_to.send(result);
+            }
+            AttendanceMessage::GetParticipation { course, reply_to } => {
+                let _ = reply_to.send(self.participation(course.as_ref()));
+            }
+            AttendanceMessage::SubmitParticipation {
+                course,
+                deadline,
+                mut reply_to,
+            } => {
+                let result = if let Some(john) = &self.john {
+                    trace!("[ACTOR]: Attendance submitting participation scores to John");
+
+                    let john = john.with_deadline(call::downstream(deadline));
+                    let _waiting = wiring::waiting(self.id, john.id());
+                    let scores = self.participation(course.as_ref());
+                    let assessment = &self.policy.assessment;
+                    let submit = async {
+                        for p in &scores {
+                            let john = john.in_course(p.course.clone());
+                            john.record_submission(
+                                p.student.clone(),
+                                assessment.clone(),
+                                p.grade,
+                                p.last_meeting,
+                            )
+                            .await?;
+                        }
+                        Ok(scores.clone())
+                    };
+                    call::unless_cancelled(ACTOR, &mut reply_to, submit).await
+                } else {
+                    Err(ActorError::Refused {
+                        actor: ACTOR,
+                        reason: "Attendance has no John to submit to".to_string(),
+                    })
+                };
+
+                let _ = reply_to.send(result);
+            }
+            AttendanceMessage::SetPolicy { policy } => self.policy = policy,
+            AttendanceMessage::SetJohn { john_handle } => {
+                trace!("[ACTOR]: Attendance setting John");
+                self.john = Some(john_handle);
+            }
+            AttendanceMessage::GetMeetings { reply_to } => {
+                let _ = reply_to.send(self.meetings.clone());
+            }
+            AttendanceMessage::LoadMeetings { meetings } => self.meetings = meetings,
+            AttendanceMessage::RollOver => self.meetings.clear(),
+        }
+    }
+}
+
+// ###################################################### //
+// ################### ACTOR FRONTEND ################### //
+// ###################################################### //
+
+async fn run_attendance_actor(mut actor: Attendance) {
+    while let Some(msg) = actor.receiver.recv().await {
+        trace!("[run_attendance_actor] is blocking until an AttendanceMessage is received");
+        actor.handle_message(msg).await;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AttendanceHandle {
+    id: ActorId,
+    sender: mpsc::Sender<AttendanceMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
+    course: Option<Course>, // Note: meetings without a course are `DEFAULT_COURSE`'s, like John's rosters
+}
+
+impl AttendanceHandle {
+    pub async fn new() -> Self {
+        AttendanceHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let actor = Attendance::new(id, receiver);
+        tokio::spawn(run_attendance_actor(actor));
+        AttendanceHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            course: None,
+        }
+    }
+
+    /// Which Attendance actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        AttendanceHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        AttendanceHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        AttendanceHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `course` only.
+    pub fn in_course(&self, course: Course) -> Self {
+        AttendanceHandle {
+            course: Some(course),
+            ..self.clone()
+        }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    /// Records a meeting held at `held_at` and how every student in `marks` turned up, returning the meeting's
+    /// number in this handle's course.
+    pub async fn record_meeting(
+        &self,
+        held_at: SystemTime,
+        marks: Vec<Mark>,
+    ) -> Result<usize, ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AttendanceMessage::RecordMeeting {
+            course: self.course.clone().unwrap_or_default(),
+            held_at,
+            marks,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Marks one student at an earlier meeting, replacing how they were marked before.
+    ///  - Returns `ActorError::Refused` if this handle's course has no meeting with that number.
+    pub async fn mark(&self, meeting: usize, mark: Mark) -> Result<(), ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AttendanceMessage::MarkStudent {
+            course: self.course.clone().unwrap_or_default(),
+            meeting,
+            mark,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Every marked student's attendance and participation score in the courses this handle is about.
+    pub async fn participation(&self) -> Result<Vec<Participation>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AttendanceMessage::GetParticipation {
+            course: self.course.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Records every participation score in the courses this handle is about in John, as work on the policy's
+    /// assessment handed in at the student's last meeting; from there it syncs like any other grade.
+    ///  - Returns `ActorError::Refused` if Attendance has no John, or John refuses a score (e.g. the assessment is
+    ///    not in `[deadlines]`, or the student is not registered with him).
+    pub async fn submit_participation(&self) -> Result<Vec<Participation>, ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AttendanceMessage::SubmitParticipation {
+            course: self.course.clone(),
+            deadline,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await?
+    }
+
+    /// Tells Attendance how meetings add up to a participation score (see `topology.toml`).
+    ///  - Returns `ActorError::Refused` without changing anything if `policy.validate()` finds a problem.
+    pub async fn set_policy(&self, policy: ParticipationPolicy) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        policy.validate().map_err(|reason| ActorError::Refused {
+            actor: ACTOR,
+            reason,
+        })?;
+        let msg = AttendanceMessage::SetPolicy { policy };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Gives Attendance the John it submits participation scores to.
+    ///  - Returns `ActorError::Cycle` without changing anything if that John can already reach this Attendance.
+    pub async fn set_john(&self, john_handle: JohnHandle) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let link = wiring::connect(self.id, john_handle.id())?;
+        let msg = AttendanceMessage::SetJohn { john_handle };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    /// Every meeting in every course, e.g. to save them.
+    pub async fn get_meetings(&self) -> Result<BTreeMap<Course, Vec<Meeting>>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = AttendanceMessage::GetMeetings { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Replaces every meeting Attendance holds with `meetings`, e.g. when loading a saved gradebook.
+    pub async fn load_meetings(
+        &self,
+        meetings: BTreeMap<Course, Vec<Meeting>>,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = AttendanceMessage::LoadMeetings { meetings };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Starts the next term's attendance from no meetings at all.
+    ///  - Note: the participation scores already submitted stay in John's archived term.
+    pub async fn roll_over(&self) -> Result<(), ActorError> {
+        self.authorize(Operation::RollOverTerm)?;
+        let msg = AttendanceMessage::RollOver;
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+}
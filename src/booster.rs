@@ -1,61 +1,231 @@
+use std::str::FromStr;
+use std::time::Duration;
+
 use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
 
+use crate::access::{self, Caller, Operation};
+use crate::admin::BoosterApproval;
+use crate::call::{self, ActorError};
+use crate::course::Scope;
+use crate::grade::{Grade, MAX_SCORE};
+use crate::proposal::{GradeChange, ProposalId};
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
 use crate::*;
 
+const ACTOR: &str = "Booster";
+
+/// How Booster changes the grades it gets from Admin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoostPolicy {
+    /// Everyone gets 100 (the original Booster behaviour).
+    Perfect,
+    /// Everyone gets this many extra points, capped at 100.
+    Add(f64),
+    /// Everyone below this grade is raised to it.
+    Floor(f64),
+    /// Every grade is scaled so the highest one becomes this grade.
+    Curve(f64),
+}
+
+impl BoostPolicy {
+    /// Why this policy cannot be used, if it cannot: its value must be a number from 0 to `MAX_SCORE`.
+    pub fn validate(self) -> Result<(), String> {
+        let value = match self {
+            BoostPolicy::Perfect => return Ok(()),
+            BoostPolicy::Add(value) | BoostPolicy::Floor(value) | BoostPolicy::Curve(value) => {
+                value
+            }
+        };
+        if !(0.0..=MAX_SCORE).contains(&value) {
+            return Err(format!(
+                "boost policy {:?} needs a value from 0 to {}",
+                self, MAX_SCORE
+            ));
+        }
+        Ok(())
+    }
+
+    /// Boosts every scored grade; ungraded, incomplete and excused students keep their state.
+    ///  - Note: a boost never lowers a grade (e.g. a curve to below the highest grade) and never goes past
+    ///    `MAX_SCORE`, whatever the policy asks for.
+    fn apply(self, grades: &[Grade]) -> Vec<Grade> {
+        let highest = Grade::scores(grades).into_iter().fold(0.0, f64::max);
+        grades
+            .iter()
+            .map(|grade| {
+                let Grade::Scored(score) = *grade else {
+                    return *grade;
+                };
+                let boosted = match self {
+                    BoostPolicy::Perfect => MAX_SCORE,
+                    BoostPolicy::Add(points) => score + points,
+                    BoostPolicy::Floor(floor) => score.max(floor),
+                    BoostPolicy::Curve(_) if highest <= 0.0 => score,
+                    BoostPolicy::Curve(target) => score * target / highest,
+                };
+                // Note: `max()` skips a NaN, so a policy that slipped past `validate()` leaves the score alone
+                Grade::Scored(boosted.max(score).min(MAX_SCORE))
+            })
+            .collect()
+    }
+}
+
+/// Parses `perfect`, `add:<points>`, `floor:<grade>` or `curve:<grade>`.
+impl FromStr for BoostPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s, None),
+        };
+        let value = || -> Result<f64, String> {
+            value
+                .ok_or_else(|| {
+                    format!("boost policy `{}` needs a value, e.g. `{}:10`", name, name)
+                })?
+                .parse::<f64>()
+                .map_err(|e| format!("bad value in boost policy `{}`: {}", s, e))
+        };
+        let policy = match name {
+            "perfect" => BoostPolicy::Perfect,
+            "add" => BoostPolicy::Add(value()?),
+            "floor" => BoostPolicy::Floor(value()?),
+            "curve" => BoostPolicy::Curve(value()?),
+            _ => {
+                return Err(format!(
+                    "unknown boost policy `{}`, expected perfect, add:<points>, floor:<grade> or curve:<grade>",
+                    s
+                ));
+            }
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+/// Booster's wiring at one moment, the only state it has (see `BoosterHandle::snapshot()`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoosterSnapshot {
+    /// The Admin Booster proposes to, if it was given one.
+    pub admin: Option<ActorId>,
+}
+
 // ##################################################### //
 // ################### ACTOR BACKEND ################### //
 // ##################################################### //
 
 struct Booster {
-   receiver: mpsc::Receiver<BoosterMessage>,
-   admin: Option<AdminHandle>,
+    id: ActorId,
+    receiver: mpsc::Receiver<BoosterMessage>,
+    admin: Option<AdminHandle>,
 }
 
 #[derive(Debug)]
 enum BoosterMessage {
-    BoostGrade{},
-    SetAdmin{admin_handle: AdminHandle},
+    BoostGrade {
+        policy: BoostPolicy,
+        requested_by: String,
+        deadline: Instant,
+        reply_to: oneshot::Sender<Result<ProposalId, ActorError>>,
+    },
+    SetAdmin {
+        admin_handle: AdminHandle,
+    },
+    Snapshot {
+        reply_to: oneshot::Sender<BoosterSnapshot>,
+    },
 }
 
 impl Booster {
-    fn new(receiver: mpsc::Receiver<BoosterMessage>) -> Self {
-        Booster{
-            receiver: receiver,
+    fn new(id: ActorId, receiver: mpsc::Receiver<BoosterMessage>) -> Self {
+        Booster {
+            id,
+            receiver,
             admin: None,
         }
     }
 
     async fn handle_message(&mut self, msg: BoosterMessage) {
-        println!(
+        trace!(
             "[Actor] Booster is running handle_message() with new BoosterMessage: {:?}",
             msg
         );
         match msg {
-            BoosterMessage::BoostGrade {} => {
-                println!("[ACTOR]: Booster boosting all grades retrieved from Admin!");
-                if let Some(ad) = &self.admin{
-                    let grades : Vec<f64> = ad.get_all_student_grades().await;
-                    let mut index = 0;
-                    let mut newGrades = grades.clone();
-                    loop{
-                        if index == newGrades.len(){
-                            break;
+            BoosterMessage::BoostGrade {
+                policy,
+                requested_by,
+                deadline,
+                mut reply_to,
+            } => {
+                trace!(
+                    "[ACTOR]: Booster proposing a boost of all grades retrieved from Admin with {:?}!",
+                    policy
+                );
+                let result = if let Some(ad) = &self.admin {
+                    let ad = ad.with_deadline(call::downstream(deadline));
+                    let _waiting = wiring::waiting(self.id, ad.id());
+                    let boost = async {
+                        let courses = ad.get_courses().await?;
+
+                        // Note: every course of the current term is boosted on its own (a curve is about one
+                        //       course), and every change says which course it is for
+                        let mut changes: Vec<GradeChange> = Vec::new();
+                        for (key, _) in courses.open_rosters() {
+                            let roster = courses.view(&Scope::of(key));
+                            let new_grades = policy.apply(&roster.grades);
+                            changes.extend(
+                                roster
+                                    .names
+                                    .into_iter()
+                                    .zip(roster.grades.into_iter().zip(new_grades))
+                                    .filter(|(_, (from, to))| from != to)
+                                    .map(|(name, (from, to))| GradeChange {
+                                        course: key.clone(),
+                                        name,
+                                        from,
+                                        to,
+                                    }),
+                            );
                         }
-                        newGrades[index] = 100.0;
-                        index = index + 1;
-                    }
-                    ad.submit_student_grades(newGrades).await;
-                    let updatedGrades : Vec<f64> = ad.get_all_student_grades().await;
-                    println!("[ACTOR]: Booster sees: {:?}", updatedGrades);
+
+                        // Note: Booster no longer writes grades itself, it asks Admin to hold the changes
+                        //       until an approver accepts them (see `AdminHandle::accept_proposal()`)
+                        let id = ad
+                            .propose_grade_changes(
+                                format!("{} for {}", self.id, requested_by),
+                                format!("boost with {:?}", policy),
+                                changes,
+                            )
+                            .await?;
+                        trace!(
+                            "[ACTOR]: Booster's boost is waiting in Admin as proposal #{}",
+                            id
+                        );
+                        Ok(id)
+                    };
+                    call::unless_cancelled(ACTOR, &mut reply_to, boost).await
                 } else {
-                    println!("[ACTOR]: Admin not initialized so Booster didn't do anything");
-                }
-                
-            },
-            BoosterMessage::SetAdmin{admin_handle} => {
-                println!("[ACTOR]: Booster setting Admin");
+                    trace!("[ACTOR]: Admin not initialized so Booster didn't do anything");
+                    Err(ActorError::Refused {
+                        actor: ACTOR,
+                        reason: "Booster has no Admin to propose to".to_string(),
+                    })
+                };
+
+                let _ = reply_to.send(result);
+            }
+            BoosterMessage::SetAdmin { admin_handle } => {
+                trace!("[ACTOR]: Booster setting Admin");
                 self.admin = Some(admin_handle);
-            },
+            }
+            BoosterMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(BoosterSnapshot {
+                    admin: self.admin.as_ref().map(|ad| ad.id()),
+                });
+            }
         };
     }
 }
@@ -65,37 +235,220 @@ impl Booster {
 // ###################################################### //
 
 async fn run_booster_actor(mut actor: Booster) {
-    // TODO
-    while let Some(msg) = actor.receiver.recv().await{
-        println!("[run_booster_actor] is blocking until a BoosterMessage is received");
+    while let Some(msg) = actor.receiver.recv().await {
+        trace!("[run_booster_actor] is blocking until a BoosterMessage is received");
         actor.handle_message(msg).await;
     }
 }
 
+#[cfg(test)]
+impl crate::harness::Steppable for Booster {
+    fn next_message(&mut self, wait: Duration) -> crate::harness::Step<'_> {
+        Box::pin(async move {
+            let msg = tokio::time::timeout(wait, self.receiver.recv())
+                .await
+                .ok()??;
+            let seen = format!("{:?}", msg);
+            self.handle_message(msg).await;
+            Some(seen)
+        })
+    }
+
+    fn run(self: Box<Self>) {
+        tokio::spawn(run_booster_actor(*self));
+    }
+}
+
+/// What a mock Booster answers (see `BoosterHandle::mocked()`).
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BoosterScript {
+    /// The id every boost's proposal gets.
+    pub proposal_id: ProposalId,
+    /// If set, every boost fails with this error.
+    pub boost_error: Option<ActorError>,
+}
+
+#[cfg(test)]
+impl BoosterScript {
+    fn answer(&mut self, msg: BoosterMessage) {
+        match msg {
+            BoosterMessage::BoostGrade { reply_to, .. } => {
+                let _ = reply_to.send(self.boost_error.clone().map_or(Ok(self.proposal_id), Err));
+            }
+            BoosterMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(BoosterSnapshot::default());
+            }
+            BoosterMessage::SetAdmin { .. } => {}
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BoosterHandle {
+    id: ActorId,
     sender: mpsc::Sender<BoosterMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
 }
 
 impl BoosterHandle {
     pub async fn new() -> Self {
-        // TODO
-        let (sender, receiver) = mpsc::channel(8);
-        let actor: Booster = Booster::new(receiver);
+        BoosterHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let actor: Booster = Booster::new(id, receiver);
         tokio::spawn(run_booster_actor(actor));
-        BoosterHandle {sender: sender}
+        BoosterHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+        }
     }
 
-    pub async fn boost_grades(&self){
-        let msg: BoosterMessage = BoosterMessage::BoostGrade{ 
+    /// Same as `with_mailbox()`, but Booster only handles a message when the test steps it (see `harness::Stepper`).
+    #[cfg(test)]
+    pub(crate) fn stepped(capacity: usize) -> (Self, crate::harness::Stepper) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let handle = BoosterHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
         };
-        let _ = self.sender.send(msg).await;
+        (
+            handle,
+            crate::harness::Stepper::new(Booster::new(id, receiver)),
+        )
     }
 
-    pub async fn set_admin(&self, admin_handle : AdminHandle){
-        let msg: BoosterMessage = BoosterMessage::SetAdmin{
-            admin_handle: admin_handle,
+    /// Same as `with_mailbox()`, but the handle talks to a mock Booster that answers from `script`
+    /// (see `harness::Mock`).
+    #[cfg(test)]
+    pub(crate) fn mocked(
+        capacity: usize,
+        script: BoosterScript,
+    ) -> (Self, crate::harness::Mock<BoosterScript>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = BoosterHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
         };
-        let _ = self.sender.send(msg).await;
+        let mock = crate::harness::Mock::spawn(receiver, script, BoosterScript::answer);
+        (handle, mock)
+    }
+
+    /// Which Booster actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        BoosterHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        BoosterHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        BoosterHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    /// Proposes boosting every grade in Admin to 100.
+    pub async fn boost_grades(&self) -> Result<ProposalId, ActorError> {
+        self.boost_grades_with(BoostPolicy::Perfect).await
+    }
+
+    /// Proposes boosting every grade in Admin according to `policy`.
+    ///  - Note: nothing changes yet, Admin holds the returned proposal until someone accepts or rejects it.
+    ///  - Returns `ActorError::Refused` if `policy` does not pass `BoostPolicy::validate()`.
+    pub async fn boost_grades_with(&self, policy: BoostPolicy) -> Result<ProposalId, ActorError> {
+        self.authorize(Operation::Boost)?;
+        policy.validate().map_err(|reason| ActorError::Refused {
+            actor: ACTOR,
+            reason,
+        })?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let requested_by = self
+            .caller
+            .as_ref()
+            .map(|caller| caller.to_string())
+            .unwrap_or_else(|| "the owner".to_string());
+        let msg: BoosterMessage = BoosterMessage::BoostGrade {
+            policy,
+            requested_by,
+            deadline,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await?
+    }
+
+    /// Gives this Booster the Admin that `approval` was issued by (see `AdminHandle::approve_booster()`).
+    ///  - Returns `ActorError::Cycle` without changing anything if that Admin can already reach this Booster.
+    pub async fn set_admin(&self, approval: BoosterApproval) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        trace!(
+            "[ACCESS]: Booster gets an Admin approved by {}",
+            approval.approved_by()
+        );
+        let admin_handle = approval.into_admin();
+        let link = wiring::connect(self.id, admin_handle.id())?;
+        let msg: BoosterMessage = BoosterMessage::SetAdmin { admin_handle };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    /// Which Admin Booster proposes to.
+    ///  - Note: Booster has nothing else to restore, `snapshot::restore()` just hands it an approved Admin again.
+    pub async fn snapshot(&self) -> Result<BoosterSnapshot, ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: BoosterMessage = BoosterMessage::Snapshot { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
     }
 }
@@ -1,85 +1,441 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
 use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation};
+use crate::admin::GradebookFeed;
+use crate::call::{self, ActorError};
+use crate::course::{Course, CourseKey, Courses, Roster, Scope, Term};
+use crate::events::student_of;
+use crate::feedback::LmsRow;
+use crate::grade::Grade;
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
+
+const ACTOR: &str = "Brightspace";
+
+/// Two coordinators sent the same student with different grades; Brightspace kept the owner's grade.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub course: CourseKey,
+    pub student: String,
+    /// The coordinator who sent the student first, and whose grade stands.
+    pub owner: ActorId,
+    pub owner_grade: Grade,
+    /// The coordinator whose grade was not taken.
+    pub rival: ActorId,
+    pub rival_grade: Grade,
+    pub raised_at: SystemTime,
+}
 
-use crate::*;
+/// Everything Brightspace holds at one moment (see `BrightspaceHandle::snapshot()`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BrightspaceSnapshot {
+    /// Every course shell, names with any career IDs Brightspace appended.
+    pub shells: Courses,
+    pub owners: Vec<(CourseKey, String, ActorId)>,
+    pub conflicts: Vec<Conflict>,
+    /// The Admin Brightspace reports to, if it has one.
+    pub admin: Option<ActorId>,
+}
 
 // ##################################################### //
 // ################### ACTOR BACKEND ################### //
 // ##################################################### //
 
 struct Brightspace {
+    id: ActorId,
     receiver: mpsc::Receiver<BrightspaceMessage>,
 
-    underlings: Vec<String>,
-    underling_grades: Vec<f64>,
-    admin: Option<AdminHandle>,
+    shells: Courses, // One course shell per course and term
+    owners: HashMap<(CourseKey, String), ActorId>, // Which coordinator (John) each student in each shell belongs to
+    edited: HashSet<(CourseKey, String)>, // Students whose grade was last changed in Brightspace, not by their owner
+    conflicts: Vec<Conflict>,
+    admin: Option<GradebookFeed>,
 }
 
 #[derive(Debug)]
 enum BrightspaceMessage {
-    ProcessStudentDump { students: Vec<String> },
-    ProcessGradeDump { grades: Vec<f64> },
-    AppendStudentCareerID,
-    SetAdmin { admin_handle: AdminHandle },
-    SendAllToAdmin { reply_to: oneshot::Sender<()> },
+    ProcessStudentDump {
+        scope: Scope,
+        students: Vec<String>,
+    },
+    ProcessGradeDump {
+        scope: Scope,
+        grades: Vec<Grade>,
+    },
+    MergeRoster {
+        scope: Scope,
+        coordinator: ActorId,
+        roster: Roster,
+    },
+    AppendStudentCareerID {
+        scope: Scope,
+    },
+    SetAdmin {
+        feed: GradebookFeed,
+    },
+    SendAllToAdmin {
+        deadline: Instant,
+        reply_to: oneshot::Sender<Result<(), ActorError>>,
+    },
+    GetAllStudentNames {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<String>>,
+    },
+    GetAllStudentGrades {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<Grade>>,
+    },
+    GetCourses {
+        reply_to: oneshot::Sender<Courses>,
+    },
+    GetConflicts {
+        reply_to: oneshot::Sender<Vec<Conflict>>,
+    },
+    GetLmsExport {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<LmsRow>>,
+    },
+    GetOwners {
+        reply_to: oneshot::Sender<Vec<(CourseKey, String, ActorId)>>,
+    },
+    LoadOwners {
+        owners: Vec<(CourseKey, String, ActorId)>,
+    },
+    LoadCourses {
+        shells: Courses,
+    },
+    RollOver {
+        term: Term,
+        reply_to: oneshot::Sender<Result<Term, String>>,
+    },
+    Snapshot {
+        reply_to: oneshot::Sender<BrightspaceSnapshot>,
+    },
+    Restore {
+        snapshot: BrightspaceSnapshot,
+    },
+}
+
+/// The career ID Brightspace gives a student, e.g. `apatel` for "Aarya Patel".
+///  - Returns `None` for names without a first and last part, since there is nothing to build an ID from.
+pub fn career_id(name: &str) -> Option<String> {
+    let (first, last) = name.trim().split_once(' ')?;
+    let first_initial = first.get(..1)?.to_ascii_lowercase();
+    let last_name = last.trim().to_ascii_lowercase();
+    Some(format!("{}{}", first_initial, last_name))
+}
+
+/// Splits a name Brightspace appended a career ID to back into the name and the ID,
+/// e.g. "Aarya Patel (apatel)" into ("Aarya Patel", "apatel").
+pub fn split_career_id(entry: &str) -> Option<(&str, &str)> {
+    let (name, rest) = entry.rsplit_once(" (")?;
+    let id = rest.strip_suffix(')')?;
+    (career_id(name)? == id).then_some((name, id))
 }
 
 impl Brightspace {
-    fn new(receiver: mpsc::Receiver<BrightspaceMessage>) -> Self {
+    fn new(id: ActorId, receiver: mpsc::Receiver<BrightspaceMessage>) -> Self {
         Brightspace {
-            receiver: receiver,
-            underlings: Vec::new(),
-            underling_grades: Vec::new(),
+            id,
+            receiver,
+            shells: Courses::default(),
+            owners: HashMap::new(),
+            edited: HashSet::new(),
+            conflicts: Vec::new(),
             admin: None,
         }
     }
 
+    /// Merges `coordinator`'s roster into the shell `scope` points at, leaving every other coordinator's students alone.
+    ///  - Note: a student belongs to whichever coordinator sent them first. The owner's grade always stands;
+    ///    a different grade from anyone else is recorded as a `Conflict` instead.
+    fn merge(&mut self, scope: &Scope, coordinator: ActorId, mut roster: Roster) {
+        let key = self.shells.key(scope);
+        let shell = match self.shells.roster_mut(scope) {
+            Ok(shell) => shell,
+            Err(term) => {
+                trace!("[ACTOR] Brightspace ignoring archived term {}.", term);
+                return;
+            }
+        };
+        shell.grades.resize(shell.names.len(), Grade::Ungraded);
+
+        // Note: students are matched without their career ID, since Brightspace may have appended one since
+        let sent: HashSet<&str> = roster.names.iter().map(|name| student_of(name)).collect();
+        let mut i = 0;
+        while i < shell.names.len() {
+            let owner_key = (key.clone(), student_of(&shell.names[i]).to_string());
+            if self.owners.get(&owner_key) == Some(&coordinator)
+                && !sent.contains(owner_key.1.as_str())
+            {
+                trace!("[ACTOR] Brightspace dropping {} from {}.", owner_key.1, key);
+                self.owners.remove(&owner_key);
+                self.edited.remove(&owner_key);
+                shell.late.remove(&owner_key.1);
+                shell.rubrics.remove(&owner_key.1);
+                shell.comments.remove(&owner_key.1);
+                shell.names.remove(i);
+                shell.grades.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        let grades = roster
+            .grades
+            .into_iter()
+            .chain(std::iter::repeat(Grade::Ungraded));
+        for (name, grade) in roster.names.into_iter().zip(grades) {
+            let student = student_of(&name).to_string();
+            let owner = *self
+                .owners
+                .entry((key.clone(), student.clone()))
+                .or_insert(coordinator);
+            let index = shell.names.iter().position(|n| student_of(n) == student);
+
+            if owner == coordinator {
+                // Note: like the grade, the late penalty, rubric score and feedback on it are the owner's to give or
+                //       take back
+                match roster.late.remove(&student) {
+                    Some(late) => shell.late.insert(student.clone(), late),
+                    None => shell.late.remove(&student),
+                };
+                match roster.rubrics.remove(&student) {
+                    Some(score) => shell.rubrics.insert(student.clone(), score),
+                    None => shell.rubrics.remove(&student),
+                };
+                match roster.comments.remove(&student) {
+                    Some(comments) => shell.comments.insert(student.clone(), comments),
+                    None => shell.comments.remove(&student),
+                };
+                match index {
+                    Some(index) => shell.grades[index] = grade,
+                    None => {
+                        shell.names.push(name);
+                        shell.grades.push(grade);
+                    }
+                }
+                self.edited.remove(&(key.clone(), student));
+                continue;
+            }
+
+            // Note: a rival who only registered the student (never graded them) does not disagree with anyone
+            let owner_grade = index.map(|index| shell.grades[index]).unwrap_or_default();
+            if grade == Grade::Ungraded || grade == owner_grade {
+                continue;
+            }
+            let reported = self.conflicts.iter().any(|c| {
+                c.course == key
+                    && c.student == student
+                    && c.rival == coordinator
+                    && c.owner_grade == owner_grade
+                    && c.rival_grade == grade
+            });
+            if !reported {
+                trace!(
+                    "[ACTOR] Brightspace keeping {}'s grade for {}, {} disagrees.",
+                    owner, student, coordinator
+                );
+                self.conflicts.push(Conflict {
+                    course: key.clone(),
+                    student,
+                    owner,
+                    owner_grade,
+                    rival: coordinator,
+                    rival_grade: grade,
+                    raised_at: SystemTime::now(),
+                });
+            }
+        }
+    }
+
     async fn handle_message(&mut self, msg: BrightspaceMessage) {
-        println!(
+        trace!(
             "[Actor] Brightspace is running handle_message() with new BrightspaceMessage: {:?}",
             msg
         );
         match msg {
-            BrightspaceMessage::ProcessStudentDump { students } => {
-                println!("[ACTOR] Brightspace is processing students.");
-                self.underlings = students
-            }
-            BrightspaceMessage::ProcessGradeDump { grades } => {
-                println!("[ACTOR] Brightspace is processing grades.");
-                self.underling_grades = grades
-            }
-            BrightspaceMessage::AppendStudentCareerID => {
-                self.underlings.iter_mut().for_each(|name| {
-                    let career_id = name.as_str().split_once(' ').map(|(first, last)| {
-                        let first_initial = first.get(..1).unwrap().to_ascii_lowercase();
-                        let last_name = last.to_ascii_lowercase();
-                        format!(" ({}{})", first_initial, last_name)
-                    });
-
-                    name.push_str(&career_id.unwrap());
+            BrightspaceMessage::ProcessStudentDump { scope, students } => {
+                trace!("[ACTOR] Brightspace is processing students.");
+                match self.shells.roster_mut(&scope) {
+                    Ok(shell) => shell.names = students,
+                    Err(term) => trace!("[ACTOR] Brightspace ignoring archived term {}.", term),
+                }
+            }
+            BrightspaceMessage::ProcessGradeDump { scope, grades } => {
+                trace!("[ACTOR] Brightspace is processing grades.");
+                let key = self.shells.key(&scope);
+                match self.shells.roster_mut(&scope) {
+                    Ok(shell) => {
+                        // Note: only grades this changes are Brightspace's own, the rest are still their owner's
+                        for (i, name) in shell.names.iter().enumerate() {
+                            if grades.get(i) != shell.grades.get(i) {
+                                self.edited
+                                    .insert((key.clone(), student_of(name).to_string()));
+                            }
+                        }
+                        shell.grades = grades;
+                    }
+                    Err(term) => trace!("[ACTOR] Brightspace ignoring archived term {}.", term),
+                }
+            }
+            BrightspaceMessage::MergeRoster {
+                scope,
+                coordinator,
+                roster,
+            } => {
+                trace!("[ACTOR] Brightspace is merging {}'s roster.", coordinator);
+                self.merge(&scope, coordinator, roster);
+            }
+            BrightspaceMessage::AppendStudentCareerID { scope } => {
+                let names = self
+                    .shells
+                    .view_mut(&scope)
+                    .flat_map(|shell| &mut shell.names);
+                names.for_each(|name| {
+                    // Note: a name that already ends in its ID was given one by an earlier AppendStudentCareerID
+                    if split_career_id(name).is_some() {
+                        return;
+                    }
+                    match career_id(name) {
+                        Some(id) => name.push_str(&format!(" ({})", id)),
+                        None => trace!(
+                            "[ACTOR] Brightspace cannot make a career ID for {:?}, skipping it.",
+                            name
+                        ),
+                    }
                 });
             }
 
-            BrightspaceMessage::SetAdmin { admin_handle } => {
-                println!("[ACTOR] Brightspace initialized Admin field with AdminHandle.");
-                self.admin = Some(admin_handle)
+            BrightspaceMessage::SetAdmin { feed } => {
+                trace!("[ACTOR] Brightspace initialized Admin field with a GradebookFeed.");
+                self.admin = Some(feed)
             }
-            BrightspaceMessage::SendAllToAdmin { reply_to } => {
-                if let Some(ad) = &self.admin {
-                    println!("[ACTOR]: Brightspace submitting all students and grades to Admin");
+            BrightspaceMessage::SendAllToAdmin {
+                deadline,
+                mut reply_to,
+            } => {
+                let result = if let Some(ad) = &self.admin {
+                    trace!("[ACTOR]: Brightspace submitting all students and grades to Admin");
 
-                    ad.submit_student_names(self.underlings.clone()).await;
-                    ad.submit_student_grades(self.underling_grades.clone())
-                        .await;
+                    let ad = ad.with_deadline(call::downstream(deadline));
+                    let _waiting = wiring::waiting(self.id, ad.id());
+                    let submit = async {
+                        for (key, shell) in self.shells.open_rosters() {
+                            let ad = ad.in_course(key.course.clone()).in_term(key.term.clone());
+                            let edited: BTreeSet<String> = self
+                                .edited
+                                .iter()
+                                .filter(|(course, _)| course == key)
+                                .map(|(_, student)| student.clone())
+                                .collect();
+                            ad.submit_student_names(shell.names.clone()).await?;
+                            ad.submit_edited_grades(shell.grades.clone(), edited)
+                                .await?;
+                            ad.submit_late_work(shell.late.clone()).await?;
+                            ad.submit_rubric_scores(shell.rubrics.clone()).await?;
+                            ad.submit_comments(shell.comments.clone()).await?;
+                        }
+                        Ok(())
+                    };
+                    call::unless_cancelled(ACTOR, &mut reply_to, submit).await
                 } else {
-                    println!(
+                    trace!(
                         "[ACTOR]: Brightspace does not have Admin initialized so nothing happened"
                     );
-                }
+                    Ok(())
+                };
 
-                let _ = reply_to.send(());
+                let _ = reply_to.send(result);
+            }
+            BrightspaceMessage::GetAllStudentNames { scope, reply_to } => {
+                let _ = reply_to.send(self.shells.view(&scope).names);
+            }
+            BrightspaceMessage::GetAllStudentGrades { scope, reply_to } => {
+                let _ = reply_to.send(self.shells.view(&scope).grades);
+            }
+            BrightspaceMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(self.shells.clone());
+            }
+            BrightspaceMessage::GetConflicts { reply_to } => {
+                let _ = reply_to.send(self.conflicts.clone());
+            }
+            BrightspaceMessage::GetLmsExport { scope, reply_to } => {
+                let _ = reply_to.send(self.lms_rows(&scope));
+            }
+            BrightspaceMessage::GetOwners { reply_to } => {
+                let _ = reply_to.send(self.owner_list());
+            }
+            BrightspaceMessage::LoadOwners { owners } => self.load_owners(owners),
+            BrightspaceMessage::LoadCourses { shells } => {
+                self.edited.clear();
+                self.shells = shells;
+            }
+            BrightspaceMessage::RollOver { term, reply_to } => {
+                trace!("[ACTOR] Brightspace opening term {}.", term);
+                let _ = reply_to.send(self.shells.roll_over(term));
+            }
+            BrightspaceMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(BrightspaceSnapshot {
+                    shells: self.shells.clone(),
+                    owners: self.owner_list(),
+                    conflicts: self.conflicts.clone(),
+                    admin: self.admin.as_ref().map(|ad| ad.id()),
+                });
+            }
+            // Note: the Admin id is only a record, Brightspace is re-wired through `set_admin()`
+            BrightspaceMessage::Restore { snapshot } => {
+                self.edited.clear();
+                self.shells = snapshot.shells;
+                self.load_owners(snapshot.owners);
+                self.conflicts = snapshot.conflicts;
             }
         }
     }
+
+    /// Every student in the shells `scope` covers, shell by shell, with the feedback on their grade.
+    fn lms_rows(&self, scope: &Scope) -> Vec<LmsRow> {
+        let shells = self.shells.rosters();
+        let shells = shells.filter(|(key, _)| scope.includes(key, self.shells.term()));
+        shells
+            .flat_map(|(key, shell)| {
+                shell.names.iter().enumerate().map(|(i, name)| LmsRow {
+                    course: key.clone(),
+                    student: name.clone(),
+                    grade: shell.grades.get(i).copied().unwrap_or(Grade::Ungraded),
+                    feedback: shell
+                        .comments
+                        .get(student_of(name))
+                        .into_iter()
+                        .flatten()
+                        .map(|c| c.text.clone())
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Every student's owner, course by course.
+    fn owner_list(&self) -> Vec<(CourseKey, String, ActorId)> {
+        let mut owners: Vec<_> = self
+            .owners
+            .iter()
+            .map(|((course, student), owner)| (course.clone(), student.clone(), *owner))
+            .collect();
+        owners.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        owners
+    }
+
+    fn load_owners(&mut self, owners: Vec<(CourseKey, String, ActorId)>) {
+        self.owners = owners
+            .into_iter()
+            .map(|(course, student, owner)| ((course, student), owner))
+            .collect();
+    }
 }
 
 // ###################################################### //
@@ -88,54 +444,430 @@ impl Brightspace {
 
 #[derive(Clone, Debug)]
 pub struct BrightspaceHandle {
+    id: ActorId,
     sender: mpsc::Sender<BrightspaceMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
+    scope: Scope,
 }
 
 async fn run_brightspace_actor(mut actor: Brightspace) {
-    println!("[run_brightspace_actor()]: is blocking until a BrightspaceMessage is received...");
+    trace!("[run_brightspace_actor()]: is blocking until a BrightspaceMessage is received...");
     while let Some(msg) = actor.receiver.recv().await {
-        println!(
+        trace!(
             "\n[run_brightspace_actor()]: received a new BrightspaceMessage and calling handle_message()..."
         );
         actor.handle_message(msg).await;
     }
 }
 
+#[cfg(test)]
+impl crate::harness::Steppable for Brightspace {
+    fn next_message(&mut self, wait: Duration) -> crate::harness::Step<'_> {
+        Box::pin(async move {
+            let msg = tokio::time::timeout(wait, self.receiver.recv())
+                .await
+                .ok()??;
+            let seen = format!("{:?}", msg);
+            self.handle_message(msg).await;
+            Some(seen)
+        })
+    }
+
+    fn run(self: Box<Self>) {
+        tokio::spawn(run_brightspace_actor(*self));
+    }
+}
+
+/// What a mock Brightspace answers (see `BrightspaceHandle::mocked()`).
+///  - Note: the mock ignores scopes, every query is answered from `roster`; writes are only recorded.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BrightspaceScript {
+    pub roster: Roster,
+    pub conflicts: Vec<Conflict>,
+    pub owners: Vec<(CourseKey, String, ActorId)>,
+    /// If set, reporting to Admin fails with this error.
+    pub report_error: Option<ActorError>,
+    /// If set, rolling over is refused with this reason.
+    pub refusal: Option<String>,
+}
+
+#[cfg(test)]
+impl BrightspaceScript {
+    fn answer(&mut self, msg: BrightspaceMessage) {
+        match msg {
+            BrightspaceMessage::SendAllToAdmin { reply_to, .. } => {
+                let _ = reply_to.send(self.report_error.clone().map_or(Ok(()), Err));
+            }
+            BrightspaceMessage::GetAllStudentNames { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.names.clone());
+            }
+            BrightspaceMessage::GetAllStudentGrades { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.grades.clone());
+            }
+            BrightspaceMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(crate::harness::courses_of(self.roster.clone()));
+            }
+            BrightspaceMessage::GetConflicts { reply_to } => {
+                let _ = reply_to.send(self.conflicts.clone());
+            }
+            BrightspaceMessage::GetOwners { reply_to } => {
+                let _ = reply_to.send(self.owners.clone());
+            }
+            BrightspaceMessage::GetLmsExport { reply_to, .. } => {
+                let _ = reply_to.send(Vec::new());
+            }
+            BrightspaceMessage::RollOver { term, reply_to } => {
+                let _ = reply_to.send(self.refusal.clone().map_or(Ok(term), Err));
+            }
+            BrightspaceMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(BrightspaceSnapshot {
+                    shells: crate::harness::courses_of(self.roster.clone()),
+                    owners: self.owners.clone(),
+                    conflicts: self.conflicts.clone(),
+                    admin: None,
+                });
+            }
+            BrightspaceMessage::Restore { .. }
+            | BrightspaceMessage::ProcessStudentDump { .. }
+            | BrightspaceMessage::ProcessGradeDump { .. }
+            | BrightspaceMessage::MergeRoster { .. }
+            | BrightspaceMessage::AppendStudentCareerID { .. }
+            | BrightspaceMessage::SetAdmin { .. }
+            | BrightspaceMessage::LoadOwners { .. }
+            | BrightspaceMessage::LoadCourses { .. } => {}
+        }
+    }
+}
+
 impl BrightspaceHandle {
     pub async fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(8);
-        let actor = Brightspace::new(receiver);
+        BrightspaceHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let actor = Brightspace::new(id, receiver);
         tokio::spawn(run_brightspace_actor(actor));
 
-        BrightspaceHandle { sender: sender }
+        BrightspaceHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        }
+    }
+
+    /// Same as `with_mailbox()`, but Brightspace only handles a message when the test steps it (see `harness::Stepper`).
+    #[cfg(test)]
+    pub(crate) fn stepped(capacity: usize) -> (Self, crate::harness::Stepper) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let handle = BrightspaceHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        (
+            handle,
+            crate::harness::Stepper::new(Brightspace::new(id, receiver)),
+        )
+    }
+
+    /// Same as `with_mailbox()`, but the handle talks to a mock Brightspace that answers from `script`
+    /// (see `harness::Mock`).
+    #[cfg(test)]
+    pub(crate) fn mocked(
+        capacity: usize,
+        script: BrightspaceScript,
+    ) -> (Self, crate::harness::Mock<BrightspaceScript>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = BrightspaceHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        let mock = crate::harness::Mock::spawn(receiver, script, BrightspaceScript::answer);
+        (handle, mock)
+    }
+
+    /// Which Brightspace actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
     }
 
-    pub async fn enter_students_into_brightspace(&self, students: Vec<String>) {
-        let msg = BrightspaceMessage::ProcessStudentDump { students };
-        let _ = self.sender.send(msg).await;
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        BrightspaceHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        BrightspaceHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        BrightspaceHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about the shell of `course` only.
+    pub fn in_course(&self, course: Course) -> Self {
+        BrightspaceHandle {
+            scope: Scope {
+                course: Some(course),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `term` instead of the current term.
+    pub fn in_term(&self, term: Term) -> Self {
+        BrightspaceHandle {
+            scope: Scope {
+                term: Some(term),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
     }
 
-    pub async fn enter_student_grades_into_brightspace(&self, grades: Vec<f64>) {
-        let msg = BrightspaceMessage::ProcessGradeDump { grades };
-        let _ = self.sender.send(msg).await;
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
     }
 
-    pub async fn generate_and_append_student_career_id(&self) {
-        let msg = BrightspaceMessage::AppendStudentCareerID;
-        let _ = self.sender.send(msg).await;
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
     }
 
-    pub async fn set_admin(&self, admin_handle: AdminHandle) {
-        let msg = BrightspaceMessage::SetAdmin { admin_handle };
-        let _ = self.sender.send(msg).await;
+    pub async fn enter_students_into_brightspace(
+        &self,
+        students: Vec<String>,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::EditBrightspace)?;
+        let msg = BrightspaceMessage::ProcessStudentDump {
+            scope: self.scope.clone(),
+            students,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    pub async fn enter_student_grades_into_brightspace(
+        &self,
+        grades: Vec<Grade>,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::EditBrightspace)?;
+        let msg = BrightspaceMessage::ProcessGradeDump {
+            scope: self.scope.clone(),
+            grades,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Merges `coordinator`'s students and grades into this Brightspace without touching anyone else's
+    /// (unlike `enter_students_into_brightspace()`, which replaces the whole shell).
+    pub async fn merge_roster_into_brightspace(
+        &self,
+        coordinator: ActorId,
+        roster: Roster,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::EditBrightspace)?;
+        let msg = BrightspaceMessage::MergeRoster {
+            scope: self.scope.clone(),
+            coordinator,
+            roster,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    pub async fn generate_and_append_student_career_id(&self) -> Result<(), ActorError> {
+        self.authorize(Operation::EditBrightspace)?;
+        let msg = BrightspaceMessage::AppendStudentCareerID {
+            scope: self.scope.clone(),
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Gives this Brightspace an Admin to report to, through the feed that Admin made for it (see `AdminHandle::feed()`).
+    ///  - Returns `ActorError::Cycle` without changing anything if that Admin can already reach this Brightspace.
+    pub async fn set_admin(&self, feed: GradebookFeed) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let link = wiring::connect(self.id, feed.id())?;
+        let msg = BrightspaceMessage::SetAdmin { feed };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    pub async fn report_all_students_and_grades_to_admin(&self) -> Result<(), ActorError> {
+        self.authorize(Operation::ReportToAdmin)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::SendAllToAdmin {
+            deadline,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await?
+    }
+
+    pub async fn get_all_student_names(&self) -> Result<Vec<String>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::GetAllStudentNames {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
     }
 
-    pub async fn report_all_students_and_grades_to_admin(&self) {
+    pub async fn get_all_student_grades(&self) -> Result<Vec<Grade>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
         let (tx, rx) = oneshot::channel();
 
-        let msg = BrightspaceMessage::SendAllToAdmin { reply_to: tx };
-        let _ = self.sender.send(msg).await;
+        let msg = BrightspaceMessage::GetAllStudentGrades {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every course shell this Brightspace holds, in every term.
+    pub async fn get_courses(&self) -> Result<Courses, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::GetCourses { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every time two coordinators sent the same student with different grades, oldest first.
+    pub async fn get_conflicts(&self) -> Result<Vec<Conflict>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::GetConflicts { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// The LMS export of the shells this handle is about: every student's grade, with the feedback John wrote on it.
+    pub async fn lms_export(&self) -> Result<Vec<LmsRow>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::GetLmsExport {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Which coordinator every student in every shell belongs to, shell by shell.
+    pub async fn get_owners(&self) -> Result<Vec<(CourseKey, String, ActorId)>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::GetOwners { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Replaces who owns which student with `owners`, e.g. when loading a saved gradebook.
+    pub async fn load_owners(
+        &self,
+        owners: Vec<(CourseKey, String, ActorId)>,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = BrightspaceMessage::LoadOwners { owners };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Replaces every course shell with `shells`, e.g. when loading a saved gradebook.
+    pub async fn load_courses(&self, shells: Courses) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg = BrightspaceMessage::LoadCourses { shells };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Archives the current term's shells and starts `term`, returning the term that was archived.
+    pub async fn roll_over(&self, term: Term) -> Result<Term, ActorError> {
+        self.authorize(Operation::RollOverTerm)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::RollOver { term, reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Everything Brightspace holds right now, and which Admin it reports to.
+    pub async fn snapshot(&self) -> Result<BrightspaceSnapshot, ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = BrightspaceMessage::Snapshot { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
 
-        let _ = rx.await;
+    /// Puts Brightspace's shells, owners and conflicts back the way `snapshot` found them.
+    ///  - Note: this does not re-wire Brightspace, give it its Admin with `set_admin()` (see `snapshot::restore()`).
+    pub async fn restore(&self, snapshot: BrightspaceSnapshot) -> Result<(), ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let msg = BrightspaceMessage::Restore { snapshot };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
     }
 }
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Instant, timeout_at};
+
+use crate::access::Operation;
+use crate::wiring::ActorId;
+
+// ###################################################### //
+// ############### SHARED HANDLE PLUMBING ############### //
+// ###################################################### //
+
+/// How long a handle call may take when no explicit timeout or deadline was given.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How much of a deadline an actor keeps for itself before forwarding the rest downstream.
+///  - Note: this makes the slowest actor in a chain time out FIRST, so the error names it
+///    instead of whoever happens to be at the top of the chain.
+const HOP_MARGIN: Duration = Duration::from_millis(20);
+
+/// Everything that can go wrong when a handle talks to its actor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActorError {
+    /// The actor (named here) did not accept or answer the message before the deadline.
+    Timeout { actor: &'static str },
+    /// The caller gave up, so the actor (named here) abandoned the work.
+    Cancelled { actor: &'static str },
+    /// The actor (named here) is no longer running.
+    Closed { actor: &'static str },
+    /// Wiring these actors together would make a loop (first actor == last actor), so it was refused.
+    Cycle { path: Vec<ActorId> },
+    /// The actor (named here) got the message but refused to do it, for `reason`.
+    Refused { actor: &'static str, reason: String },
+    /// The handle's caller is not allowed to ask this actor (named here) for `operation`.
+    Denied {
+        actor: &'static str,
+        caller: String,
+        operation: Operation,
+    },
+}
+
+impl fmt::Display for ActorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorError::Timeout { actor } => {
+                write!(f, "{} did not respond before the deadline", actor)
+            }
+            ActorError::Cancelled { actor } => {
+                write!(f, "{} cancelled the call because the caller gave up", actor)
+            }
+            ActorError::Closed { actor } => write!(f, "{} is no longer running", actor),
+            ActorError::Refused { actor, reason } => write!(f, "{} refused: {}", actor, reason),
+            ActorError::Denied {
+                actor,
+                caller,
+                operation,
+            } => write!(f, "{} may not ask {} for {:?}", caller, actor, operation),
+            ActorError::Cycle { path } => {
+                let path: Vec<String> = path.iter().map(|id| id.to_string()).collect();
+                write!(
+                    f,
+                    "wiring refused, it would create a cycle: {}",
+                    path.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActorError {}
+
+/// The deadline an actor should hand to the next actor in the chain.
+pub(crate) fn downstream(deadline: Instant) -> Instant {
+    deadline
+        .checked_sub(HOP_MARGIN)
+        .filter(|d| *d > Instant::now())
+        .unwrap_or(deadline)
+}
+
+/// Puts `msg` in `actor`'s mailbox, waiting for room no longer than `deadline`.
+pub(crate) async fn send<M>(
+    actor: &'static str,
+    sender: &mpsc::Sender<M>,
+    msg: M,
+    deadline: Instant,
+) -> Result<(), ActorError> {
+    match timeout_at(deadline, sender.send(msg)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) => Err(ActorError::Closed { actor }),
+        Err(_) => Err(ActorError::Timeout { actor }),
+    }
+}
+
+/// Waits for `actor`'s reply on `rx` no longer than `deadline`.
+///  - Note: giving up drops `rx`, which is how the actor learns that its caller cancelled.
+pub(crate) async fn recv<T>(
+    actor: &'static str,
+    rx: oneshot::Receiver<T>,
+    deadline: Instant,
+) -> Result<T, ActorError> {
+    match timeout_at(deadline, rx).await {
+        Ok(Ok(reply)) => Ok(reply),
+        Ok(Err(_)) => Err(ActorError::Closed { actor }),
+        Err(_) => Err(ActorError::Timeout { actor }),
+    }
+}
+
+/// Runs `work` on behalf of a caller waiting on `reply_to`, and stops it early if that caller goes away.
+///  - Note: dropping `work` also drops any downstream `recv()` inside it, so the cancellation keeps travelling down the chain.
+pub(crate) async fn unless_cancelled<R>(
+    actor: &'static str,
+    reply_to: &mut oneshot::Sender<Result<R, ActorError>>,
+    work: impl Future<Output = Result<R, ActorError>>,
+) -> Result<R, ActorError> {
+    tokio::select! {
+        biased;
+        _ = reply_to.closed() => Err(ActorError::Cancelled { actor }),
+        result = work => result,
+    }
+}
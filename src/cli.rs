@@ -0,0 +1,1554 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::access::{Caller, Role};
+use crate::admin::GradeStatistics;
+use crate::attendance::{AttendanceHandle, AttendanceStatus, Mark, Meeting};
+use crate::booster::BoostPolicy;
+use crate::call::ActorError;
+use crate::course::{Course, CourseKey, Courses, Scope, Term};
+use crate::deadline::Timestamp;
+use crate::events::student_of;
+use crate::feedback::CommentText;
+use crate::grade::Grade;
+use crate::history::{History, Revision};
+use crate::proposal::{Outcome, Proposal, ProposalBook, ProposalId};
+use crate::reconcile::{self, Sides, TruthRule};
+use crate::rubric::Selection;
+use crate::snapshot::{self, SystemSnapshot};
+use crate::timeline::{CourseTrend, Timelines};
+use crate::topology::{Actors, Topology};
+use crate::wiring::ActorId;
+use crate::*;
+
+// ###################################################### //
+// ################# COMMAND-LINE FRONT ################# //
+// ###################################################### //
+
+/// The John actor commands go to unless `--coordinator` names another one.
+pub const DEFAULT_COORDINATOR: &str = "john";
+
+/// Onboarding gradebook for the Purdue Aerial Robotics Team.
+///  - Without a subcommand this runs the onboarding demo script.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// File the gradebook is loaded from before, and saved to after, every command.
+    #[arg(long, global = true, default_value = "gradebook.json")]
+    pub state: PathBuf,
+
+    /// Topology file describing which actors to start and how to wire them.
+    #[arg(long, global = true, default_value = "topology.toml")]
+    pub topology: PathBuf,
+
+    /// Print JSON instead of tables.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Who is running the command (defaults to $USER).
+    #[arg(long, global = true)]
+    pub user: Option<String>,
+
+    /// coordinator, ta or student: decides which commands are allowed (student, the least, unless given).
+    #[arg(long, global = true, default_value = "student")]
+    pub role: Role,
+
+    /// Which John-style coordinator registers and grades (the name of a John actor in the topology).
+    #[arg(long, global = true, default_value = DEFAULT_COORDINATOR)]
+    pub coordinator: String,
+
+    /// Only this course (NAME or NAME/SECTION): register into it, grade in it, or ask Admin about it alone.
+    #[arg(long, global = true)]
+    pub course: Option<Course>,
+
+    /// A term other than the current one, e.g. an archived term to read its grades.
+    #[arg(long, global = true)]
+    pub term: Option<Term>,
+
+    /// Print the actors' trace lines while the command runs.
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// The person the actors should check every command against.
+    pub fn caller(&self) -> Caller {
+        let name = self
+            .user
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| "cli".to_string());
+        Caller::new(name, self.role)
+    }
+
+    /// Which course and term every command is about.
+    pub fn scope(&self) -> Scope {
+        Scope {
+            course: self.course.clone(),
+            term: self.term.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the onboarding demo script (John -> Brightspace -> Admin, then Booster).
+    Demo,
+    /// Start an interactive shell over the running actors.
+    Shell,
+    /// Serve the gradebook as an HTTP/JSON API until Ctrl-C.
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+        /// A TOML file of `[[tokens]]` (token, user, role); without it every request is an anonymous student.
+        #[arg(long)]
+        tokens: Option<PathBuf>,
+    },
+    /// Register new students with John.
+    Register {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Remove students from John; they leave Brightspace and Admin on the next sync.
+    Remove {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Have John record a student's grade: a number from 0 to 100, or ungraded, incomplete or excused.
+    Grade { name: String, grade: Grade },
+    /// Have John record a student's work on an assessment, marked down if it came in late (see `[deadlines]`).
+    Submit {
+        name: String,
+        assessment: String,
+        /// What the work scored before any late penalty.
+        grade: Grade,
+        /// When it was handed in, in UTC, e.g. 2026-10-21T09:30:00Z (defaults to now).
+        #[arg(long)]
+        at: Option<Timestamp>,
+    },
+    /// Have John grade a student on a rubric from `[[rubrics]]`, picking one level of every criterion.
+    RubricGrade {
+        name: String,
+        rubric: String,
+        /// One CRITERION=LEVEL per criterion, e.g. requirements=excellent.
+        #[arg(required = true)]
+        selections: Vec<Selection>,
+    },
+    /// Have John attach feedback to a student's grade: your own words, or a comment from `[comments]`.
+    Comment {
+        name: String,
+        #[arg(required_unless_present_any = ["bank", "clear"], conflicts_with_all = ["bank", "clear"])]
+        text: Option<String>,
+        /// The key of a comment in `[comments]`, e.g. needs-docs.
+        #[arg(long, conflicts_with = "clear")]
+        bank: Option<String>,
+        /// Take back all of the feedback on the student's grade instead.
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Record a lab meeting and how every student turned up to it.
+    Meeting {
+        /// One NAME=STATUS per student, where STATUS is present, late, absent or excused.
+        #[arg(required = true)]
+        marks: Vec<Mark>,
+        /// When the meeting was held, in UTC (defaults to now).
+        #[arg(long)]
+        at: Option<Timestamp>,
+    },
+    /// Change how a student was marked at an earlier meeting (numbered from 1 in each course).
+    Mark {
+        meeting: usize,
+        name: String,
+        status: AttendanceStatus,
+    },
+    /// Show every student's attendance and participation score (see `[attendance]`).
+    Participation {
+        /// Also record the scores in John, who syncs them like any other grade.
+        #[arg(long)]
+        submit: bool,
+    },
+    /// Push every coordinator's roster to Brightspace, then Brightspace's roster to Admin.
+    Sync,
+    /// Have Brightspace append career IDs to its names, then push its roster to Admin.
+    CareerIds,
+    /// Have Booster propose boosting every grade in Admin (see `proposals`, `approve`, `reject`).
+    Boost {
+        /// perfect, add:<points>, floor:<grade> or curve:<grade>, each value from 0 to 100 (a boost never lowers a grade)
+        #[arg(long, default_value = "perfect")]
+        policy: BoostPolicy,
+    },
+    /// List grade-change proposals waiting in Admin (add --all for decided ones too).
+    Proposals {
+        #[arg(long)]
+        all: bool,
+    },
+    /// Accept a pending proposal, applying all of its grade changes at once.
+    Approve { id: ProposalId },
+    /// Reject a pending proposal without changing any grade.
+    Reject {
+        id: ProposalId,
+        #[arg(long, default_value = "rejected")]
+        reason: String,
+    },
+    /// Take back the newest change to Admin's gradebook (a sync, an approved boost, ...).
+    Undo {
+        /// Also put the corrected rosters back into Brightspace.
+        #[arg(long)]
+        push: bool,
+    },
+    /// Make the most recently undone change to Admin's gradebook again.
+    Redo {
+        /// Also put the rosters back into Brightspace.
+        #[arg(long)]
+        push: bool,
+    },
+    /// List the changes to Admin's gradebook that `undo` can take back and `redo` make again.
+    History,
+    /// Compare John's, Brightspace's and Admin's rosters student by student and list every mismatch.
+    Reconcile {
+        /// Also rewrite every roster that disagrees with `--truth`.
+        #[arg(long)]
+        fix: bool,
+        /// Which side is right: john, brightspace or admin, or `<students>:<grades>`, e.g. john:admin.
+        #[arg(long, default_value = "john")]
+        truth: TruthRule,
+    },
+    /// Show every grade one student has had in Admin, who gave it to them, and how their scores are trending.
+    GradeHistory { name: String },
+    /// Show one student's grade and letter grade from the StudentPortal.
+    Portal {
+        /// The student's career ID, e.g. apatel (defaults to --user).
+        career_id: Option<String>,
+    },
+    /// List every student who is ungraded or under an alert threshold right now.
+    AtRisk,
+    /// List the alerts raised while this command (or shell) has been running.
+    Alerts,
+    /// List the students two coordinators sent Brightspace with different grades while this command (or shell)
+    /// has been running (`sync` prints them as it finds them).
+    Conflicts,
+    /// Archive the current term everywhere and start `term` (its rosters start out empty).
+    Rollover { term: Term },
+    /// List every course and section Admin has, term by term.
+    Courses,
+    /// Show summary statistics of Admin's gradebook.
+    Stats,
+    /// List every student Admin has below the passing grade.
+    Failing,
+    /// List every grade in Admin that was marked down for late work, next to what it was before the penalty.
+    Late,
+    /// Show how every rubric-graded student in Admin scored on each criterion (or just `name`).
+    Feedback {
+        /// A student's name or career ID.
+        name: Option<String>,
+    },
+    /// Show the written feedback on every student's grade in Admin (or just `name`'s).
+    Comments {
+        /// A student's name or career ID.
+        name: Option<String>,
+    },
+    /// Write every John, Brightspace, Admin and Booster (and how they are wired) to a snapshot file.
+    Snapshot {
+        #[arg(long, default_value = "snapshot.json")]
+        out: PathBuf,
+    },
+    /// Start the actors afresh from a snapshot file, replacing the saved gradebook (see `snapshot`).
+    Restore { file: PathBuf },
+    /// Write Admin's gradebook (or Brightspace's LMS export, with feedback) out as CSV or JSON.
+    Export {
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+        #[arg(long, value_enum, default_value = "admin")]
+        from: ExportSource,
+        /// Write to this file instead of stdout.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportSource {
+    Admin,
+    Brightspace,
+}
+
+/// One student in one Brightspace shell, and the coordinator they belong to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ownership {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub coordinator: String,
+}
+
+/// Everything the CLI keeps between invocations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedState {
+    pub john: Courses,
+    /// Every other coordinator's rosters, by the name of its John actor in the topology.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub leads: BTreeMap<String, Courses>,
+    pub brightspace: Courses,
+    /// Which coordinator every student in Brightspace belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<Ownership>,
+    pub admin: Courses,
+    #[serde(default)]
+    pub proposals: ProposalBook,
+    /// Admin's changes that `undo` can still take back (and `redo` make again).
+    #[serde(default)]
+    pub history: History,
+    /// Every grade every student has had in Admin (see `grade-history`).
+    #[serde(default)]
+    pub timelines: Timelines,
+    /// Every lab meeting of the current term, by course (see `meeting`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attendance: BTreeMap<Course, Vec<Meeting>>,
+}
+
+#[derive(Serialize)]
+struct StudentRow<'a> {
+    name: &'a str,
+    grade: Grade,
+}
+
+/// A `Conflict` with the coordinators called by their topology names.
+#[derive(Debug, Serialize)]
+pub(crate) struct ConflictRow {
+    course: String,
+    term: Term,
+    student: String,
+    owner: String,
+    owner_grade: Grade,
+    rival: String,
+    rival_grade: Grade,
+    raised_at: SystemTime,
+}
+
+#[derive(Serialize)]
+struct CourseRow<'a> {
+    course: &'a Course,
+    term: &'a Term,
+    students: usize,
+    mean: Option<f64>,
+    status: &'static str,
+}
+
+/// The actors the CLI drives, pulled out of the topology by their conventional names.
+#[derive(Clone)]
+pub(crate) struct Session {
+    pub(crate) john: JohnHandle, // The coordinator commands go to (see `--coordinator`)
+    pub(crate) coordinators: BTreeMap<String, JohnHandle>, // Every John in the topology, by name
+    pub(crate) brightspace: BrightspaceHandle,
+    pub(crate) admin: AdminHandle,
+    pub(crate) booster: BoosterHandle,
+    pub(crate) portal: StudentPortalHandle,
+    pub(crate) alerter: AlerterHandle,
+    pub(crate) attendance: AttendanceHandle,
+    pub(crate) actors: Actors, // Everything the topology started, by name (see `snapshot`)
+}
+
+impl Session {
+    /// Starts the actors from `cli.topology` and loads the gradebook saved in `cli.state` into them.
+    pub(crate) async fn start(cli: &Cli) -> anyhow::Result<Self> {
+        let topology = Topology::from_file(&cli.topology)?;
+        let actors = topology.start().await?;
+        let session = Session::from_actors(&actors)?
+            .as_coordinator(&cli.coordinator)
+            .with_context(|| format!("topology has no John named `{}`", cli.coordinator))?;
+        // Note: restore before wiring, so the saved gradebook does not look like a burst of new changes
+        session.restore(&load_state(&cli.state)?).await?;
+        topology.wire(&actors).await?;
+        Ok(session)
+    }
+
+    /// The same actors, but every call is checked against what `caller` may do.
+    ///  - Note: keep the unscoped session around for `persist()`, which needs to read everything.
+    pub(crate) fn acting_as(&self, caller: Caller) -> Session {
+        Session {
+            john: self.john.acting_as(caller.clone()),
+            coordinators: self
+                .coordinators
+                .iter()
+                .map(|(name, john)| (name.clone(), john.acting_as(caller.clone())))
+                .collect(),
+            brightspace: self.brightspace.acting_as(caller.clone()),
+            admin: self.admin.acting_as(caller.clone()),
+            booster: self.booster.acting_as(caller.clone()),
+            portal: self.portal.acting_as(caller.clone()),
+            alerter: self.alerter.acting_as(caller.clone()),
+            attendance: self.attendance.acting_as(caller.clone()),
+            actors: self.actors.acting_as(caller),
+        }
+    }
+
+    /// The same actors, but commands go to the coordinator called `name` instead (`None` if there is none).
+    pub(crate) fn as_coordinator(&self, name: &str) -> Option<Session> {
+        Some(Session {
+            john: self.coordinators.get(name)?.clone(),
+            ..self.clone()
+        })
+    }
+
+    /// What `id`'s coordinator is called in the topology.
+    fn coordinator_name(&self, id: ActorId) -> String {
+        self.coordinators
+            .iter()
+            .find(|(_, john)| john.id() == id)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Every conflict Brightspace recorded between two coordinators.
+    pub(crate) async fn conflict_report(&self) -> Result<Vec<ConflictRow>, ActorError> {
+        let conflicts = self.brightspace.get_conflicts().await?;
+        Ok(conflicts
+            .into_iter()
+            .map(|c| ConflictRow {
+                course: c.course.course.to_string(),
+                term: c.course.term,
+                student: c.student,
+                owner: self.coordinator_name(c.owner),
+                owner_grade: c.owner_grade,
+                rival: self.coordinator_name(c.rival),
+                rival_grade: c.rival_grade,
+                raised_at: c.raised_at,
+            })
+            .collect())
+    }
+
+    /// The same actors, but John, Brightspace and Admin only see the course and term in `scope`.
+    pub(crate) fn scoped(&self, scope: &Scope) -> Session {
+        let mut session = self.clone();
+        if let Some(course) = &scope.course {
+            session.john = session.john.in_course(course.clone());
+            session.brightspace = session.brightspace.in_course(course.clone());
+            session.admin = session.admin.in_course(course.clone());
+            session.attendance = session.attendance.in_course(course.clone());
+        }
+        if let Some(term) = &scope.term {
+            session.john = session.john.in_term(term.clone());
+            session.brightspace = session.brightspace.in_term(term.clone());
+            session.admin = session.admin.in_term(term.clone());
+        }
+        session
+    }
+
+    /// Archives the current term in Admin, Brightspace and every coordinator and starts `term`, returning the archived term.
+    pub(crate) async fn roll_over(&self, term: Term) -> Result<Term, ActorError> {
+        // Note: Admin goes first, since a term Admin refuses must not be half started downstream
+        let previous = self.admin.roll_over(term.clone()).await?;
+        self.brightspace.roll_over(term.clone()).await?;
+        for john in self.coordinators.values() {
+            john.roll_over(term.clone()).await?;
+        }
+        self.attendance.roll_over().await?;
+        Ok(previous)
+    }
+
+    /// Overwrites every Brightspace shell `revision` touched with the roster Admin now has for it.
+    async fn push_to_brightspace(&self, revision: &Revision) -> anyhow::Result<()> {
+        for (key, roster) in &revision.rosters {
+            let shell = self
+                .brightspace
+                .in_course(key.course.clone())
+                .in_term(key.term.clone());
+            shell
+                .enter_students_into_brightspace(roster.names.clone())
+                .await?;
+            shell
+                .enter_student_grades_into_brightspace(roster.grades.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Saves the actors' rosters back to `cli.state`.
+    pub(crate) async fn persist(&self, cli: &Cli) -> anyhow::Result<()> {
+        let state = self.save().await?;
+        // Note: reading Admin above means it has published every change, so the Alerter can send the last alerts
+        self.alerter.flush().await?;
+        write_state(&cli.state, &state)
+    }
+
+    fn from_actors(actors: &Actors) -> anyhow::Result<Self> {
+        // Note: `john` is only a placeholder until `as_coordinator()` picks the coordinator commands go to
+        let john = actors
+            .johns
+            .values()
+            .next()
+            .context("topology has no John actor")?;
+        Ok(Session {
+            john: john.clone(),
+            coordinators: actors.johns.clone(),
+            brightspace: actors
+                .brightspace("brightspace")
+                .context("topology has no actor `brightspace`")?
+                .clone(),
+            admin: actors
+                .admin("admin")
+                .context("topology has no actor `admin`")?
+                .clone(),
+            booster: actors
+                .booster("booster")
+                .context("topology has no actor `booster`")?
+                .clone(),
+            portal: actors
+                .portal("portal")
+                .context("topology has no actor `portal`")?
+                .clone(),
+            alerter: actors
+                .alerter("alerter")
+                .context("topology has no actor `alerter`")?
+                .clone(),
+            attendance: actors
+                .attendance("attendance")
+                .context("topology has no actor `attendance`")?
+                .clone(),
+            actors: actors.clone(),
+        })
+    }
+
+    /// Feeds a saved state back into freshly started actors through their normal handle methods.
+    async fn restore(&self, state: &SavedState) -> anyhow::Result<()> {
+        for (name, john) in &self.coordinators {
+            let courses = match name.as_str() {
+                DEFAULT_COORDINATOR => state.john.clone(),
+                _ => state.leads.get(name).cloned().unwrap_or_default(),
+            };
+            john.load_courses(courses).await?;
+        }
+        self.brightspace
+            .load_courses(state.brightspace.clone())
+            .await?;
+        // Note: a coordinator that is no longer in the topology gives up its students to whoever sends them next
+        let owners = state
+            .owners
+            .iter()
+            .filter_map(|o| {
+                let john = self.coordinators.get(&o.coordinator)?;
+                Some((o.course.clone(), o.student.clone(), john.id()))
+            })
+            .collect();
+        self.brightspace.load_owners(owners).await?;
+        self.admin.load_courses(state.admin.clone()).await?;
+        self.admin.load_proposals(state.proposals.clone()).await?;
+        self.admin.load_history(state.history.clone()).await?;
+        self.admin.load_timelines(state.timelines.clone()).await?;
+        self.attendance
+            .load_meetings(state.attendance.clone())
+            .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn save(&self) -> anyhow::Result<SavedState> {
+        let mut john = Courses::default();
+        let mut leads = BTreeMap::new();
+        for (name, handle) in &self.coordinators {
+            let courses = handle.get_courses().await?;
+            match name.as_str() {
+                DEFAULT_COORDINATOR => john = courses,
+                _ => {
+                    leads.insert(name.clone(), courses);
+                }
+            }
+        }
+        Ok(SavedState {
+            john,
+            leads,
+            brightspace: self.brightspace.get_courses().await?,
+            owners: self
+                .brightspace
+                .get_owners()
+                .await?
+                .into_iter()
+                .map(|(course, student, owner)| Ownership {
+                    course,
+                    student,
+                    coordinator: self.coordinator_name(owner),
+                })
+                .collect(),
+            admin: self.admin.get_courses().await?,
+            proposals: self.admin.get_proposals().await?,
+            history: self.admin.history().await?,
+            timelines: self.admin.timelines().await?,
+            attendance: self.attendance.get_meetings().await?,
+        })
+    }
+}
+
+fn load_state(path: &Path) -> anyhow::Result<SavedState> {
+    if !path.exists() {
+        return Ok(SavedState::default());
+    }
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a saved gradebook", path.display()))
+}
+
+fn write_state(path: &Path, state: &SavedState) -> anyhow::Result<()> {
+    // Note: write next to the real file and rename it over, so a crash never leaves half a gradebook behind
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("could not write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("could not write {}", path.display()))?;
+    Ok(())
+}
+
+/// Prints `rows` as a table with `headers` (see `table()`).
+pub(crate) fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    print!("{}", table(headers, rows));
+}
+
+/// `rows` as lines of a table with `headers`, padding every column to its widest cell.
+pub(crate) fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let mut text = line(headers.iter().map(|h| h.to_string()).collect());
+    text.push_str(&line(widths.iter().map(|w| "-".repeat(*w)).collect()));
+    for row in rows {
+        text.push_str(&line(row.clone()));
+    }
+    text
+}
+
+pub(crate) fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// What a command did to `names`, e.g. "registered Aarya Patel, Ben Ortiz", or that it did nothing.
+pub(crate) fn listed(done: &str, names: &[String]) -> String {
+    match names {
+        [] => format!("nothing {}", done),
+        names => format!("{} {}", done, names.join(", ")),
+    }
+}
+
+fn print_done(json: bool, message: String) -> anyhow::Result<()> {
+    if json {
+        print_json(&serde_json::json!({ "ok": true, "message": message }))
+    } else {
+        println!("{}", message);
+        Ok(())
+    }
+}
+
+pub(crate) fn print_students(json: bool, names: &[String], grades: &[Grade]) -> anyhow::Result<()> {
+    print!("{}", students(json, names, grades)?);
+    Ok(())
+}
+
+/// Students and their grades as a `NAME`/`GRADE` table, or as a JSON array of `{ "name", "grade" }`.
+pub(crate) fn students(json: bool, names: &[String], grades: &[Grade]) -> anyhow::Result<String> {
+    if json {
+        let rows: Vec<StudentRow> = names
+            .iter()
+            .zip(grades)
+            .map(|(name, grade)| StudentRow {
+                name,
+                grade: *grade,
+            })
+            .collect();
+        return Ok(format!("{}\n", serde_json::to_string_pretty(&rows)?));
+    }
+    let rows: Vec<Vec<String>> = names
+        .iter()
+        .zip(grades)
+        .map(|(name, grade)| vec![name.clone(), format!("{:.1}", grade)])
+        .collect();
+    Ok(table(&["NAME", "GRADE"], &rows))
+}
+
+fn proposal_row(proposal: &Proposal, status: String) -> Vec<String> {
+    let changes: Vec<String> = proposal
+        .changes
+        .iter()
+        .map(|c| format!("{} in {}: {} -> {}", c.name, c.course.course, c.from, c.to))
+        .collect();
+    vec![
+        format!("#{}", proposal.id),
+        status,
+        proposal.proposed_by.clone(),
+        changes.join("; "),
+        proposal.description.clone(),
+    ]
+}
+
+fn describe_outcome(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Accepted { by } => format!("accepted by {}", by),
+        Outcome::Rejected { by, reason } => format!("rejected by {} ({})", by, reason),
+        Outcome::Expired => "expired".to_string(),
+    }
+}
+
+/// Writes an export to `out`, or to stdout without one.
+fn write_export(out: Option<&Path>, text: &str) -> anyhow::Result<()> {
+    match out {
+        Some(path) => std::fs::write(path, text)
+            .with_context(|| format!("could not write {}", path.display())),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// `field` as a quoted CSV field.
+fn csv_field(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// How long ago `at` was, e.g. "3h ago".
+fn ago(at: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(at)
+        .unwrap_or_default()
+        .as_secs();
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// How late something was, e.g. "2d 3h".
+fn lateness(late_by: Duration) -> String {
+    let secs = late_by.as_secs();
+    match secs {
+        0..3600 => format!("{}m", secs.div_ceil(60)),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.2}", v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Fails if `john` is scoped to an archived term, where John would quietly ignore every change.
+async fn ensure_open(john: &JohnHandle) -> anyhow::Result<()> {
+    let courses = john.get_courses().await?;
+    let key = courses.key(john.scope());
+    if courses.is_archived(&key.term) {
+        anyhow::bail!("{} is archived, its rosters can no longer change", key.term);
+    }
+    Ok(())
+}
+
+/// Runs one non-demo subcommand against actors restored from `cli.state`, then saves them back.
+pub async fn run(cli: &Cli, command: &Command) -> anyhow::Result<()> {
+    if let Command::Restore { file } = command {
+        return restore_snapshot(cli, file).await;
+    }
+    let owner = Session::start(cli).await?;
+    let session = owner.acting_as(cli.caller()).scoped(&cli.scope());
+    execute(&session, command, cli.json).await?;
+    owner.persist(cli).await
+}
+
+/// Starts the actors from `cli.topology` afresh, loads and re-wires them from the snapshot in `file`, and
+/// saves them to `cli.state`, replacing whatever was saved there.
+async fn restore_snapshot(cli: &Cli, file: &Path) -> anyhow::Result<()> {
+    let snapshot = snapshot::read(file)?;
+    let topology = Topology::from_file(&cli.topology)?;
+    let actors = topology.start().await?;
+    snapshot::restore(&topology, &actors.acting_as(cli.caller()), &snapshot).await?;
+    Session::from_actors(&actors)?.persist(cli).await?;
+    print_done(
+        cli.json,
+        format!(
+            "restored {} from {}",
+            snapshot_names(&snapshot),
+            file.display()
+        ),
+    )
+}
+
+/// Every actor in `snapshot`, e.g. "john, brightspace, admin and booster".
+fn snapshot_names(snapshot: &SystemSnapshot) -> String {
+    let mut names: Vec<&str> = (snapshot.johns.keys())
+        .chain(snapshot.brightspaces.keys())
+        .chain(snapshot.admins.keys())
+        .chain(snapshot.boosters.keys())
+        .map(String::as_str)
+        .collect();
+    match names.pop() {
+        None => "no actors".to_string(),
+        Some(last) if names.is_empty() => last.to_string(),
+        Some(last) => format!("{} and {}", names.join(", "), last),
+    }
+}
+
+/// Runs one gradebook subcommand against an already running `session`.
+pub(crate) async fn execute(
+    session: &Session,
+    command: &Command,
+    json: bool,
+) -> anyhow::Result<()> {
+    match command {
+        Command::Demo | Command::Shell | Command::Serve { .. } | Command::Restore { .. } => {
+            anyhow::bail!("that command starts its own actors, run it on its own")
+        }
+        Command::Register { names } => {
+            ensure_open(&session.john).await?;
+            let existing = session.john.get_all_student_names().await?;
+            let mut registered = Vec::new();
+            for name in names {
+                if existing.contains(name) || registered.contains(name) {
+                    eprintln!("{} is already registered, skipping", name);
+                    continue;
+                }
+                session.john.register_new_student(name.clone()).await?;
+                registered.push(name.clone());
+            }
+            print_done(json, listed("registered", &registered))?;
+        }
+        Command::Remove { names } => {
+            ensure_open(&session.john).await?;
+            let existing = session.john.get_all_student_names().await?;
+            let mut removed = Vec::new();
+            for name in names {
+                if !existing.contains(name) || removed.contains(name) {
+                    eprintln!("{} is not registered with John, skipping", name);
+                    continue;
+                }
+                session.john.remove_student(name.clone()).await?;
+                removed.push(name.clone());
+            }
+            print_done(json, listed("removed", &removed))?;
+        }
+        Command::Grade { name, grade } => {
+            ensure_open(&session.john).await?;
+            if !session.john.get_all_student_names().await?.contains(name) {
+                anyhow::bail!("{} is not registered with John", name);
+            }
+            session
+                .john
+                .assign_grade_to_student(name.clone(), *grade)
+                .await?;
+            print_done(json, format!("{} now has {:.1}", name, grade))?;
+        }
+        Command::Submit {
+            name,
+            assessment,
+            grade,
+            at,
+        } => {
+            ensure_open(&session.john).await?;
+            let submitted_at = at.unwrap_or_else(Timestamp::now);
+            let assessed = session
+                .john
+                .record_submission(name.clone(), assessment.clone(), *grade, submitted_at.0)
+                .await?;
+            let message = match &assessed.late {
+                Some(late) => format!(
+                    "{} now has {:.1}: {} came in {} late, {:.1} less {}%",
+                    name,
+                    assessed.grade,
+                    assessment,
+                    lateness(late.late_by),
+                    late.raw,
+                    late.penalty_percent
+                ),
+                None => format!(
+                    "{} now has {:.1} ({} on time)",
+                    name, assessed.grade, assessment
+                ),
+            };
+            print_done(json, message)?;
+        }
+        Command::RubricGrade {
+            name,
+            rubric,
+            selections,
+        } => {
+            ensure_open(&session.john).await?;
+            let score = session
+                .john
+                .grade_with_rubric(name.clone(), rubric.clone(), selections.clone())
+                .await?;
+            print_done(
+                json,
+                format!(
+                    "{} now has {:.1} ({} of {} points on {})",
+                    name,
+                    score.grade(),
+                    score.points,
+                    score.max_points,
+                    rubric
+                ),
+            )?;
+        }
+        Command::Comment {
+            name,
+            text,
+            bank,
+            clear,
+        } => {
+            ensure_open(&session.john).await?;
+            if *clear {
+                session.john.clear_comments(name.clone()).await?;
+                print_done(json, format!("{}'s grade has no feedback now", name))?;
+                return Ok(());
+            }
+            let text = match (text, bank) {
+                (_, Some(key)) => CommentText::Bank(key.clone()),
+                (Some(text), None) => CommentText::Written(text.clone()),
+                (None, None) => anyhow::bail!("give the comment, or --bank <key>"),
+            };
+            let comment = session.john.comment_on(name.clone(), text).await?;
+            print_done(
+                json,
+                format!(
+                    "{}'s {:.1} now reads: {}",
+                    name, comment.grade, comment.text
+                ),
+            )?;
+        }
+        Command::Meeting { marks, at } => {
+            let held_at = at.unwrap_or_else(Timestamp::now);
+            let meeting = session
+                .attendance
+                .record_meeting(held_at.0, marks.clone())
+                .await?;
+            print_done(
+                json,
+                format!(
+                    "meeting #{} at {} recorded with {} student(s)",
+                    meeting,
+                    held_at,
+                    marks.len()
+                ),
+            )?;
+        }
+        Command::Mark {
+            meeting,
+            name,
+            status,
+        } => {
+            let mark = Mark::new(name.clone(), *status);
+            session.attendance.mark(*meeting, mark).await?;
+            print_done(
+                json,
+                format!("{} was {} at meeting #{}", name, status, meeting),
+            )?;
+        }
+        Command::Participation { submit } => {
+            let scores = match submit {
+                true => {
+                    ensure_open(&session.john).await?;
+                    session.attendance.submit_participation().await?
+                }
+                false => session.attendance.participation().await?,
+            };
+            if json {
+                print_json(&scores)?;
+            } else {
+                let rows: Vec<Vec<String>> = scores
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            p.course.to_string(),
+                            p.student.clone(),
+                            p.present.to_string(),
+                            p.late.to_string(),
+                            p.absent.to_string(),
+                            p.excused.to_string(),
+                            format!("{:.1}", p.grade),
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    &[
+                        "COURSE",
+                        "STUDENT",
+                        "PRESENT",
+                        "LATE",
+                        "ABSENT",
+                        "EXCUSED",
+                        "PARTICIPATION",
+                    ],
+                    &rows,
+                );
+                if *submit {
+                    println!("{} participation grade(s) sent to John", scores.len());
+                }
+            }
+        }
+        Command::Sync => {
+            // Note: Brightspace merges each coordinator's students, so the order they report in does not matter
+            let known = session.brightspace.get_conflicts().await?.len();
+            for john in session.coordinators.values() {
+                john.report_all_students_and_grades_to_brightspace().await?;
+            }
+            session
+                .brightspace
+                .report_all_students_and_grades_to_admin()
+                .await?;
+            let from = match session.coordinators.len() {
+                1 => "John".to_string(),
+                _ => session
+                    .coordinators
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            for c in session.conflict_report().await?.iter().skip(known) {
+                eprintln!(
+                    "[CONFLICT]: {} in {} ({}): kept {}'s {}, not {}'s {}",
+                    c.student, c.course, c.term, c.owner, c.owner_grade, c.rival, c.rival_grade
+                );
+            }
+            print_done(json, format!("{} -> Brightspace -> Admin synced", from))?;
+        }
+        Command::CareerIds => {
+            session
+                .brightspace
+                .generate_and_append_student_career_id()
+                .await?;
+            session
+                .brightspace
+                .report_all_students_and_grades_to_admin()
+                .await?;
+            print_done(json, "career IDs appended and sent to Admin".to_string())?;
+        }
+        Command::Boost { policy } => {
+            let id = session.booster.boost_grades_with(*policy).await?;
+            print_done(json, format!("proposal #{} is waiting for approval", id))?;
+        }
+        Command::Proposals { all } => {
+            let book = session.admin.get_proposals().await?;
+            if json {
+                if *all {
+                    print_json(&book)?;
+                } else {
+                    print_json(&book.pending)?;
+                }
+            } else {
+                let mut rows: Vec<Vec<String>> = book
+                    .pending
+                    .iter()
+                    .map(|p| proposal_row(p, "pending".to_string()))
+                    .collect();
+                if *all {
+                    rows.extend(
+                        book.decided
+                            .iter()
+                            .map(|d| proposal_row(&d.proposal, describe_outcome(&d.outcome))),
+                    );
+                }
+                print_table(&["ID", "STATUS", "BY", "CHANGES", "DESCRIPTION"], &rows);
+            }
+        }
+        Command::Approve { id } => {
+            session.admin.accept_proposal(*id).await?;
+            print_done(json, format!("proposal #{} accepted and applied", id))?;
+        }
+        Command::Reject { id, reason } => {
+            session.admin.reject_proposal(*id, reason.clone()).await?;
+            print_done(json, format!("proposal #{} rejected", id))?;
+        }
+        Command::Undo { push } | Command::Redo { push } => {
+            let (revision, done) = match command {
+                Command::Undo { .. } => (session.admin.undo().await?, "undid"),
+                _ => (session.admin.redo().await?, "redid"),
+            };
+            if *push {
+                session.push_to_brightspace(&revision).await?;
+            }
+            let pushed = if *push {
+                " (pushed to Brightspace)"
+            } else {
+                ""
+            };
+            print_done(
+                json,
+                format!("{}: {}{}", done, revision.description, pushed),
+            )?;
+        }
+        Command::History => {
+            let history = session.admin.history().await?;
+            if json {
+                print_json(&history)?;
+            } else {
+                // Note: newest first, the way `undo` and `redo` walk through them
+                let undone = history.undone.iter().map(|e| (e, "undone"));
+                let done = history.done.iter().rev().map(|e| (e, "done"));
+                let rows: Vec<Vec<String>> = undone
+                    .chain(done)
+                    .map(|(entry, status)| {
+                        vec![
+                            entry.id.to_string(),
+                            status.to_string(),
+                            entry.description.clone(),
+                        ]
+                    })
+                    .collect();
+                print_table(&["ID", "STATUS", "DESCRIPTION"], &rows);
+            }
+        }
+        Command::Reconcile { fix, truth } => {
+            let sides = Sides {
+                johns: &session.coordinators,
+                lead: &session.john,
+                brightspace: &session.brightspace,
+                admin: &session.admin,
+            };
+            let mismatches = match fix {
+                true => reconcile::fix(&sides, *truth).await?,
+                false => reconcile::check(&sides).await?,
+            };
+            if json {
+                print_json(&mismatches)?;
+            } else {
+                let show = |held: &Option<String>| held.clone().unwrap_or_else(|| "-".to_string());
+                let rows: Vec<Vec<String>> = mismatches
+                    .iter()
+                    .map(|m| {
+                        vec![
+                            m.course.to_string(),
+                            m.student.clone(),
+                            m.kind.to_string(),
+                            show(&m.john),
+                            show(&m.brightspace),
+                            show(&m.admin),
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    &[
+                        "COURSE",
+                        "STUDENT",
+                        "MISMATCH",
+                        "JOHN",
+                        "BRIGHTSPACE",
+                        "ADMIN",
+                    ],
+                    &rows,
+                );
+                if *fix && !mismatches.is_empty() {
+                    println!("fixed, {}", truth);
+                }
+            }
+        }
+        Command::GradeHistory { name } => {
+            let history = session.admin.grade_history(name).await?;
+            if json {
+                print_json(&history)?;
+            } else {
+                let rows: Vec<Vec<String>> = history
+                    .points
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            ago(p.at),
+                            p.course.to_string(),
+                            format!("{:.1}", p.grade),
+                            p.set_by.to_string(),
+                        ]
+                    })
+                    .collect();
+                print_table(&["WHEN", "COURSE", "GRADE", "SET BY"], &rows);
+                if history.trends.is_empty() {
+                    println!("trend: not enough scores yet");
+                }
+                for CourseTrend { course, trend } in &history.trends {
+                    println!(
+                        "trend in {}: {:+.1} points overall, {} per week",
+                        course,
+                        trend.change,
+                        trend
+                            .per_week
+                            .map(|w| format!("{:+.1}", w))
+                            .unwrap_or_else(|| "-".to_string())
+                    );
+                }
+            }
+        }
+        Command::Portal { career_id } => {
+            let record = match career_id {
+                Some(career_id) => session.portal.look_up(career_id).await?,
+                None => session.portal.my_record().await?,
+            };
+            if json {
+                print_json(&record)?;
+            } else {
+                match record {
+                    Some(r) => {
+                        let rows: Vec<Vec<String>> = r
+                            .courses
+                            .iter()
+                            .map(|c| {
+                                vec![
+                                    r.name.clone(),
+                                    r.career_id.clone(),
+                                    c.course.to_string(),
+                                    format!("{:.1}", c.grade),
+                                    c.letter.map(String::from).unwrap_or_default(),
+                                ]
+                            })
+                            .collect();
+                        print_table(&["NAME", "CAREER ID", "COURSE", "GRADE", "LETTER"], &rows);
+                        for c in &r.courses {
+                            for comment in &c.feedback {
+                                println!("  - {}: {}", c.course, comment);
+                            }
+                        }
+                    }
+                    None => println!("the portal has no grade for that career ID yet"),
+                }
+            }
+        }
+        Command::AtRisk => {
+            let at_risk = session.alerter.get_at_risk().await?;
+            if json {
+                let rows: Vec<_> = at_risk
+                    .iter()
+                    .map(|(course, name, grade, reason)| {
+                        serde_json::json!({
+                            "course": course,
+                            "name": name,
+                            "grade": grade,
+                            "reason": reason,
+                        })
+                    })
+                    .collect();
+                print_json(&rows)?;
+            } else {
+                let rows: Vec<Vec<String>> = at_risk
+                    .iter()
+                    .map(|(course, name, grade, reason)| {
+                        vec![
+                            course.to_string(),
+                            name.clone(),
+                            format!("{:.1}", grade),
+                            reason.to_string(),
+                        ]
+                    })
+                    .collect();
+                print_table(&["COURSE", "NAME", "GRADE", "REASON"], &rows);
+            }
+        }
+        Command::Alerts => {
+            let alerts = session.alerter.get_alerts().await?;
+            if json {
+                print_json(&alerts)?;
+            } else {
+                let rows: Vec<Vec<String>> = alerts
+                    .iter()
+                    .map(|a| {
+                        vec![
+                            a.course.to_string(),
+                            a.student.clone(),
+                            format!("{:.1}", a.grade),
+                            a.reason.to_string(),
+                        ]
+                    })
+                    .collect();
+                print_table(&["COURSE", "NAME", "GRADE", "REASON"], &rows);
+            }
+        }
+        Command::Conflicts => {
+            let conflicts = session.conflict_report().await?;
+            if json {
+                print_json(&conflicts)?;
+            } else {
+                let rows: Vec<Vec<String>> = conflicts
+                    .iter()
+                    .map(|c| {
+                        vec![
+                            format!("{} ({})", c.course, c.term),
+                            c.student.clone(),
+                            format!("{} ({:.1})", c.owner, c.owner_grade),
+                            format!("{} ({:.1})", c.rival, c.rival_grade),
+                        ]
+                    })
+                    .collect();
+                print_table(&["COURSE", "STUDENT", "KEPT", "NOT TAKEN"], &rows);
+            }
+        }
+        Command::Rollover { term } => {
+            let previous = session.roll_over(term.clone()).await?;
+            print_done(
+                json,
+                format!("archived {}, {} is the current term", previous, term),
+            )?;
+        }
+        Command::Courses => {
+            let courses = session.admin.get_courses().await?;
+            let rows: Vec<CourseRow> = courses
+                .rosters()
+                .map(|(key, roster)| CourseRow {
+                    course: &key.course,
+                    term: &key.term,
+                    students: roster.names.len(),
+                    mean: GradeStatistics::of(&roster.grades).mean,
+                    status: if courses.is_archived(&key.term) {
+                        "archived"
+                    } else if key.term == *courses.term() {
+                        "current"
+                    } else {
+                        "upcoming"
+                    },
+                })
+                .collect();
+            if json {
+                print_json(&rows)?;
+            } else {
+                let rows: Vec<Vec<String>> = rows
+                    .iter()
+                    .map(|row| {
+                        vec![
+                            row.course.to_string(),
+                            row.term.to_string(),
+                            row.students.to_string(),
+                            format_optional(row.mean),
+                            row.status.to_string(),
+                        ]
+                    })
+                    .collect();
+                print_table(&["COURSE", "TERM", "STUDENTS", "MEAN", "STATUS"], &rows);
+            }
+        }
+        Command::Stats => {
+            let stats = session.admin.get_statistics().await?;
+            if json {
+                print_json(&stats)?;
+            } else {
+                print_table(
+                    &["STATISTIC", "VALUE"],
+                    &[
+                        vec!["scored".to_string(), stats.count.to_string()],
+                        vec!["mean".to_string(), format_optional(stats.mean)],
+                        vec!["median".to_string(), format_optional(stats.median)],
+                        vec!["min".to_string(), format_optional(stats.min)],
+                        vec!["max".to_string(), format_optional(stats.max)],
+                        vec!["failing".to_string(), stats.failing.to_string()],
+                        vec!["ungraded".to_string(), stats.ungraded.to_string()],
+                        vec!["incomplete".to_string(), stats.incomplete.to_string()],
+                        vec!["excused".to_string(), stats.excused.to_string()],
+                    ],
+                );
+            }
+        }
+        Command::Failing => {
+            let failing = session.admin.get_failing_students().await?;
+            let (names, grades): (Vec<String>, Vec<Grade>) = failing.into_iter().unzip();
+            print_students(json, &names, &grades)?;
+        }
+        Command::Late => {
+            let reports = session.admin.get_late_work().await?;
+            if json {
+                print_json(&reports)?;
+            } else {
+                let rows: Vec<Vec<String>> = reports
+                    .iter()
+                    .map(|r| {
+                        vec![
+                            r.course.to_string(),
+                            r.student.clone(),
+                            r.work.assessment.clone(),
+                            Timestamp(r.work.submitted_at).to_string(),
+                            lateness(r.work.late_by),
+                            format!("{}%", r.work.penalty_percent),
+                            format!("{:.1}", r.work.raw),
+                            format!("{:.1}", r.grade),
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    &[
+                        "COURSE",
+                        "STUDENT",
+                        "ASSESSMENT",
+                        "SUBMITTED",
+                        "LATE BY",
+                        "PENALTY",
+                        "RAW",
+                        "GRADE",
+                    ],
+                    &rows,
+                );
+            }
+        }
+        Command::Feedback { name } => {
+            let mut reports = session.admin.get_rubric_scores().await?;
+            if let Some(name) = name {
+                reports.retain(|r| {
+                    student_of(&r.student) == name
+                        || brightspace::career_id(student_of(&r.student)).as_deref() == Some(name)
+                });
+            }
+            if json {
+                print_json(&reports)?;
+            } else {
+                let mut rows = Vec::new();
+                for r in &reports {
+                    for c in &r.score.criteria {
+                        rows.push(vec![
+                            r.course.to_string(),
+                            r.student.clone(),
+                            r.score.rubric.clone(),
+                            c.criterion.clone(),
+                            c.level.clone(),
+                            format!("{}/{}", c.points, c.max_points),
+                        ]);
+                    }
+                    rows.push(vec![
+                        r.course.to_string(),
+                        r.student.clone(),
+                        r.score.rubric.clone(),
+                        "total".to_string(),
+                        format!("grade {:.1}", r.grade),
+                        format!("{}/{}", r.score.points, r.score.max_points),
+                    ]);
+                }
+                print_table(
+                    &[
+                        "COURSE",
+                        "STUDENT",
+                        "RUBRIC",
+                        "CRITERION",
+                        "LEVEL",
+                        "POINTS",
+                    ],
+                    &rows,
+                );
+            }
+        }
+        Command::Comments { name } => {
+            let mut reports = session.admin.get_feedback().await?;
+            if let Some(name) = name {
+                reports.retain(|r| {
+                    student_of(&r.student) == name
+                        || brightspace::career_id(student_of(&r.student)).as_deref() == Some(name)
+                });
+            }
+            if json {
+                print_json(&reports)?;
+            } else {
+                let rows: Vec<Vec<String>> = reports
+                    .iter()
+                    .flat_map(|r| {
+                        r.comments.iter().map(|c| {
+                            vec![
+                                r.course.to_string(),
+                                r.student.clone(),
+                                format!("{:.1}", c.grade),
+                                ago(c.at),
+                                c.template.clone().unwrap_or_else(|| "-".to_string()),
+                                c.text.clone(),
+                            ]
+                        })
+                    })
+                    .collect();
+                print_table(
+                    &["COURSE", "STUDENT", "ON", "WHEN", "BANK", "COMMENT"],
+                    &rows,
+                );
+            }
+        }
+        Command::Snapshot { out } => {
+            let snapshot = snapshot::take(&session.actors).await?;
+            snapshot::write(out, &snapshot)?;
+            print_done(
+                json,
+                format!(
+                    "snapshot of {} written to {}",
+                    snapshot_names(&snapshot),
+                    out.display()
+                ),
+            )?;
+        }
+        Command::Export {
+            format,
+            from: ExportSource::Brightspace,
+            out,
+        } => {
+            let rows = session.brightspace.lms_export().await?;
+            let text = match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&rows)?,
+                ExportFormat::Csv => {
+                    let mut csv = String::from("course,name,grade,feedback\n");
+                    for row in &rows {
+                        csv.push_str(&format!(
+                            "{},{},{},{}\n",
+                            csv_field(&row.course.to_string()),
+                            csv_field(&row.student),
+                            row.grade,
+                            csv_field(&row.feedback.join("\n"))
+                        ));
+                    }
+                    csv
+                }
+            };
+            write_export(out.as_deref(), &text)?;
+        }
+        Command::Export {
+            format,
+            from: ExportSource::Admin,
+            out,
+        } => {
+            let names = session.admin.get_all_student_names().await?;
+            let grades = session.admin.get_all_student_grades().await?;
+            let text = match format {
+                ExportFormat::Json => {
+                    let rows: Vec<StudentRow> = names
+                        .iter()
+                        .zip(&grades)
+                        .map(|(name, grade)| StudentRow {
+                            name,
+                            grade: *grade,
+                        })
+                        .collect();
+                    serde_json::to_string_pretty(&rows)?
+                }
+                ExportFormat::Csv => {
+                    let mut csv = String::from("name,grade\n");
+                    for (name, grade) in names.iter().zip(&grades) {
+                        csv.push_str(&format!("{},{}\n", csv_field(name), grade));
+                    }
+                    csv
+                }
+            };
+            write_export(out.as_deref(), &text)?;
+        }
+    }
+
+    Ok(())
+}
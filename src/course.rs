@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::deadline::LateWork;
+use crate::events::student_of;
+use crate::feedback::Comment;
+use crate::grade::Grade;
+use crate::rubric::RubricScore;
+
+// ###################################################### //
+// ################# COURSES AND TERMS ################## //
+// ###################################################### //
+
+/// The course every roster from before there were courses belongs to, and the one a handle that was
+/// never scoped with `in_course()` writes to.
+pub const DEFAULT_COURSE: &str = "PART";
+
+/// The term every actor starts in, until the first `roll_over()`.
+pub const DEFAULT_TERM: &str = "default";
+
+/// A semester, e.g. `2026-fall`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Term(String);
+
+impl Default for Term {
+    fn default() -> Self {
+        Term(DEFAULT_TERM.to_string())
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Term {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("a term needs a name, e.g. 2026-fall".to_string());
+        }
+        Ok(Term(s.to_string()))
+    }
+}
+
+/// A course, and optionally one section of it, e.g. `VIP-UAV/02` (the UAV sub-team's second section).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Course {
+    pub name: String,
+    pub section: Option<String>,
+}
+
+impl Course {
+    pub fn new(name: impl Into<String>) -> Self {
+        Course {
+            name: name.into(),
+            section: None,
+        }
+    }
+
+    pub fn section(self, section: impl Into<String>) -> Self {
+        Course {
+            section: Some(section.into()),
+            ..self
+        }
+    }
+}
+
+impl Default for Course {
+    fn default() -> Self {
+        Course::new(DEFAULT_COURSE)
+    }
+}
+
+impl fmt::Display for Course {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.section {
+            Some(section) => write!(f, "{}/{}", self.name, section),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Parses `NAME` or `NAME/SECTION`.
+impl FromStr for Course {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, section) = match s.trim().split_once('/') {
+            Some((name, section)) => (name.trim(), Some(section.trim())),
+            None => (s.trim(), None),
+        };
+        if name.is_empty() || section.is_some_and(str::is_empty) {
+            return Err(format!(
+                "`{}` is not a course, expected NAME or NAME/SECTION",
+                s
+            ));
+        }
+        Ok(Course {
+            name: name.to_string(),
+            section: section.map(str::to_string),
+        })
+    }
+}
+
+// Note: a course is written the same way everywhere, "VIP-UAV/02" on the command line and in JSON alike
+impl Serialize for Course {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Course {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which roster something is about: one course (or section) in one term.
+///  - Note: terms sort first, so every course of a term sits together.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CourseKey {
+    pub term: Term,
+    pub course: Course,
+}
+
+impl fmt::Display for CourseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.course, self.term)
+    }
+}
+
+/// What a handle's calls are about (see `in_course()` and `in_term()` on every roster-holding handle).
+///  - Note: the default scope is every course of the actor's current term; writes without a course go to
+///    `DEFAULT_COURSE`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scope {
+    pub course: Option<Course>,
+    pub term: Option<Term>,
+}
+
+impl Scope {
+    /// Exactly the roster under `key`.
+    pub fn of(key: &CourseKey) -> Self {
+        Scope {
+            course: Some(key.course.clone()),
+            term: Some(key.term.clone()),
+        }
+    }
+
+    /// Whether the roster under `key` is one of the rosters this scope covers, while `current` is the current term.
+    pub(crate) fn includes(&self, key: &CourseKey, current: &Term) -> bool {
+        key.term == *self.term.as_ref().unwrap_or(current)
+            && self
+                .course
+                .as_ref()
+                .is_none_or(|course| *course == key.course)
+    }
+}
+
+/// Names and grades side by side, e.g. one course's roster.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Roster {
+    pub names: Vec<String>,
+    pub grades: Vec<Grade>,
+    /// The late penalty behind each student's grade, if their last graded work came in late, by name (without any
+    /// career ID); the grade in `grades` is the one after the penalty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub late: BTreeMap<String, LateWork>,
+    /// The rubric score behind each student's grade, if they were last graded on a rubric, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rubrics: BTreeMap<String, RubricScore>,
+    /// The written feedback on each student's grade, oldest first, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub comments: BTreeMap<String, Vec<Comment>>,
+}
+
+impl Roster {
+    /// Takes `corrected`'s names and grades, keeping the late penalties, rubric scores and feedback of the
+    /// students still on it (see `reconcile::fix()`).
+    pub(crate) fn correct(&mut self, corrected: Roster) {
+        let kept = |student: &String| {
+            corrected
+                .names
+                .iter()
+                .any(|name| student_of(name) == student)
+        };
+        let mut late = std::mem::take(&mut self.late);
+        late.retain(|student, _| kept(student));
+        let mut rubrics = std::mem::take(&mut self.rubrics);
+        rubrics.retain(|student, _| kept(student));
+        let mut comments = std::mem::take(&mut self.comments);
+        comments.retain(|student, _| kept(student));
+        *self = Roster {
+            late,
+            rubrics,
+            comments,
+            ..corrected
+        };
+    }
+}
+
+/// Every roster an actor holds, keyed by course and term, plus which term is current.
+///  - Note: rolling over to a new term archives the old one: its rosters can still be read, but no
+///    longer changed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Courses {
+    term: Term,
+    archived: BTreeSet<Term>,
+    rosters: BTreeMap<CourseKey, Roster>,
+}
+
+impl Courses {
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+
+    pub fn is_archived(&self, term: &Term) -> bool {
+        self.archived.contains(term)
+    }
+
+    pub fn archived(&self) -> impl Iterator<Item = &Term> {
+        self.archived.iter()
+    }
+
+    /// Every roster, term by term.
+    pub fn rosters(&self) -> impl Iterator<Item = (&CourseKey, &Roster)> {
+        self.rosters.iter()
+    }
+
+    /// Every roster that can still change, i.e. every one outside an archived term.
+    pub fn open_rosters(&self) -> impl Iterator<Item = (&CourseKey, &Roster)> {
+        self.rosters
+            .iter()
+            .filter(|(key, _)| !self.is_archived(&key.term))
+    }
+
+    /// Exactly the roster under `key`, if there is one.
+    pub fn roster(&self, key: &CourseKey) -> Option<&Roster> {
+        self.rosters.get(key)
+    }
+
+    /// Replaces the roster under `key` with `roster`, or removes it if `None` (see `History`).
+    pub(crate) fn put(&mut self, key: CourseKey, roster: Option<Roster>) {
+        match roster {
+            Some(roster) => self.rosters.insert(key, roster),
+            None => self.rosters.remove(&key),
+        };
+    }
+
+    /// The one roster a write through `scope` goes to.
+    pub fn key(&self, scope: &Scope) -> CourseKey {
+        CourseKey {
+            term: scope.term.clone().unwrap_or_else(|| self.term.clone()),
+            course: scope.course.clone().unwrap_or_default(),
+        }
+    }
+
+    /// The roster a write through `scope` changes, made empty if it does not exist yet.
+    ///  - Returns the archived term instead if `scope` is about one.
+    pub fn roster_mut(&mut self, scope: &Scope) -> Result<&mut Roster, Term> {
+        let key = self.key(scope);
+        if self.is_archived(&key.term) {
+            return Err(key.term);
+        }
+        Ok(self.rosters.entry(key).or_default())
+    }
+
+    /// Every roster `scope` covers, one after the other in course order.
+    pub fn view(&self, scope: &Scope) -> Roster {
+        let mut view = Roster::default();
+        let rosters = self.rosters.iter();
+        for (_, roster) in rosters.filter(|(key, _)| scope.includes(key, &self.term)) {
+            view.names.extend(roster.names.iter().cloned());
+            // Note: a roster can have names before it has grades, and those names are ungraded
+            let mut grades = roster.grades.clone();
+            grades.resize(roster.names.len(), Grade::Ungraded);
+            view.grades.extend(grades);
+            for (student, comments) in &roster.comments {
+                let all = view.comments.entry(student.clone()).or_default();
+                all.extend(comments.iter().cloned());
+            }
+        }
+        view
+    }
+
+    /// Every roster `scope` covers, mutably, or none at all if `scope` is about an archived term.
+    pub fn view_mut(&mut self, scope: &Scope) -> impl Iterator<Item = &mut Roster> {
+        let open = !self.is_archived(&self.key(scope).term);
+        let current = &self.term;
+        self.rosters
+            .iter_mut()
+            .filter(move |(key, _)| open && scope.includes(key, current))
+            .map(|(_, roster)| roster)
+    }
+
+    /// Archives the current term and makes `term` current, returning the term that was archived.
+    pub fn roll_over(&mut self, term: Term) -> Result<Term, String> {
+        if term == self.term {
+            return Err(format!("{} is already the current term", term));
+        }
+        if self.is_archived(&term) {
+            return Err(format!("{} is archived, terms only roll forward", term));
+        }
+        let previous = std::mem::replace(&mut self.term, term);
+        self.archived.insert(previous.clone());
+        Ok(previous)
+    }
+}
+
+/// One roster as it is stored on disk.
+#[derive(Serialize, Deserialize)]
+struct CourseRoster {
+    #[serde(flatten)]
+    key: CourseKey,
+    #[serde(flatten)]
+    roster: Roster,
+}
+
+/// How `Courses` look in JSON: every roster with its key, or (in gradebooks saved before there were
+/// courses) a single roster, which belongs to `DEFAULT_COURSE` in `DEFAULT_TERM`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CoursesRepr {
+    Courses {
+        term: Term,
+        #[serde(default)]
+        archived: Vec<Term>,
+        rosters: Vec<CourseRoster>,
+    },
+    Flat(Roster),
+}
+
+impl Serialize for Courses {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Repr<'a> {
+            term: &'a Term,
+            archived: &'a BTreeSet<Term>,
+            rosters: Vec<CourseRoster>,
+        }
+        Repr {
+            term: &self.term,
+            archived: &self.archived,
+            rosters: self
+                .rosters
+                .iter()
+                .map(|(key, roster)| CourseRoster {
+                    key: key.clone(),
+                    roster: roster.clone(),
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Courses {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let courses = match CoursesRepr::deserialize(deserializer)? {
+            CoursesRepr::Courses {
+                term,
+                archived,
+                rosters,
+            } => Courses {
+                term,
+                archived: archived.into_iter().collect(),
+                rosters: rosters.into_iter().map(|r| (r.key, r.roster)).collect(),
+            },
+            CoursesRepr::Flat(roster) => {
+                let mut courses = Courses::default();
+                if roster != Roster::default() {
+                    courses.rosters.insert(CourseKey::default(), roster);
+                }
+                courses
+            }
+        };
+        Ok(courses)
+    }
+}
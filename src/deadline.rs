@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::course::CourseKey;
+use crate::events::student_of;
+use crate::grade::Grade;
+
+// ###################################################### //
+// ############ DEADLINES AND LATE PENALTIES ############ //
+// ###################################################### //
+
+const HOUR: f64 = 60.0 * 60.0;
+const DAY: f64 = 24.0 * HOUR;
+
+/// A point in time as it is written in `topology.toml` and on the command line, always in UTC,
+/// e.g. `2026-10-20T23:59:00Z` (the seconds and the `Z` are optional, and a space works instead of the `T`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub SystemTime);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Timestamp(SystemTime::now())
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date `days` after 1970-01-01, as (year, month, day).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .0
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let (year, month, day) = civil_from_days(secs / 86400);
+        let time = secs % 86400;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        )
+    }
+}
+
+/// Parses `YYYY-MM-DDTHH:MM[:SS][Z]`, in UTC.
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "`{}` is not a timestamp, expected YYYY-MM-DDTHH:MM[:SS] in UTC, e.g. 2026-10-20T23:59:00Z",
+                s
+            )
+        };
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_suffix(['Z', 'z']).unwrap_or(trimmed);
+        let (date, time) = trimmed.split_once(['T', 't', ' ']).ok_or_else(invalid)?;
+        let number = |part: &str| part.parse::<i64>().map_err(|_| invalid());
+
+        let date: Vec<i64> = date.split('-').map(number).collect::<Result<_, _>>()?;
+        let time: Vec<i64> = time.split(':').map(number).collect::<Result<_, _>>()?;
+        let (&[year, month, day], &[hour, minute, ref second @ ..]) = (&date[..], &time[..]) else {
+            return Err(invalid());
+        };
+        let second = match second {
+            [] => 0,
+            [second] => *second,
+            _ => return Err(invalid()),
+        };
+        let days = days_from_civil(year, month, day);
+        // Note: a date like Feb 30 comes back as another day, so it is caught by converting back
+        if civil_from_days(days) != (year, month, day)
+            || !(0..24).contains(&hour)
+            || !(0..60).contains(&minute)
+            || !(0..60).contains(&second)
+            || days < 0
+        {
+            return Err(invalid());
+        }
+        let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+        Ok(Timestamp(UNIX_EPOCH + Duration::from_secs(secs as u64)))
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// How late work is marked down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatePolicy {
+    /// What late work loses for every day (or part of a day) it is late, in percent of its raw score.
+    pub percent_per_day: f64,
+    /// How late work can be before it counts as late at all, in hours.
+    pub grace_hours: f64,
+    /// Work later than this many days scores 0 (`None` takes work however late it is).
+    pub max_days_late: Option<f64>,
+}
+
+impl Default for LatePolicy {
+    fn default() -> Self {
+        LatePolicy {
+            percent_per_day: 10.0,
+            grace_hours: 0.0,
+            max_days_late: None,
+        }
+    }
+}
+
+/// One piece of graded work and when it is due.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Assessment {
+    pub name: String,
+    pub due: Timestamp,
+    /// Extra days individual students were given, by name (without any career ID).
+    #[serde(default)]
+    pub extensions: BTreeMap<String, f64>,
+}
+
+/// The `[deadlines]` table: every assessment, and the policy late work on any of them is marked down by.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Deadlines {
+    #[serde(flatten)]
+    pub policy: LatePolicy,
+    #[serde(default)]
+    pub assessments: Vec<Assessment>,
+}
+
+/// Why a student's grade is lower than what their work scored: it came in late.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LateWork {
+    pub assessment: String,
+    pub submitted_at: SystemTime,
+    /// How long after the due date (plus any extension) the work came in.
+    pub late_by: Duration,
+    /// How much of the raw score was taken off, 100 for work past the maximum lateness.
+    pub penalty_percent: f64,
+    /// What the work scored before the penalty.
+    pub raw: Grade,
+}
+
+/// What a submission is worth once the late policy has been applied (see `Deadlines::assess()`).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Assessed {
+    pub grade: Grade,
+    /// Why `grade` is lower than the raw grade, `None` for work that was on time.
+    pub late: Option<LateWork>,
+}
+
+/// One late submission in Admin: the grade the student has, and what it was before the penalty
+/// (see `AdminHandle::get_late_work()`).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LateReport {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub grade: Grade,
+    #[serde(flatten)]
+    pub work: LateWork,
+}
+
+impl Deadlines {
+    /// The first problem with the policy or the assessments, e.g. a negative grace period or a name used twice.
+    pub fn validate(&self) -> Result<(), String> {
+        let policy = &self.policy;
+        let non_negative = [
+            ("percent_per_day", Some(policy.percent_per_day)),
+            ("grace_hours", Some(policy.grace_hours)),
+            ("max_days_late", policy.max_days_late),
+        ];
+        for (field, value) in non_negative {
+            if value.is_some_and(|value| !(value >= 0.0 && value.is_finite())) {
+                return Err(format!("`{}` must be a number of at least 0", field));
+            }
+        }
+
+        let mut names = BTreeSet::new();
+        for assessment in &self.assessments {
+            if !names.insert(&assessment.name) {
+                return Err(format!(
+                    "assessment `{}` is declared more than once",
+                    assessment.name
+                ));
+            }
+            if let Some((student, _)) = assessment
+                .extensions
+                .iter()
+                .find(|(_, days)| !(**days >= 0.0 && days.is_finite()))
+            {
+                return Err(format!(
+                    "{}'s extension on `{}` must be a number of days of at least 0",
+                    student, assessment.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn assessment(&self, name: &str) -> Option<&Assessment> {
+        self.assessments.iter().find(|a| a.name == name)
+    }
+
+    /// What `student`'s work on `assessment`, which scored `raw`, is worth when it came in at `submitted_at`.
+    ///  - Note: only scores are marked down, work that is incomplete or excused stays that way however late it is.
+    pub fn assess(
+        &self,
+        assessment: &str,
+        student: &str,
+        raw: Grade,
+        submitted_at: SystemTime,
+    ) -> Result<Assessed, String> {
+        let assessment = self
+            .assessment(assessment)
+            .ok_or_else(|| format!("there is no assessment called `{}`", assessment))?;
+        let extension = assessment
+            .extensions
+            .get(student_of(student))
+            .copied()
+            .unwrap_or(0.0);
+        let due =
+            assessment.due.0 + Duration::try_from_secs_f64(extension * DAY).unwrap_or_default();
+
+        let on_time = Assessed {
+            grade: raw,
+            late: None,
+        };
+        let Ok(late_by) = submitted_at.duration_since(due) else {
+            return Ok(on_time);
+        };
+        if late_by.as_secs_f64() <= self.policy.grace_hours * HOUR {
+            return Ok(on_time);
+        }
+
+        let days_late = (late_by.as_secs_f64() / DAY).ceil();
+        let penalty_percent = match self.policy.max_days_late {
+            Some(max) if late_by.as_secs_f64() > max * DAY => 100.0,
+            _ => (days_late * self.policy.percent_per_day).min(100.0),
+        };
+        let grade = match raw {
+            Grade::Scored(score) => Grade::Scored(score * (100.0 - penalty_percent) / 100.0),
+            other => other,
+        };
+        Ok(Assessed {
+            grade,
+            late: Some(LateWork {
+                assessment: assessment.name.clone(),
+                submitted_at,
+                late_by,
+                penalty_percent,
+                raw,
+            }),
+        })
+    }
+}
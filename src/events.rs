@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::admin::GradebookView;
+use crate::brightspace;
+use crate::course::CourseKey;
+use crate::grade::Grade;
+
+// ###################################################### //
+// ################ ADMIN CHANGE EVENTS ################# //
+// ###################################################### //
+
+/// How many events Admin keeps for a subscriber that has not caught up yet; a subscriber that falls
+/// further behind than this loses the oldest ones and is told how many it missed.
+pub const EVENT_BUFFER: usize = 64;
+
+/// Something that changed in one course of Admin's gradebook.
+///  - Note: a student in two courses is two students as far as events go, each with their own grade.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum AdminEvent {
+    /// A student new to the course, with the grade they arrived with.
+    StudentAdded {
+        course: CourseKey,
+        name: String,
+        grade: Grade,
+    },
+    /// A student already in the course got a different grade.
+    GradeChanged {
+        course: CourseKey,
+        name: String,
+        from: Grade,
+        to: Grade,
+    },
+    /// Sent alongside the `StudentAdded` or `GradeChanged` that put the student below `PASSING_GRADE`.
+    StudentFellBelowPassing {
+        course: CourseKey,
+        name: String,
+        grade: f64,
+    },
+    /// A student is no longer in the course: removed from it, or their term was archived.
+    StudentRemoved { course: CourseKey, name: String },
+}
+
+/// The kinds of `AdminEvent`, for filtering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EventKind {
+    StudentAdded,
+    GradeChanged,
+    StudentFellBelowPassing,
+    StudentRemoved,
+}
+
+impl AdminEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            AdminEvent::StudentAdded { .. } => EventKind::StudentAdded,
+            AdminEvent::GradeChanged { .. } => EventKind::GradeChanged,
+            AdminEvent::StudentFellBelowPassing { .. } => EventKind::StudentFellBelowPassing,
+            AdminEvent::StudentRemoved { .. } => EventKind::StudentRemoved,
+        }
+    }
+
+    pub fn course(&self) -> &CourseKey {
+        match self {
+            AdminEvent::StudentAdded { course, .. }
+            | AdminEvent::GradeChanged { course, .. }
+            | AdminEvent::StudentFellBelowPassing { course, .. }
+            | AdminEvent::StudentRemoved { course, .. } => course,
+        }
+    }
+
+    pub fn student(&self) -> &str {
+        match self {
+            AdminEvent::StudentAdded { name, .. }
+            | AdminEvent::GradeChanged { name, .. }
+            | AdminEvent::StudentFellBelowPassing { name, .. }
+            | AdminEvent::StudentRemoved { name, .. } => name,
+        }
+    }
+}
+
+impl fmt::Display for AdminEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminEvent::StudentAdded {
+                course,
+                name,
+                grade,
+            } => write!(f, "{} was added to {} ({})", name, course, grade),
+            AdminEvent::GradeChanged {
+                course,
+                name,
+                from,
+                to,
+            } => write!(f, "{} went from {} to {} in {}", name, from, to, course),
+            AdminEvent::StudentFellBelowPassing {
+                course,
+                name,
+                grade,
+            } => write!(
+                f,
+                "{} fell below passing in {} with {}",
+                name, course, grade
+            ),
+            AdminEvent::StudentRemoved { course, name } => {
+                write!(f, "{} is no longer in {}", name, course)
+            }
+        }
+    }
+}
+
+/// The student a gradebook entry is about, ignoring any career ID Brightspace appended to it,
+/// so "Aarya Patel (apatel)" is still the same student as "Aarya Patel".
+pub(crate) fn student_of(entry: &str) -> &str {
+    brightspace::split_career_id(entry)
+        .map(|(name, _)| name)
+        .unwrap_or(entry)
+}
+
+/// Every event that turns gradebook `before` into `after`, course by course in roster order, then every
+/// student `after` no longer has.
+pub(crate) fn diff(before: &GradebookView, after: &GradebookView) -> Vec<AdminEvent> {
+    let old_grades: HashMap<(&CourseKey, &str), Grade> = before
+        .students()
+        .map(|(course, name, grade)| ((course, student_of(name)), grade))
+        .collect();
+
+    let mut events = Vec::new();
+    for (course, entry, grade) in after.students() {
+        let old_grade = match old_grades.get(&(course, student_of(entry))) {
+            Some(old_grade) if *old_grade == grade => continue,
+            Some(old_grade) => {
+                events.push(AdminEvent::GradeChanged {
+                    course: course.clone(),
+                    name: entry.to_string(),
+                    from: *old_grade,
+                    to: grade,
+                });
+                *old_grade
+            }
+            None => {
+                events.push(AdminEvent::StudentAdded {
+                    course: course.clone(),
+                    name: entry.to_string(),
+                    grade,
+                });
+                Grade::Ungraded
+            }
+        };
+        if let Grade::Scored(score) = grade
+            && grade.is_failing()
+            && !old_grade.is_failing()
+        {
+            events.push(AdminEvent::StudentFellBelowPassing {
+                course: course.clone(),
+                name: entry.to_string(),
+                grade: score,
+            });
+        }
+    }
+
+    let remaining: HashSet<(&CourseKey, &str)> = after
+        .students()
+        .map(|(course, name, _)| (course, student_of(name)))
+        .collect();
+    for (course, entry, _) in before.students() {
+        if !remaining.contains(&(course, student_of(entry))) {
+            events.push(AdminEvent::StudentRemoved {
+                course: course.clone(),
+                name: entry.to_string(),
+            });
+        }
+    }
+    events
+}
+
+/// Which events a subscriber wants, e.g. `EventFilter::default().kinds([EventKind::StudentFellBelowPassing])`.
+///  - Note: the default filter lets everything through.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventFilter {
+    kinds: Vec<EventKind>,
+    student: Option<String>,
+}
+
+impl EventFilter {
+    /// Only events of these kinds (no kinds at all means every kind).
+    pub fn kinds(self, kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        EventFilter {
+            kinds: kinds.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Only events about this student (with or without their career ID).
+    pub fn student(self, name: impl Into<String>) -> Self {
+        EventFilter {
+            student: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn matches(&self, event: &AdminEvent) -> bool {
+        let kind_matches = self.kinds.is_empty() || self.kinds.contains(&event.kind());
+        let student_matches = self
+            .student
+            .as_deref()
+            .is_none_or(|name| student_of(name) == student_of(event.student()));
+        kind_matches && student_matches
+    }
+}
+
+/// What a subscriber gets next: an event, or word that it was too slow and some were dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notice {
+    Event(AdminEvent),
+    Lagged { missed: u64 },
+}
+
+/// A stream of Admin's events through one filter (see `AdminHandle::subscribe()`).
+#[derive(Debug)]
+pub struct Subscription {
+    receiver: broadcast::Receiver<AdminEvent>,
+    filter: EventFilter,
+    missed: u64,
+    gradebook: GradebookView,
+}
+
+impl Subscription {
+    pub(crate) fn new(
+        receiver: broadcast::Receiver<AdminEvent>,
+        filter: EventFilter,
+        gradebook: GradebookView,
+    ) -> Self {
+        Subscription {
+            receiver,
+            filter,
+            missed: 0,
+            gradebook,
+        }
+    }
+
+    /// Admin's gradebook at the moment this subscription started: every event after it is delivered.
+    pub fn gradebook(&self) -> &GradebookView {
+        &self.gradebook
+    }
+
+    /// Waits for the next event that passes the filter, or `None` once Admin has stopped.
+    ///  - Note: missed events are counted before filtering, since nobody knows what they were.
+    pub async fn recv(&mut self) -> Option<Notice> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(Notice::Event(event)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    self.missed += missed;
+                    return Some(Notice::Lagged { missed });
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Like `recv()`, but returns `None` straight away when no event is waiting yet.
+    pub fn try_recv(&mut self) -> Option<Notice> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) if self.filter.matches(&event) => return Some(Notice::Event(event)),
+                Ok(_) => continue,
+                Err(broadcast::error::TryRecvError::Lagged(missed)) => {
+                    self.missed += missed;
+                    return Some(Notice::Lagged { missed });
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// How many events this subscriber has lost to lag so far.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+}
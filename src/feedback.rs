@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::CourseKey;
+use crate::events::student_of;
+use crate::grade::Grade;
+
+// ###################################################### //
+// ############### WRITTEN FEEDBACK ON GRADES ########### //
+// ###################################################### //
+
+/// The `[comments]` table: comments every coordinator reuses, by key, e.g.
+/// `needs-docs = "{name}, your design review ({grade}) needs a README."`
+///  - Note: `{name}` becomes the student's name and `{grade}` their grade when the comment is attached.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CommentBank {
+    pub templates: BTreeMap<String, String>,
+}
+
+impl CommentBank {
+    /// The comment `key` stands for, written out for `student` and their `grade`.
+    pub fn render(&self, key: &str, student: &str, grade: Grade) -> Result<String, String> {
+        let template = self.templates.get(key).ok_or_else(|| {
+            let known: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+            match known.is_empty() {
+                true => format!("there is no comment `{}`, the comment bank is empty", key),
+                false => format!(
+                    "there is no comment `{}`, expected one of {}",
+                    key,
+                    known.join(", ")
+                ),
+            }
+        })?;
+        Ok(template
+            .replace("{name}", student_of(student))
+            .replace("{grade}", &format!("{:.1}", grade)))
+    }
+}
+
+/// What a coordinator asks John to attach: their own words, or a comment from the bank.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommentText {
+    Written(String),
+    Bank(String),
+}
+
+/// One piece of feedback on a student's grade.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub text: String,
+    /// The comment bank entry it was written from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// The grade the comment was written about.
+    pub grade: Grade,
+    pub at: SystemTime,
+}
+
+/// One student's feedback in Admin, next to their grade (see `AdminHandle::get_feedback()`).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FeedbackReport {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub grade: Grade,
+    pub comments: Vec<Comment>,
+}
+
+/// One row of Brightspace's LMS export: a student's grade in one course shell, with their feedback.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LmsRow {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub grade: Grade,
+    pub feedback: Vec<String>,
+}
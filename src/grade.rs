@@ -0,0 +1,132 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::admin::PASSING_GRADE;
+
+// ###################################################### //
+// ####################### GRADES ####################### //
+// ###################################################### //
+
+/// The best score there is; scores run from 0 up to this.
+pub const MAX_SCORE: f64 = 100.0;
+
+/// A student's grade, which is not always a number.
+///  - Note: only `Scored` grades count towards statistics or can be failing; the other states are
+///    counted separately and Booster leaves them alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Grade {
+    /// Nobody has graded the student yet (what John gives every new student).
+    #[default]
+    Ungraded,
+    /// Graded, but the student still has work outstanding.
+    Incomplete,
+    /// The student does not need a grade at all.
+    Excused,
+    Scored(f64),
+}
+
+impl Grade {
+    /// `Grade::Scored(score)`, or why `score` cannot be anyone's grade.
+    pub fn scored(score: f64) -> Result<Grade, String> {
+        Grade::Scored(score).checked()
+    }
+
+    /// This grade, or why it cannot be anyone's grade: every state can, a score only from 0 to `MAX_SCORE`.
+    ///  - Note: `From<f64>` does not check, so anything a caller hands over as a number is checked here.
+    pub fn checked(self) -> Result<Grade, String> {
+        match self {
+            Grade::Scored(score) if !(0.0..=MAX_SCORE).contains(&score) => Err(format!(
+                "`{}` is not a grade, scores run from 0 to {}",
+                score, MAX_SCORE
+            )),
+            grade => Ok(grade),
+        }
+    }
+
+    /// The number, if this grade has one.
+    pub fn score(self) -> Option<f64> {
+        match self {
+            Grade::Scored(score) => Some(score),
+            _ => None,
+        }
+    }
+
+    pub fn is_failing(self) -> bool {
+        self.score().is_some_and(|score| score < PASSING_GRADE)
+    }
+
+    /// Just the numbers out of `grades`, skipping every grade that has none.
+    pub fn scores(grades: &[Grade]) -> Vec<f64> {
+        grades.iter().filter_map(|grade| grade.score()).collect()
+    }
+}
+
+impl From<f64> for Grade {
+    fn from(score: f64) -> Self {
+        Grade::Scored(score)
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grade::Ungraded => write!(f, "ungraded"),
+            Grade::Incomplete => write!(f, "incomplete"),
+            Grade::Excused => write!(f, "excused"),
+            // Note: passing the formatter on keeps `{:.1}` working for scores
+            Grade::Scored(score) => score.fmt(f),
+        }
+    }
+}
+
+/// Parses a number from 0 to `MAX_SCORE`, or `ungraded`, `incomplete` or `excused`.
+impl FromStr for Grade {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ungraded" => Ok(Grade::Ungraded),
+            "incomplete" => Ok(Grade::Incomplete),
+            "excused" => Ok(Grade::Excused),
+            _ => {
+                let score = s.parse::<f64>().map_err(|_| {
+                    format!(
+                        "`{}` is not a grade, expected a number, ungraded, incomplete or excused",
+                        s
+                    )
+                })?;
+                Grade::scored(score)
+            }
+        }
+    }
+}
+
+/// How a grade looks in JSON: a plain number, or the name of its state.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GradeRepr {
+    Scored(f64),
+    State(String),
+}
+
+// Note: saved gradebooks from before grades had states are plain numbers, and still load as `Scored`
+impl Serialize for Grade {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Grade::Scored(score) => GradeRepr::Scored(*score),
+            state => GradeRepr::State(state.to_string()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grade {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match GradeRepr::deserialize(deserializer)? {
+            GradeRepr::Scored(score) => Grade::scored(score).map_err(serde::de::Error::custom),
+            GradeRepr::State(state) => state.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
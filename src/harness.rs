@@ -0,0 +1,206 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Notify, mpsc};
+use tokio::task::JoinHandle;
+
+use crate::admin::AdminHandle;
+use crate::brightspace::BrightspaceHandle;
+use crate::course::{Courses, Roster, Scope};
+use crate::john::JohnHandle;
+
+// ###################################################### //
+// #################### TEST HARNESS #################### //
+// ###################################################### //
+
+// Note: tests run on a paused clock (`#[tokio::test(start_paused = true)]`), so timeouts and deadlines
+//       pass instantly and in the same order every run; time only moves when every task is waiting.
+
+/// How long (in virtual time) `Stepper::step()` waits for a message before deciding none is coming.
+pub(crate) const STEP_WAIT: Duration = Duration::from_millis(100);
+
+pub(crate) type Step<'a> = Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
+
+/// An actor whose mailbox a test empties one message at a time (see e.g. `JohnHandle::stepped()`).
+pub(crate) trait Steppable: Send {
+    /// Waits up to `wait` for the next message and handles it, returning it as `{:?}` prints it.
+    ///  - Note: only the wait for a message gives up after `wait`, a message already taken is always handled in full.
+    fn next_message(&mut self, wait: Duration) -> Step<'_>;
+
+    /// Hands the actor back to its normal run loop.
+    fn run(self: Box<Self>);
+}
+
+/// The test's grip on a stepped actor: nothing reaches the actor until the test calls `step()`.
+pub(crate) struct Stepper {
+    actor: Box<dyn Steppable>,
+    received: Vec<String>,
+}
+
+impl Stepper {
+    pub(crate) fn new(actor: impl Steppable + 'static) -> Self {
+        Stepper {
+            actor: Box::new(actor),
+            received: Vec::new(),
+        }
+    }
+
+    /// Handles the next message, or returns `None` if nothing arrives within `STEP_WAIT`.
+    pub(crate) async fn step(&mut self) -> Option<&str> {
+        let msg = self.actor.next_message(STEP_WAIT).await?;
+        self.received.push(msg);
+        self.received.last().map(String::as_str)
+    }
+
+    /// Handles messages until none arrives within `STEP_WAIT`, returning how many it handled.
+    pub(crate) async fn step_until_idle(&mut self) -> usize {
+        let mut handled = 0;
+        while self.step().await.is_some() {
+            handled += 1;
+        }
+        handled
+    }
+
+    /// Every message handled so far, oldest first, as `{:?}` prints them.
+    pub(crate) fn received(&self) -> &[String] {
+        &self.received
+    }
+
+    /// Just the variant of every message handled so far, e.g. `["AddUnderling", "SetUnderlingGrade"]`.
+    pub(crate) fn kinds(&self) -> Vec<&str> {
+        self.received.iter().map(|msg| kind_of(msg)).collect()
+    }
+
+    /// Lets the actor handle its messages on its own from now on.
+    pub(crate) fn release(self) {
+        self.actor.run();
+    }
+}
+
+/// The variant of a message as `{:?}` prints it, e.g. `AddUnderling` for `AddUnderling { scope: .. }`.
+fn kind_of(msg: &str) -> &str {
+    msg.split([' ', '{', '(']).next().unwrap_or(msg)
+}
+
+/// The test's grip on a mock actor (see e.g. `AdminHandle::mocked()`): the mock records every message it
+/// gets and answers from its script `S`, and the test can change the script, slow the mock down or kill it
+/// at any point.
+pub(crate) struct Mock<S> {
+    shared: Arc<Mutex<Mocked<S>>>,
+    killed: Arc<Notify>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct Mocked<S> {
+    script: S,
+    calls: Vec<String>,
+    delay: Duration,
+}
+
+impl<S: Send + 'static> Mock<S> {
+    /// Starts a mock that answers every message from `receiver` with `answer`.
+    ///  - Note: a message `answer` drops without replying looks like Admin (or whoever) died to its caller.
+    pub(crate) fn spawn<M: fmt::Debug + Send + 'static>(
+        mut receiver: mpsc::Receiver<M>,
+        script: S,
+        answer: fn(&mut S, M),
+    ) -> Self {
+        let shared = Arc::new(Mutex::new(Mocked {
+            script,
+            calls: Vec::new(),
+            delay: Duration::ZERO,
+        }));
+        let killed = Arc::new(Notify::new());
+
+        let (mock, dead) = (shared.clone(), killed.clone());
+        let task = tokio::spawn(async move {
+            loop {
+                // Note: once killed, the mailbox is dropped, so queued and future calls all fail with `Closed`
+                let msg = tokio::select! {
+                    biased;
+                    _ = dead.notified() => break,
+                    msg = receiver.recv() => match msg {
+                        Some(msg) => msg,
+                        None => break,
+                    },
+                };
+                let delay = {
+                    let mut mock = mock.lock().unwrap();
+                    mock.calls.push(format!("{:?}", msg));
+                    mock.delay
+                };
+                if !delay.is_zero() {
+                    tokio::select! {
+                        _ = dead.notified() => break,
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                }
+                answer(&mut mock.lock().unwrap().script, msg);
+            }
+        });
+
+        Mock {
+            shared,
+            killed,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Every message the mock got so far, oldest first, as `{:?}` prints them.
+    pub(crate) fn calls(&self) -> Vec<String> {
+        self.shared.lock().unwrap().calls.clone()
+    }
+
+    /// Just the variant of every message the mock got so far (see `Stepper::kinds()`).
+    pub(crate) fn kinds(&self) -> Vec<String> {
+        let calls = self.calls();
+        calls.iter().map(|msg| kind_of(msg).to_string()).collect()
+    }
+
+    /// Changes what the mock answers from now on.
+    pub(crate) fn script(&self, edit: impl FnOnce(&mut S)) {
+        edit(&mut self.shared.lock().unwrap().script);
+    }
+
+    /// Makes the mock wait `delay` (in virtual time) before answering each message.
+    pub(crate) fn slow(&self, delay: Duration) {
+        self.shared.lock().unwrap().delay = delay;
+    }
+
+    /// Makes the mock act like an actor that stopped running: every call from now on fails with `Closed`.
+    pub(crate) async fn kill(&self) {
+        self.killed.notify_one();
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+}
+
+/// A John, Brightspace and Admin wired into the usual chain, all running on their own.
+pub(crate) async fn chain() -> (JohnHandle, BrightspaceHandle, AdminHandle) {
+    let john = JohnHandle::new().await;
+    let brightspace = BrightspaceHandle::new().await;
+    let admin = AdminHandle::new().await;
+    brightspace.set_admin(admin.feed().unwrap()).await.unwrap();
+    john.set_brightspace(brightspace.clone()).await.unwrap();
+    (john, brightspace, admin)
+}
+
+/// A stand-in Admin that starts out holding `roster` (in the default course) and is wired to nothing,
+/// e.g. for testing Booster without the rest of the chain.
+pub(crate) async fn stub_admin(roster: Roster) -> AdminHandle {
+    let admin = AdminHandle::new().await;
+    admin.load_courses(courses_of(roster)).await.unwrap();
+    admin
+}
+
+/// Courses holding just `roster`, in the default course of the default term.
+pub(crate) fn courses_of(roster: Roster) -> Courses {
+    let mut courses = Courses::default();
+    *courses.roster_mut(&Scope::default()).unwrap() = roster;
+    courses
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::{CourseKey, Courses, Roster};
+
+// ###################################################### //
+// ############### ADMIN'S UNDO/REDO HISTORY ############ //
+// ###################################################### //
+
+/// How many changes Admin remembers before it starts forgetting the oldest.
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+pub type EntryId = u64;
+
+/// One change to Admin's gradebook (a dump from Brightspace, an accepted proposal, ...) that can be undone.
+///  - Note: an entry keeps every roster it touched as it was before and after, `None` if it did not exist.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: EntryId,
+    pub description: String,
+    pub at: SystemTime,
+    before: Vec<(CourseKey, Option<Roster>)>,
+    after: Vec<(CourseKey, Option<Roster>)>,
+}
+
+impl HistoryEntry {
+    /// Every course this entry changed.
+    pub fn courses(&self) -> impl Iterator<Item = &CourseKey> {
+        self.before.iter().map(|(key, _)| key)
+    }
+}
+
+/// What an `undo()` or `redo()` did: which change it took back (or made again), and the rosters it left
+/// behind, e.g. to push to Brightspace.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Revision {
+    pub description: String,
+    pub rosters: Vec<(CourseKey, Roster)>,
+}
+
+/// Admin's changes, newest last, and the changes undone since the last new one, most recently undone last.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub done: VecDeque<HistoryEntry>,
+    pub undone: Vec<HistoryEntry>,
+    pub limit: usize,
+    pub next_id: EntryId,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            limit: DEFAULT_HISTORY_LIMIT,
+            next_id: 0,
+        }
+    }
+}
+
+impl History {
+    /// Remembers whatever changed between `before` and `after` as one entry, unless nothing did.
+    ///  - Note: a new change makes everything undone so far impossible to redo, as in any editor.
+    pub(crate) fn record(&mut self, description: String, before: &Courses, after: &Courses) {
+        let keys: BTreeSet<&CourseKey> = before
+            .rosters()
+            .chain(after.rosters())
+            .map(|(key, _)| key)
+            .filter(|key| before.roster(key) != after.roster(key))
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        self.next_id += 1;
+        let of = |courses: &Courses| {
+            keys.iter()
+                .map(|key| ((*key).clone(), courses.roster(key).cloned()))
+                .collect()
+        };
+        self.done.push_back(HistoryEntry {
+            id: self.next_id,
+            description,
+            at: SystemTime::now(),
+            before: of(before),
+            after: of(after),
+        });
+        self.undone.clear();
+        self.trim();
+    }
+
+    /// The newest change that can be undone, if any.
+    pub fn newest(&self) -> Option<&HistoryEntry> {
+        self.done.back()
+    }
+
+    /// Folds a follow-up change into the newest entry (e.g. the grades that come right after a name dump),
+    /// so undoing takes back both at once; `after` is what every roster of that entry looks like now.
+    ///  - Note: if the two changes cancel out, the entry is dropped, there is nothing left to undo.
+    pub(crate) fn amend(&mut self, description: String, after: &Courses) {
+        let Some(entry) = self.done.back_mut() else {
+            return;
+        };
+        entry.description = description;
+        entry.after = entry
+            .before
+            .iter()
+            .map(|(key, _)| (key.clone(), after.roster(key).cloned()))
+            .collect();
+        if entry.before == entry.after {
+            self.done.pop_back();
+        }
+    }
+
+    /// Keeps at most `limit` changes, forgetting the oldest first.
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.done.len() > self.limit {
+            self.done.pop_front();
+        }
+        self.undone.truncate(self.limit);
+    }
+
+    /// Puts the rosters of the newest change in `courses` back the way they were before it.
+    pub(crate) fn undo(&mut self, courses: &mut Courses) -> Result<Revision, String> {
+        let entry = self.done.back().ok_or("there is nothing to undo")?;
+        let revision = Self::put(courses, entry, &entry.before)
+            .map_err(|reason| format!("cannot undo: {}", reason))?;
+        self.undone.extend(self.done.pop_back());
+        Ok(revision)
+    }
+
+    /// Makes the most recently undone change in `courses` again.
+    pub(crate) fn redo(&mut self, courses: &mut Courses) -> Result<Revision, String> {
+        let entry = self.undone.last().ok_or("there is nothing to redo")?;
+        let revision = Self::put(courses, entry, &entry.after)
+            .map_err(|reason| format!("cannot redo: {}", reason))?;
+        self.done.extend(self.undone.pop());
+        Ok(revision)
+    }
+
+    fn put(
+        courses: &mut Courses,
+        entry: &HistoryEntry,
+        rosters: &[(CourseKey, Option<Roster>)],
+    ) -> Result<Revision, String> {
+        // Note: checked before anything changes, so an entry is put back whole or not at all
+        if let Some(key) = rosters
+            .iter()
+            .find(|(key, _)| courses.is_archived(&key.term))
+        {
+            return Err(format!("{} is archived", key.0.term));
+        }
+        for (key, roster) in rosters {
+            courses.put(key.clone(), roster.clone());
+        }
+        Ok(Revision {
+            description: entry.description.clone(),
+            rosters: rosters
+                .iter()
+                .map(|(key, roster)| (key.clone(), roster.clone().unwrap_or_default()))
+                .collect(),
+        })
+    }
+
+    /// Forgets every change, e.g. once the rosters they were about are archived.
+    pub(crate) fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path as FilePath;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{FromRequestParts, Path, Query};
+use axum::http::request::Parts;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::access::{Caller, Role};
+use crate::alerts::{Alert, AlertReason};
+use crate::booster::BoostPolicy;
+use crate::call::ActorError;
+use crate::cli::{Cli, ConflictRow, Session};
+use crate::course::{Course, CourseKey, Courses, Scope, Term};
+use crate::grade::Grade;
+use crate::portal::StudentRecord;
+use crate::proposal::{ProposalBook, ProposalId};
+
+// ###################################################### //
+// ################### HTTP/JSON API #################### //
+// ###################################################### //
+
+/// The OpenAPI description of every route below, served at `GET /openapi.json`.
+const OPENAPI: &str = include_str!("openapi.json");
+
+/// What every failing request gets back: a status code plus `{ "error": "..." }`.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ActorError> for ApiError {
+    fn from(e: ActorError) -> Self {
+        let status = match e {
+            ActorError::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            ActorError::Cancelled { .. } | ActorError::Closed { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ActorError::Cycle { .. } => StatusCode::CONFLICT,
+            ActorError::Denied { .. } => StatusCode::FORBIDDEN,
+            ActorError::Refused { .. } => StatusCode::CONFLICT,
+        };
+        ApiError::new(status, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Who each bearer token stands for, read from the file `serve --tokens` names, e.g.
+/// ```toml
+/// [[tokens]]
+/// token = "a long random string"
+/// user = "Lee Coordinator"
+/// role = "coordinator"
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Tokens {
+    callers: HashMap<String, Caller>,
+}
+
+#[derive(Deserialize)]
+struct TokenFile {
+    #[serde(default)]
+    tokens: Vec<TokenEntry>,
+}
+
+#[derive(Deserialize)]
+struct TokenEntry {
+    token: String,
+    user: String,
+    role: Role,
+}
+
+impl Tokens {
+    pub fn from_file(path: impl AsRef<FilePath>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read tokens from {}", path.display()))?;
+        Tokens::parse(&text).with_context(|| format!("bad tokens file {}", path.display()))
+    }
+
+    /// Parses a tokens file, refusing blank or repeated tokens.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let file: TokenFile = toml::from_str(text)?;
+        let mut callers = HashMap::new();
+        for entry in file.tokens {
+            anyhow::ensure!(
+                !entry.token.trim().is_empty(),
+                "{} has a blank token",
+                entry.user
+            );
+            let caller = Caller::new(entry.user, entry.role);
+            if let Some(other) = callers.insert(entry.token, caller.clone()) {
+                anyhow::bail!("{} and {} share a token", other, caller);
+            }
+        }
+        Ok(Tokens { callers })
+    }
+
+    fn caller(&self, token: &str) -> Option<&Caller> {
+        self.callers.get(token)
+    }
+}
+
+/// What every request is answered from: the server's actors and the tokens it accepts.
+#[derive(Clone)]
+struct Api {
+    session: Session,
+    tokens: Arc<Tokens>,
+}
+
+/// Who `Authorization: Bearer <token>` says sent the request, or `None` if it has no token.
+///  - Returns a `401` for anything but a `Bearer` token the server knows.
+fn token_caller(parts: &Parts, api: &Api) -> Result<Option<Caller>, ApiError> {
+    let Some(value) = parts.headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let token = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "expected a Bearer token"))?;
+    match api.tokens.caller(token.trim()) {
+        Some(caller) => Ok(Some(caller.clone())),
+        None => Err(ApiError::new(StatusCode::UNAUTHORIZED, "unknown token")),
+    }
+}
+
+/// The server's actors, scoped to whoever sent the request and the course and term it is about.
+///  - Note: the caller is whoever `Authorization: Bearer <token>` names in the server's tokens; a request
+///    without one is an anonymous student, so it can only read statistics. An unknown token is a `401`.
+///    Even a token cannot go past the role the server runs as (see `access::narrow()`).
+///  - Note: `?course=NAME[/SECTION]` and `?term=TERM` on any route narrow it to one course or term,
+///    and `?coordinator=NAME` sends `/john` routes to another John actor in the topology.
+struct Scoped(Session);
+
+impl FromRequestParts<Api> for Scoped {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, api: &Api) -> Result<Self, ApiError> {
+        let caller =
+            token_caller(parts, api)?.unwrap_or_else(|| Caller::new("anonymous", Role::Student));
+        let Query(scope) = Query::<ScopeQuery>::try_from_uri(&parts.uri)
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.body_text()))?;
+        let session = match &scope.coordinator {
+            Some(name) => api.session.as_coordinator(name).ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("no coordinator named {}", name),
+                )
+            })?,
+            None => api.session.clone(),
+        };
+        let scope = Scope {
+            course: scope.course,
+            term: scope.term,
+        };
+        Ok(Scoped(session.acting_as(caller).scoped(&scope)))
+    }
+}
+
+/// A request that came with a token the server knows; anything else is a `401`.
+///  - Note: a student's token is what makes a career ID theirs, so routes about one student need it.
+struct Authenticated;
+
+impl FromRequestParts<Api> for Authenticated {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, api: &Api) -> Result<Self, ApiError> {
+        match token_caller(parts, api)? {
+            Some(_) => Ok(Authenticated),
+            None => Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "a student record needs a token",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ScopeQuery {
+    coordinator: Option<String>,
+    course: Option<Course>,
+    term: Option<Term>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewTerm {
+    term: Term,
+}
+
+#[derive(Debug, Serialize)]
+struct Rollover {
+    archived: Term,
+    current: Term,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewStudent {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewGrade {
+    grade: Grade,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BoostRequest {
+    policy: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Student {
+    name: String,
+    grade: Grade,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Rejection {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NewProposal {
+    proposal: ProposalId,
+}
+
+#[derive(Debug, Serialize)]
+struct AtRiskStudent {
+    course: CourseKey,
+    name: String,
+    grade: Grade,
+    reason: AlertReason,
+}
+
+#[derive(Debug, Serialize)]
+struct FailingCount {
+    failing: usize,
+}
+
+fn students(names: Vec<String>, grades: Vec<Grade>) -> Vec<Student> {
+    names
+        .into_iter()
+        .zip(grades)
+        .map(|(name, grade)| Student { name, grade })
+        .collect()
+}
+
+async fn register_student(
+    Scoped(session): Scoped,
+    Json(body): Json<NewStudent>,
+) -> ApiResult<StatusCode> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "name must not be empty",
+        ));
+    }
+    if session.john.get_all_student_names().await?.contains(&name) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("{} is already registered", name),
+        ));
+    }
+    session.john.register_new_student(name).await?;
+    Ok(StatusCode::CREATED)
+}
+
+// Note: a score outside 0-100 never gets this far, `Grade` refuses to deserialize it (a 422 from `Json`)
+async fn assign_grade(
+    Scoped(session): Scoped,
+    Path(name): Path<String>,
+    Json(body): Json<NewGrade>,
+) -> ApiResult<StatusCode> {
+    if !session.john.get_all_student_names().await?.contains(&name) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("{} is not registered", name),
+        ));
+    }
+    session
+        .john
+        .assign_grade_to_student(name, body.grade)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn john_students(Scoped(session): Scoped) -> ApiResult<Json<Vec<Student>>> {
+    let names = session.john.get_all_student_names().await?;
+    let grades = session.john.get_all_student_grades().await?;
+    Ok(Json(students(names, grades)))
+}
+
+async fn john_report(Scoped(session): Scoped) -> ApiResult<StatusCode> {
+    session
+        .john
+        .report_all_students_and_grades_to_brightspace()
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn career_ids(Scoped(session): Scoped) -> ApiResult<StatusCode> {
+    session
+        .brightspace
+        .generate_and_append_student_career_id()
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn brightspace_students(Scoped(session): Scoped) -> ApiResult<Json<Vec<Student>>> {
+    let names = session.brightspace.get_all_student_names().await?;
+    let grades = session.brightspace.get_all_student_grades().await?;
+    Ok(Json(students(names, grades)))
+}
+
+async fn conflicts(Scoped(session): Scoped) -> ApiResult<Json<Vec<ConflictRow>>> {
+    Ok(Json(session.conflict_report().await?))
+}
+
+async fn brightspace_report(Scoped(session): Scoped) -> ApiResult<StatusCode> {
+    session
+        .brightspace
+        .report_all_students_and_grades_to_admin()
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn boost(
+    Scoped(session): Scoped,
+    body: Option<Json<BoostRequest>>,
+) -> ApiResult<(StatusCode, Json<NewProposal>)> {
+    let policy = match body.and_then(|Json(b)| b.policy) {
+        Some(policy) => policy
+            .parse::<BoostPolicy>()
+            .map_err(|e| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, e))?,
+        None => BoostPolicy::Perfect,
+    };
+    let proposal = session.booster.boost_grades_with(policy).await?;
+    Ok((StatusCode::ACCEPTED, Json(NewProposal { proposal })))
+}
+
+async fn proposals(Scoped(session): Scoped) -> ApiResult<Json<ProposalBook>> {
+    Ok(Json(session.admin.get_proposals().await?))
+}
+
+/// Fails with a `404` unless Admin has proposal `id`, pending or decided (Admin itself only refuses).
+async fn known_proposal(session: &Session, id: ProposalId) -> ApiResult<()> {
+    let book = session.admin.get_proposals().await?;
+    let pending = book.pending.iter().map(|p| p.id);
+    let decided = book.decided.iter().map(|d| d.proposal.id);
+    if !pending.chain(decided).any(|known| known == id) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("there is no proposal #{}", id),
+        ));
+    }
+    Ok(())
+}
+
+async fn accept_proposal(
+    Scoped(session): Scoped,
+    Path(id): Path<ProposalId>,
+) -> ApiResult<StatusCode> {
+    known_proposal(&session, id).await?;
+    session.admin.accept_proposal(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn reject_proposal(
+    Scoped(session): Scoped,
+    Path(id): Path<ProposalId>,
+    body: Option<Json<Rejection>>,
+) -> ApiResult<StatusCode> {
+    let reason = body
+        .and_then(|Json(b)| b.reason)
+        .unwrap_or_else(|| "rejected".to_string());
+    known_proposal(&session, id).await?;
+    session.admin.reject_proposal(id, reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_students(Scoped(session): Scoped) -> ApiResult<Json<Vec<Student>>> {
+    let names = session.admin.get_all_student_names().await?;
+    let grades = session.admin.get_all_student_grades().await?;
+    Ok(Json(students(names, grades)))
+}
+
+async fn admin_failing(Scoped(session): Scoped) -> ApiResult<Json<Vec<Student>>> {
+    let failing = session.admin.get_failing_students().await?;
+    let (names, grades) = failing.into_iter().unzip();
+    Ok(Json(students(names, grades)))
+}
+
+async fn admin_failing_count(Scoped(session): Scoped) -> ApiResult<Json<FailingCount>> {
+    let failing = session.admin.count_number_of_failing_students().await?;
+    Ok(Json(FailingCount { failing }))
+}
+
+async fn admin_statistics(Scoped(session): Scoped) -> ApiResult<impl IntoResponse> {
+    Ok(Json(session.admin.get_statistics().await?))
+}
+
+async fn courses(Scoped(session): Scoped) -> ApiResult<Json<Courses>> {
+    Ok(Json(session.admin.get_courses().await?))
+}
+
+async fn rollover(Scoped(session): Scoped, Json(body): Json<NewTerm>) -> ApiResult<Json<Rollover>> {
+    let archived = session.roll_over(body.term.clone()).await?;
+    Ok(Json(Rollover {
+        archived,
+        current: body.term,
+    }))
+}
+
+async fn alerts(Scoped(session): Scoped) -> ApiResult<Json<Vec<Alert>>> {
+    Ok(Json(session.alerter.get_alerts().await?))
+}
+
+async fn at_risk(Scoped(session): Scoped) -> ApiResult<Json<Vec<AtRiskStudent>>> {
+    let at_risk = session.alerter.get_at_risk().await?;
+    Ok(Json(
+        at_risk
+            .into_iter()
+            .map(|(course, name, grade, reason)| AtRiskStudent {
+                course,
+                name,
+                grade,
+                reason,
+            })
+            .collect(),
+    ))
+}
+
+async fn portal_record(
+    _: Authenticated,
+    Scoped(session): Scoped,
+    Path(career_id): Path<String>,
+) -> ApiResult<Json<StudentRecord>> {
+    match session.portal.look_up(&career_id).await? {
+        Some(record) => Ok(Json(record)),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("the portal has no grade for {} yet", career_id),
+        )),
+    }
+}
+
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+/// Every route of the API, answering from the actors in `session` to whoever holds one of `tokens`.
+pub(crate) fn router(session: Session, tokens: Tokens) -> Router {
+    Router::new()
+        .route("/john/students", get(john_students).post(register_student))
+        .route("/john/students/{name}/grade", put(assign_grade))
+        .route("/john/report", post(john_report))
+        .route("/brightspace/students", get(brightspace_students))
+        .route("/brightspace/career-ids", post(career_ids))
+        .route("/brightspace/report", post(brightspace_report))
+        .route("/brightspace/conflicts", get(conflicts))
+        .route("/booster/boost", post(boost))
+        .route("/admin/students", get(admin_students))
+        .route("/admin/failing", get(admin_failing))
+        .route("/admin/failing/count", get(admin_failing_count))
+        .route("/admin/statistics", get(admin_statistics))
+        .route("/admin/proposals", get(proposals))
+        .route("/admin/proposals/{id}/accept", post(accept_proposal))
+        .route("/admin/proposals/{id}/reject", post(reject_proposal))
+        .route("/admin/courses", get(courses))
+        .route("/admin/rollover", post(rollover))
+        .route("/alerts", get(alerts))
+        .route("/alerts/at-risk", get(at_risk))
+        .route("/portal/{career_id}", get(portal_record))
+        .route("/openapi.json", get(openapi))
+        .with_state(Api {
+            session,
+            tokens: Arc::new(tokens),
+        })
+}
+
+/// Serves the API on `addr` until Ctrl-C, then saves the gradebook like every other command.
+pub async fn serve(cli: &Cli, addr: SocketAddr, tokens: Option<&FilePath>) -> anyhow::Result<()> {
+    let tokens = match tokens {
+        Some(path) => Tokens::from_file(path)?,
+        None => Tokens::default(),
+    };
+    let session = Session::start(cli).await?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    eprintln!(
+        "serving the gradebook API on http://{}",
+        listener.local_addr()?
+    );
+
+    // Note: a token only ever narrows what the server itself may do (`--role`)
+    axum::serve(listener, router(session.acting_as(cli.caller()), tokens))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    session.persist(cli).await
+}
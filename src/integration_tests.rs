@@ -0,0 +1,2934 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use clap::Parser;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+
+use crate::access::{Caller, Operation, Role};
+use crate::admin::{AdminHandle, AdminScript, PASSING_GRADE};
+use crate::alerts::{
+    Alert, AlertReason, AlertRules, AlerterHandle, FileNotifier, MAX_ALERTS, Notifier,
+    NotifierSpec, Notifying,
+};
+use crate::attendance::{AttendanceHandle, AttendanceStatus, Mark, ParticipationPolicy};
+use crate::booster::{BoostPolicy, BoosterHandle, BoosterScript};
+use crate::brightspace::{BrightspaceHandle, BrightspaceScript};
+use crate::call::ActorError;
+use crate::cli::{self, Cli, Session};
+use crate::course::{Course, CourseKey, Roster};
+use crate::deadline::{Assessment, Deadlines, LatePolicy, Timestamp};
+use crate::events::{AdminEvent, EVENT_BUFFER, EventFilter, EventKind, Notice, Subscription};
+use crate::feedback::{CommentBank, CommentText};
+use crate::grade::Grade;
+use crate::harness::{self, STEP_WAIT};
+use crate::http;
+use crate::john::{JohnHandle, JohnScript};
+use crate::portal::StudentPortalHandle;
+use crate::proposal::{DEFAULT_PROPOSAL_TTL, GradeChange, Outcome, ProposalBook};
+use crate::reconcile::{self, MismatchKind, Sides, TruthRule};
+use crate::rubric::{Criterion, Level, Rubric, Selection};
+use crate::shell;
+use crate::snapshot::{self, SNAPSHOT_VERSION, SystemSnapshot};
+use crate::timeline::{GradePoint, GradeSource, Trend};
+use crate::topology::{ActorKind, Topology, TopologyError};
+use crate::wiring::{self, ActorId, Stall};
+
+// ###################################################### //
+// ############ JOHN -> BRIGHTSPACE -> ADMIN ############ //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn names_and_grades_reach_admin() {
+    let (john, brightspace, admin) = harness::chain().await;
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.register_new_student("Ben Ortiz".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 91.0)
+        .await
+        .unwrap();
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        admin.get_all_student_names().await.unwrap(),
+        ["Aarya Patel", "Ben Ortiz"]
+    );
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(91.0), Grade::Ungraded]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn john_handles_one_message_per_step() {
+    let (john, mut stepper) = JohnHandle::stepped(8);
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 74.0)
+        .await
+        .unwrap();
+
+    // Note: both messages are already waiting in the mailbox, but nothing happens until John is stepped
+    assert!(stepper.received().is_empty());
+    stepper.step().await.unwrap();
+    assert_eq!(stepper.kinds(), ["AddUnderling"]);
+    stepper.step().await.unwrap();
+    assert_eq!(stepper.kinds(), ["AddUnderling", "SetUnderlingGrade"]);
+    assert!(stepper.received()[1].contains("74"));
+    assert!(stepper.step().await.is_none());
+
+    stepper.release();
+    assert_eq!(
+        john.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(74.0)]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn admin_receives_each_course_names_then_grades() {
+    let john = JohnHandle::new().await;
+    let brightspace = BrightspaceHandle::new().await;
+    let (admin, mut stepper) = AdminHandle::stepped(10);
+    brightspace.set_admin(admin.feed().unwrap()).await.unwrap();
+    john.set_brightspace(brightspace.clone()).await.unwrap();
+
+    for course in ["VIP-UAV", "VIP-ROV"] {
+        let john = john.in_course(Course::new(course));
+        john.register_new_student(format!("{} Student", course))
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    // Note: submitting to Admin only needs room in its mailbox, so this finishes before Admin is stepped
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    assert_eq!(stepper.step_until_idle().await, 10);
+    assert_eq!(
+        stepper.kinds(),
+        [
+            "ProcessStudentDump",
+            "ProcessGradeDump",
+            "ProcessLateDump",
+            "ProcessRubricDump",
+            "ProcessCommentDump",
+            "ProcessStudentDump",
+            "ProcessGradeDump",
+            "ProcessLateDump",
+            "ProcessRubricDump",
+            "ProcessCommentDump"
+        ]
+    );
+    // Note: rosters are sent in course order, so VIP-ROV comes before VIP-UAV
+    assert!(stepper.received()[0].contains("VIP-ROV Student"));
+    assert!(stepper.received()[5].contains("VIP-UAV Student"));
+
+    stepper.release();
+    let uav = admin.in_course(Course::new("VIP-UAV"));
+    assert_eq!(
+        uav.get_all_student_names().await.unwrap(),
+        ["VIP-UAV Student"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stuck_brightspace_times_out_john_report() {
+    let john = JohnHandle::new().await;
+    // Note: a mailbox of one that is never stepped, so John's second course cannot be delivered
+    let (brightspace, mut stepper) = BrightspaceHandle::stepped(1);
+    john.set_brightspace(brightspace).await.unwrap();
+    for course in ["VIP-UAV", "VIP-ROV"] {
+        let john = john.in_course(Course::new(course));
+        john.register_new_student("Aarya Patel".to_string())
+            .await
+            .unwrap();
+    }
+
+    let started = Instant::now();
+    let result = john
+        .with_timeout(Duration::from_secs(1))
+        .report_all_students_and_grades_to_brightspace()
+        .await;
+
+    // Note: Brightspace gets a slightly earlier deadline than John, so the error names Brightspace
+    assert_eq!(
+        result,
+        Err(ActorError::Timeout {
+            actor: "Brightspace"
+        })
+    );
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(stepper.step_until_idle().await, 1);
+    assert_eq!(stepper.kinds(), ["MergeRoster"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn courses_stay_apart_from_john_to_admin() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let uav = Course::new("VIP-UAV");
+    let rov = Course::new("VIP-ROV").section("02");
+    john.in_course(uav.clone())
+        .register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.in_course(rov.clone())
+        .register_new_student("Ben Ortiz".to_string())
+        .await
+        .unwrap();
+    john.in_course(rov.clone())
+        .assign_grade_to_student("Ben Ortiz".to_string(), 55.0)
+        .await
+        .unwrap();
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    let admin_uav = admin.in_course(uav);
+    let admin_rov = admin.in_course(rov);
+    assert_eq!(
+        admin_uav.get_all_student_names().await.unwrap(),
+        ["Aarya Patel"]
+    );
+    assert_eq!(
+        admin_uav.count_number_of_failing_students().await.unwrap(),
+        0
+    );
+    assert_eq!(
+        admin_rov.get_all_student_names().await.unwrap(),
+        ["Ben Ortiz"]
+    );
+    assert_eq!(
+        admin_rov.count_number_of_failing_students().await.unwrap(),
+        1
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn rival_coordinator_grades_become_conflicts() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let rival = JohnHandle::new().await;
+    rival.set_brightspace(brightspace.clone()).await.unwrap();
+    for (coordinator, grade) in [(&john, 88.0), (&rival, 64.0)] {
+        coordinator
+            .register_new_student("Aarya Patel".to_string())
+            .await
+            .unwrap();
+        coordinator
+            .assign_grade_to_student("Aarya Patel".to_string(), grade)
+            .await
+            .unwrap();
+        coordinator
+            .report_all_students_and_grades_to_brightspace()
+            .await
+            .unwrap();
+    }
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    // Note: the first John to report owns the student, so his grade is the one Admin sees
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(88.0)]
+    );
+    let conflicts = brightspace.get_conflicts().await.unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].owner, john.id());
+    assert_eq!(conflicts[0].rival, rival.id());
+    assert_eq!(conflicts[0].rival_grade, Grade::Scored(64.0));
+}
+
+#[tokio::test(start_paused = true)]
+async fn appending_career_ids_twice_changes_nothing_the_second_time() {
+    let (john, brightspace, _admin) = harness::chain().await;
+    for name in ["Alice A", "Aarya Patel", "Prince"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    let once = brightspace.get_all_student_names().await.unwrap();
+    // Note: a single word has no last name to make an ID from, so it is left alone
+    assert_eq!(once, ["Alice A (aa)", "Aarya Patel (apatel)", "Prince"]);
+
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    assert_eq!(brightspace.get_all_student_names().await.unwrap(), once);
+}
+
+#[tokio::test(start_paused = true)]
+async fn only_scores_from_0_to_100_are_grades() {
+    for text in ["nan", "NaN", "inf", "-5", "150", "100.5"] {
+        assert!(text.parse::<Grade>().is_err(), "`{}` parsed", text);
+    }
+    assert_eq!("0".parse(), Ok(Grade::Scored(0.0)));
+    assert_eq!("100".parse(), Ok(Grade::Scored(100.0)));
+    assert_eq!("Excused".parse(), Ok(Grade::Excused));
+    assert!(serde_json::from_str::<Grade>("150").is_err());
+    assert!(serde_json::from_str::<Grade>("-0.5").is_err());
+    assert_eq!(
+        serde_json::from_str::<Grade>("58.5").unwrap(),
+        Grade::Scored(58.5)
+    );
+
+    // Note: a plain number handed to John is not parsed, so John checks it itself
+    let john = JohnHandle::new().await;
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    for score in [f64::NAN, f64::INFINITY, -5.0, 150.0] {
+        let result = john
+            .assign_grade_to_student("Aarya Patel".to_string(), score)
+            .await;
+        assert!(matches!(
+            result,
+            Err(ActorError::Refused { actor: "John", .. })
+        ));
+    }
+    assert_eq!(
+        john.get_all_student_grades().await.unwrap(),
+        [Grade::Ungraded]
+    );
+}
+
+// ###################################################### //
+// ################ BOOSTER WITH A STUB ################# //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn booster_proposes_boost_to_stub_admin() {
+    let admin = harness::stub_admin(Roster {
+        names: vec!["Aarya Patel".to_string(), "Ben Ortiz".to_string()],
+        grades: vec![Grade::Scored(50.0), Grade::Excused],
+        ..Roster::default()
+    })
+    .await;
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+
+    let id = booster
+        .boost_grades_with(BoostPolicy::Add(10.0))
+        .await
+        .unwrap();
+
+    let book = admin.get_proposals().await.unwrap();
+    assert_eq!(book.pending.len(), 1);
+    assert_eq!(book.pending[0].id, id);
+    assert_eq!(
+        book.pending[0].changes,
+        [GradeChange {
+            course: CourseKey::default(),
+            name: "Aarya Patel".to_string(),
+            from: Grade::Scored(50.0),
+            to: Grade::Scored(60.0),
+        }]
+    );
+    // Note: Booster only proposes, the grades stay as they were until someone accepts
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(50.0), Grade::Excused]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn boosts_stay_between_a_student_s_grade_and_100() {
+    for text in [
+        "add:-50",
+        "floor:150",
+        "curve:200",
+        "curve:nan",
+        "add:inf",
+        "floor",
+    ] {
+        assert!(text.parse::<BoostPolicy>().is_err(), "`{}` parsed", text);
+    }
+    assert_eq!("curve:90".parse(), Ok(BoostPolicy::Curve(90.0)));
+
+    let admin = harness::stub_admin(Roster {
+        names: vec!["Aarya Patel".to_string(), "Ben Ortiz".to_string()],
+        grades: vec![Grade::Scored(95.0), Grade::Scored(45.0)],
+        ..Roster::default()
+    })
+    .await;
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+    let proposed = |policy| {
+        let (booster, admin) = (booster.clone(), admin.clone());
+        async move {
+            let id = booster.boost_grades_with(policy).await?;
+            let book = admin.get_proposals().await.unwrap();
+            let proposal = book.pending.into_iter().find(|p| p.id == id).unwrap();
+            let changes: Vec<(String, Grade)> = proposal
+                .changes
+                .into_iter()
+                .map(|change| (change.name, change.to))
+                .collect();
+            Ok::<_, ActorError>(changes)
+        }
+    };
+
+    // Note: a policy built in code is checked too, since it never went through `from_str()`
+    let result = proposed(BoostPolicy::Curve(200.0)).await;
+    assert!(matches!(
+        result,
+        Err(ActorError::Refused {
+            actor: "Booster",
+            ..
+        })
+    ));
+    assert_eq!(
+        proposed(BoostPolicy::Add(10.0)).await.unwrap(),
+        [
+            ("Aarya Patel".to_string(), Grade::Scored(100.0)),
+            ("Ben Ortiz".to_string(), Grade::Scored(55.0)),
+        ]
+    );
+    // Note: curving a top grade of 95 to 90 would lower every grade, so the proposal changes nobody
+    assert_eq!(proposed(BoostPolicy::Curve(90.0)).await.unwrap(), []);
+}
+
+#[tokio::test(start_paused = true)]
+async fn boost_of_a_student_in_two_courses_changes_each_course_on_its_own() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let uav = Course::new("VIP-UAV");
+    let rov = Course::new("VIP-ROV");
+    for (course, score) in [(&uav, 50.0), (&rov, 70.0)] {
+        let john = john.in_course(course.clone());
+        john.register_new_student("Al Bo".to_string())
+            .await
+            .unwrap();
+        john.assign_grade_to_student("Al Bo".to_string(), score)
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+
+    let id = booster
+        .boost_grades_with(BoostPolicy::Add(10.0))
+        .await
+        .unwrap();
+    let book = admin.get_proposals().await.unwrap();
+    let courses: Vec<&Course> = book.pending[0]
+        .changes
+        .iter()
+        .map(|change| &change.course.course)
+        .collect();
+    assert_eq!(courses, [&rov, &uav]);
+
+    // Note: each change only has to match the grade in its own course, so two different grades are not stale
+    admin.accept_proposal(id).await.unwrap();
+    assert_eq!(
+        admin.in_course(uav).get_all_student_grades().await.unwrap(),
+        [Grade::Scored(60.0)]
+    );
+    assert_eq!(
+        admin.in_course(rov).get_all_student_grades().await.unwrap(),
+        [Grade::Scored(80.0)]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn booster_reads_courses_then_proposes() {
+    let (admin, mut admin_steps) = AdminHandle::stepped(8);
+    let (booster, mut booster_steps) = BoosterHandle::stepped(8);
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+    booster_steps.step().await.unwrap();
+
+    let boost = tokio::spawn(async move { booster.boost_grades().await });
+    // Note: Booster waits on Admin while handling the boost, so both are stepped side by side
+    let (_, handled) = tokio::join!(booster_steps.step(), admin_steps.step_until_idle());
+
+    assert_eq!(handled, 2);
+    assert_eq!(booster_steps.kinds(), ["SetAdmin", "BoostGrade"]);
+    assert_eq!(admin_steps.kinds(), ["GetCourses", "ProposeGradeChanges"]);
+    assert!(boost.await.unwrap().is_ok());
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_step_returns_none_after_step_wait() {
+    let (_admin, mut stepper) = AdminHandle::stepped(8);
+    let started = Instant::now();
+    assert!(stepper.step().await.is_none());
+    assert_eq!(started.elapsed(), STEP_WAIT);
+}
+
+/// A proposal giving Aarya Patel 60 instead of 50, in the default course.
+fn regrade() -> Vec<GradeChange> {
+    vec![GradeChange {
+        course: CourseKey::default(),
+        name: "Aarya Patel".to_string(),
+        from: Grade::Scored(50.0),
+        to: Grade::Scored(60.0),
+    }]
+}
+
+#[tokio::test(start_paused = true)]
+async fn rejected_proposal_cannot_be_accepted_afterwards() {
+    let admin = harness::stub_admin(roster(&[("Aarya Patel", Grade::Scored(50.0))])).await;
+    let id = admin
+        .propose_grade_changes("test".to_string(), "regrade".to_string(), regrade())
+        .await
+        .unwrap();
+    admin
+        .reject_proposal(id, "too generous".to_string())
+        .await
+        .unwrap();
+
+    let accepted = admin.accept_proposal(id).await;
+    assert!(
+        matches!(&accepted, Err(ActorError::Refused { reason, .. }) if reason.contains("already decided"))
+    );
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(50.0)]
+    );
+    let book = admin.get_proposals().await.unwrap();
+    assert!(book.pending.is_empty());
+    assert_eq!(book.decided.len(), 1);
+    assert!(
+        matches!(&book.decided[0].outcome, Outcome::Rejected { reason, .. } if reason == "too generous")
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn expired_proposal_is_refused() {
+    let admin = harness::stub_admin(roster(&[("Aarya Patel", Grade::Scored(50.0))])).await;
+    admin.set_proposal_ttl(Duration::ZERO).await.unwrap();
+    let id = admin
+        .propose_grade_changes("test".to_string(), "regrade".to_string(), regrade())
+        .await
+        .unwrap();
+
+    // Note: straight away, before Admin's sweep had a chance to throw the proposal out
+    let accepted = admin.accept_proposal(id).await;
+    assert!(
+        matches!(&accepted, Err(ActorError::Refused { reason, .. }) if reason.contains("expired"))
+    );
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(50.0)]
+    );
+    let book = admin.get_proposals().await.unwrap();
+    assert!(book.pending.is_empty());
+    assert_eq!(book.decided[0].outcome, Outcome::Expired);
+
+    let mut book = ProposalBook::default();
+    let stale = book.submit(
+        "a".to_string(),
+        "old".to_string(),
+        regrade(),
+        Duration::ZERO,
+    );
+    let fresh = book.submit(
+        "b".to_string(),
+        "new".to_string(),
+        regrade(),
+        DEFAULT_PROPOSAL_TTL,
+    );
+    assert_eq!(book.expire(), 1);
+    assert_eq!(book.pending[0].id, fresh);
+    assert_eq!(book.decided[0].proposal.id, stale);
+    assert_eq!(book.expire(), 0);
+    assert!(book.take(stale).unwrap_err().contains("expired"));
+}
+
+// ###################################################### //
+// ################## MOCKED NEIGHBOURS ################# //
+// ###################################################### //
+
+fn roster(students: &[(&str, Grade)]) -> Roster {
+    Roster {
+        names: students.iter().map(|(name, _)| name.to_string()).collect(),
+        grades: students.iter().map(|(_, grade)| *grade).collect(),
+        ..Roster::default()
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn brightspace_submits_each_course_to_mock_admin() {
+    let (admin, mock) = AdminHandle::mocked(10, AdminScript::default());
+    let brightspace = BrightspaceHandle::new().await;
+    brightspace.set_admin(admin.feed().unwrap()).await.unwrap();
+    let coordinator = ActorId::next("John");
+    for course in ["VIP-UAV", "VIP-ROV"] {
+        let shell = brightspace.in_course(Course::new(course));
+        let students = roster(&[(course, Grade::Scored(80.0))]);
+        shell
+            .merge_roster_into_brightspace(coordinator, students)
+            .await
+            .unwrap();
+    }
+
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    tokio::task::yield_now().await;
+    assert_eq!(
+        mock.kinds(),
+        [
+            "ProcessStudentDump",
+            "ProcessGradeDump",
+            "ProcessLateDump",
+            "ProcessRubricDump",
+            "ProcessCommentDump",
+            "ProcessStudentDump",
+            "ProcessGradeDump",
+            "ProcessLateDump",
+            "ProcessRubricDump",
+            "ProcessCommentDump"
+        ]
+    );
+    let calls = mock.calls();
+    assert!(calls[0].contains("VIP-ROV") && calls[5].contains("VIP-UAV"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn dead_admin_fails_brightspace_report() {
+    let (admin, mock) = AdminHandle::mocked(8, AdminScript::default());
+    let brightspace = BrightspaceHandle::new().await;
+    brightspace.set_admin(admin.feed().unwrap()).await.unwrap();
+    let students = roster(&[("Aarya Patel", Grade::Ungraded)]);
+    brightspace
+        .merge_roster_into_brightspace(ActorId::next("John"), students)
+        .await
+        .unwrap();
+
+    mock.kill().await;
+    let result = brightspace.report_all_students_and_grades_to_admin().await;
+
+    assert_eq!(result, Err(ActorError::Closed { actor: "Admin" }));
+    assert!(mock.calls().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn booster_proposes_from_scripted_admin() {
+    let script = AdminScript {
+        roster: roster(&[
+            ("Aarya Patel", Grade::Scored(50.0)),
+            ("Ben Ortiz", Grade::Scored(70.0)),
+        ]),
+        proposal_id: 42,
+        ..AdminScript::default()
+    };
+    let (admin, mock) = AdminHandle::mocked(8, script);
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+
+    let id = booster
+        .boost_grades_with(BoostPolicy::Floor(60.0))
+        .await
+        .unwrap();
+
+    assert_eq!(id, 42);
+    assert_eq!(mock.kinds(), ["GetCourses", "ProposeGradeChanges"]);
+    let proposal = mock.calls().pop().unwrap();
+    assert!(proposal.contains(r#"name: "Aarya Patel", from: Scored(50.0), to: Scored(60.0)"#));
+    assert!(!proposal.contains("Ben Ortiz"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_admin_times_out_booster() {
+    let (admin, mock) = AdminHandle::mocked(8, AdminScript::default());
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+    mock.slow(Duration::from_secs(10));
+
+    let started = Instant::now();
+    let result = booster
+        .with_timeout(Duration::from_secs(1))
+        .boost_grades()
+        .await;
+
+    assert_eq!(result, Err(ActorError::Timeout { actor: "Admin" }));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(mock.kinds(), ["GetCourses"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn scripted_refusal_reaches_the_caller() {
+    let (admin, mock) = AdminHandle::mocked(8, AdminScript::default());
+    assert_eq!(admin.accept_proposal(7).await, Ok(()));
+
+    mock.script(|script| script.refusal = Some("grades are frozen".to_string()));
+    assert_eq!(
+        admin.accept_proposal(7).await,
+        Err(ActorError::Refused {
+            actor: "Admin",
+            reason: "grades are frozen".to_string()
+        })
+    );
+    assert_eq!(mock.kinds(), ["AcceptProposal", "AcceptProposal"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn john_report_fails_when_mock_brightspace_dies() {
+    let (brightspace, mock) = BrightspaceHandle::mocked(8, BrightspaceScript::default());
+    let john = JohnHandle::new().await;
+    john.set_brightspace(brightspace).await.unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    mock.kill().await;
+    let result = john.report_all_students_and_grades_to_brightspace().await;
+
+    assert_eq!(
+        result,
+        Err(ActorError::Closed {
+            actor: "Brightspace"
+        })
+    );
+    assert_eq!(mock.kinds(), ["MergeRoster"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn mock_john_and_booster_answer_from_their_scripts() {
+    let script = JohnScript {
+        roster: roster(&[("Aarya Patel", Grade::Incomplete)]),
+        report_error: Some(ActorError::Timeout {
+            actor: "Brightspace",
+        }),
+        ..JohnScript::default()
+    };
+    let (john, _mock) = JohnHandle::mocked(8, script);
+    assert_eq!(
+        john.get_all_student_grades().await.unwrap(),
+        [Grade::Incomplete]
+    );
+    assert_eq!(
+        john.report_all_students_and_grades_to_brightspace().await,
+        Err(ActorError::Timeout {
+            actor: "Brightspace"
+        })
+    );
+
+    let (booster, mock) = BoosterHandle::mocked(
+        8,
+        BoosterScript {
+            proposal_id: 3,
+            ..BoosterScript::default()
+        },
+    );
+    assert_eq!(booster.boost_grades().await, Ok(3));
+    mock.kill().await;
+    assert_eq!(
+        booster.boost_grades().await,
+        Err(ActorError::Closed { actor: "Booster" })
+    );
+}
+
+// ###################################################### //
+// ################ SNAPSHOT AND RESTORE ################ //
+// ###################################################### //
+
+const SNAPSHOT_TOPOLOGY: &str = r#"
+actors = [
+    { name = "john", kind = "John" },
+    { name = "rival", kind = "John" },
+    { name = "brightspace", kind = "Brightspace" },
+    { name = "admin", kind = "Admin" },
+    { name = "booster", kind = "Booster" },
+]
+links = [
+    { from = "john", to = "brightspace" },
+    { from = "rival", to = "brightspace" },
+    { from = "brightspace", to = "admin" },
+    { from = "booster", to = "admin" },
+]
+"#;
+
+#[tokio::test(start_paused = true)]
+async fn snapshot_restores_into_fresh_actors() {
+    let topology = Topology::parse(SNAPSHOT_TOPOLOGY).unwrap();
+    let before = topology.build().await.unwrap();
+    let (john, rival) = (before.john("john").unwrap(), before.john("rival").unwrap());
+    let brightspace = before.brightspace("brightspace").unwrap();
+    for (coordinator, grade) in [(john, 88.0), (rival, 64.0)] {
+        coordinator
+            .register_new_student("Aarya Patel".to_string())
+            .await
+            .unwrap();
+        coordinator
+            .assign_grade_to_student("Aarya Patel".to_string(), grade)
+            .await
+            .unwrap();
+        coordinator
+            .report_all_students_and_grades_to_brightspace()
+            .await
+            .unwrap();
+    }
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    let text = serde_json::to_string(&snapshot::take(&before).await.unwrap()).unwrap();
+
+    let after = topology.start().await.unwrap();
+    let taken: SystemSnapshot = serde_json::from_str(&text).unwrap();
+    snapshot::restore(&topology, &after, &taken).await.unwrap();
+
+    let (john, rival) = (after.john("john").unwrap(), after.john("rival").unwrap());
+    let (brightspace, admin) = (
+        after.brightspace("brightspace").unwrap(),
+        after.admin("admin").unwrap(),
+    );
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(88.0)]
+    );
+    assert_eq!(
+        rival.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(64.0)]
+    );
+    // Note: the restored owner and conflict point at the new actors, not the ones the snapshot came from
+    let conflicts = brightspace.get_conflicts().await.unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].owner, john.id());
+    assert_eq!(conflicts[0].rival, rival.id());
+    assert_eq!(snapshot::take(&after).await.unwrap().johns, taken.johns);
+
+    // The restored actors are wired: a new grade goes all the way through again
+    john.assign_grade_to_student("Aarya Patel".to_string(), 95.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(95.0)]
+    );
+    let booster = after.booster("booster").unwrap();
+    booster
+        .boost_grades_with(BoostPolicy::Perfect)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn restore_refuses_snapshots_it_cannot_place() {
+    let topology = Topology::parse(SNAPSHOT_TOPOLOGY).unwrap();
+    let actors = topology.start().await.unwrap();
+    let mut taken = snapshot::take(&actors).await.unwrap();
+
+    taken.version = SNAPSHOT_VERSION + 1;
+    assert!(snapshot::restore(&topology, &actors, &taken).await.is_err());
+
+    taken.version = SNAPSHOT_VERSION;
+    let ghost = taken.johns["john"].clone();
+    taken.johns.insert("ghost".to_string(), ghost);
+    let error = snapshot::restore(&topology, &actors, &taken)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("`ghost`"), "{}", error);
+}
+
+// ###################################################### //
+// ################# ADMIN UNDO AND REDO ################ //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn undo_takes_back_a_boost_and_redo_makes_it_again() {
+    let (john, brightspace, admin) = harness::chain().await;
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 58.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+    let id = booster
+        .boost_grades_with(BoostPolicy::Perfect)
+        .await
+        .unwrap();
+    admin.accept_proposal(id).await.unwrap();
+
+    // Note: the names and grades of one report are a single entry, so two undos go all the way back
+    let history = admin.history().await.unwrap();
+    assert_eq!(history.done.len(), 2);
+    let undone = admin.undo().await.unwrap();
+    assert!(undone.description.starts_with(&format!("proposal #{}", id)));
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(58.0)]
+    );
+    assert_eq!(undone.rosters[0].1.grades, [Grade::Scored(58.0)]);
+
+    admin.redo().await.unwrap();
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(100.0)]
+    );
+    admin.undo().await.unwrap();
+    admin.undo().await.unwrap();
+    assert!(admin.get_all_student_names().await.unwrap().is_empty());
+    assert!(matches!(
+        admin.undo().await,
+        Err(ActorError::Refused { .. })
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn new_change_clears_redo_and_limit_forgets_oldest() {
+    let admin = AdminHandle::new().await;
+    admin.set_history_limit(2).await.unwrap();
+    let feed = admin.feed().unwrap();
+    feed.submit_student_names(vec!["Aarya Patel".to_string()])
+        .await
+        .unwrap();
+    for grade in [70.0, 80.0, 90.0] {
+        feed.submit_student_grades(vec![Grade::Scored(grade)])
+            .await
+            .unwrap();
+    }
+
+    let history = admin.history().await.unwrap();
+    let descriptions: Vec<&str> = history
+        .done
+        .iter()
+        .map(|entry| entry.description.as_str())
+        .collect();
+    assert_eq!(
+        descriptions,
+        [
+            "Aarya Patel's grade 70 -> 80 in PART (default)",
+            "Aarya Patel's grade 80 -> 90 in PART (default)",
+        ]
+    );
+
+    admin.undo().await.unwrap();
+    feed.submit_student_grades(vec![Grade::Scored(85.0)])
+        .await
+        .unwrap();
+    assert!(matches!(
+        admin.redo().await,
+        Err(ActorError::Refused { .. })
+    ));
+    admin.undo().await.unwrap();
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(80.0)]
+    );
+}
+
+// ###################################################### //
+// ################### RECONCILIATION ################### //
+// ###################################################### //
+
+/// Aarya and Ben registered and synced with career IDs, then Admin regrades Aarya, Brightspace loses Ben, and
+/// John gains Cleo.
+async fn diverged() -> (JohnHandle, BrightspaceHandle, AdminHandle) {
+    let (john, brightspace, admin) = harness::chain().await;
+    for name in ["Aarya Patel", "Ben Ortiz"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    john.assign_grade_to_student("Aarya Patel".to_string(), 58.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    let id = admin
+        .propose_grade_changes(
+            "test".to_string(),
+            "regrade".to_string(),
+            vec![GradeChange {
+                course: CourseKey::default(),
+                name: "Aarya Patel (apatel)".to_string(),
+                from: Grade::Scored(58.0),
+                to: Grade::Scored(63.0),
+            }],
+        )
+        .await
+        .unwrap();
+    admin.accept_proposal(id).await.unwrap();
+    brightspace
+        .enter_students_into_brightspace(vec!["Aarya Patel (apatel)".to_string()])
+        .await
+        .unwrap();
+    brightspace
+        .enter_student_grades_into_brightspace(vec![Grade::Scored(58.0)])
+        .await
+        .unwrap();
+    john.register_new_student("Cleo Okafor".to_string())
+        .await
+        .unwrap();
+    (john, brightspace, admin)
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconcile_matches_by_career_id_and_reports_every_mismatch() {
+    let (john, brightspace, admin) = diverged().await;
+    let johns = BTreeMap::from([("john".to_string(), john.clone())]);
+    let sides = Sides {
+        johns: &johns,
+        lead: &john,
+        brightspace: &brightspace,
+        admin: &admin,
+    };
+
+    let found: Vec<(String, MismatchKind)> = reconcile::check(&sides)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.student, m.kind))
+        .collect();
+    // Note: "Aarya Patel" and "Aarya Patel (apatel)" are the same student, with the same name
+    assert_eq!(
+        found,
+        [
+            ("apatel".to_string(), MismatchKind::GradeDifference),
+            ("bortiz".to_string(), MismatchKind::MissingStudent),
+            ("cokafor".to_string(), MismatchKind::MissingStudent),
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconcile_fix_follows_the_truth_rule() {
+    let (john, brightspace, admin) = diverged().await;
+    let johns = BTreeMap::from([("john".to_string(), john.clone())]);
+    let sides = Sides {
+        johns: &johns,
+        lead: &john,
+        brightspace: &brightspace,
+        admin: &admin,
+    };
+
+    let rule: TruthRule = "john:admin".parse().unwrap();
+    assert_eq!(reconcile::fix(&sides, rule).await.unwrap().len(), 3);
+    assert!(reconcile::check(&sides).await.unwrap().is_empty());
+
+    // Note: John's students, with the career IDs Brightspace gave them and the grade Admin had
+    assert_eq!(
+        brightspace.get_all_student_names().await.unwrap(),
+        ["Aarya Patel (apatel)", "Ben Ortiz (bortiz)", "Cleo Okafor"]
+    );
+    assert_eq!(
+        admin.get_all_student_names().await.unwrap(),
+        brightspace.get_all_student_names().await.unwrap()
+    );
+    assert_eq!(
+        john.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(63.0), Grade::Ungraded, Grade::Ungraded]
+    );
+
+    // Admin's side of the fix is one undoable change
+    admin.undo().await.unwrap();
+    assert_eq!(
+        admin.get_all_student_names().await.unwrap(),
+        ["Aarya Patel (apatel)", "Ben Ortiz (bortiz)"]
+    );
+}
+
+// ###################################################### //
+// ############### GRADE HISTORY TIMELINES ############## //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn grade_history_records_every_value_and_who_set_it() {
+    let (john, brightspace, admin) = harness::chain().await;
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    for grade in [58.0, 66.0] {
+        john.assign_grade_to_student("Aarya Patel".to_string(), grade)
+            .await
+            .unwrap();
+        john.report_all_students_and_grades_to_brightspace()
+            .await
+            .unwrap();
+        brightspace
+            .report_all_students_and_grades_to_admin()
+            .await
+            .unwrap();
+    }
+    // Note: a grade changed in Brightspace itself is Brightspace's until John sends the student again
+    brightspace
+        .enter_student_grades_into_brightspace(vec![Grade::Scored(70.0)])
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    sync(&john, &brightspace, &admin).await;
+    let booster = BoosterHandle::new().await;
+    booster
+        .set_admin(admin.approve_booster().unwrap())
+        .await
+        .unwrap();
+    let id = booster
+        .boost_grades_with(BoostPolicy::Perfect)
+        .await
+        .unwrap();
+    admin.accept_proposal(id).await.unwrap();
+    admin.undo().await.unwrap();
+
+    let history = admin.grade_history("Aarya Patel").await.unwrap();
+    let points: Vec<(Grade, GradeSource)> = history
+        .points
+        .iter()
+        .map(|p| (p.grade, p.set_by.clone()))
+        .collect();
+    // Note: the first sync brings the name and the grade together, so "ungraded" never shows up in Admin
+    assert!(matches!(
+        &points[..],
+        [
+            (Grade::Scored(58.0), GradeSource::John),
+            (Grade::Scored(66.0), GradeSource::John),
+            (Grade::Scored(70.0), GradeSource::BrightspaceEdit),
+            (Grade::Scored(66.0), GradeSource::John),
+            (Grade::Scored(100.0), GradeSource::Proposal { .. }),
+            (Grade::Scored(66.0), GradeSource::Undo),
+        ]
+    ));
+    assert_eq!(history.trends.len(), 1);
+    assert_eq!(history.trends[0].trend.change, 8.0);
+
+    // The career ID finds the same student, and another course has none of their points
+    assert_eq!(admin.grade_history("apatel").await.unwrap(), history);
+    let elsewhere = admin.in_course(Course::new("VIP-UAV"));
+    assert!(
+        elsewhere
+            .grade_history("Aarya Patel")
+            .await
+            .unwrap()
+            .points
+            .is_empty()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn grade_history_trends_each_course_on_its_own() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let uav = Course::new("VIP-UAV");
+    for (john, grades) in [
+        (john.clone(), [50.0, 60.0]),
+        (john.in_course(uav.clone()), [95.0, 85.0]),
+    ] {
+        john.register_new_student("Aarya Patel".to_string())
+            .await
+            .unwrap();
+        for grade in grades {
+            john.assign_grade_to_student("Aarya Patel".to_string(), grade)
+                .await
+                .unwrap();
+            sync(&john, &brightspace, &admin).await;
+        }
+    }
+
+    // Note: over both courses the scores went from 50 to 85, which is no one's trend
+    let history = admin.grade_history("Aarya Patel").await.unwrap();
+    let trends: Vec<(String, f64)> = history
+        .trends
+        .iter()
+        .map(|t| (t.course.course.to_string(), t.trend.change))
+        .collect();
+    assert_eq!(
+        trends,
+        [
+            (Course::default().to_string(), 10.0),
+            (uav.to_string(), -10.0)
+        ]
+    );
+}
+
+#[test]
+fn trend_slope_is_points_per_week_once_scores_span_a_day() {
+    let start = SystemTime::UNIX_EPOCH;
+    let point = |days: u64, score: f64| GradePoint {
+        course: CourseKey::default(),
+        grade: Grade::Scored(score),
+        at: start + Duration::from_secs(days * 24 * 60 * 60),
+        set_by: GradeSource::John,
+    };
+
+    let weekly = Trend::of(&[point(0, 60.0), point(7, 65.0), point(14, 70.0)]).unwrap();
+    assert_eq!(weekly.change, 10.0);
+    assert!((weekly.per_week.unwrap() - 5.0).abs() < 1e-9);
+
+    let same_moment = Trend::of(&[point(0, 60.0), point(0, 90.0)]).unwrap();
+    assert_eq!(same_moment.per_week, None);
+    assert_eq!(Trend::of(&[point(0, 60.0)]), None);
+}
+
+// ###################################################### //
+// ############# DEADLINES AND LATE PENALTIES ########### //
+// ###################################################### //
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn design_review() -> Deadlines {
+    Deadlines {
+        policy: LatePolicy {
+            percent_per_day: 10.0,
+            grace_hours: 1.0,
+            max_days_late: Some(5.0),
+        },
+        assessments: vec![Assessment {
+            name: "design-review".to_string(),
+            due: "2026-10-20T23:59:00Z".parse().unwrap(),
+            extensions: BTreeMap::from([("Aarya Patel".to_string(), 2.0)]),
+        }],
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn late_work_reaches_admin_with_raw_and_penalised_grades() {
+    let (john, brightspace, admin) = harness::chain().await;
+    john.set_deadlines(design_review()).await.unwrap();
+    for name in ["Aarya Patel", "Ben Ortiz"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    let due = design_review().assessments[0].due.0;
+
+    // Ben is a day and a bit late; Aarya is just as late, but within her two-day extension
+    let ben = john
+        .record_submission(
+            "Ben Ortiz".to_string(),
+            "design-review".to_string(),
+            80.0,
+            due + DAY + DAY / 4,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ben.grade, Grade::Scored(64.0));
+    let aarya = john
+        .record_submission(
+            "Aarya Patel".to_string(),
+            "design-review".to_string(),
+            90.0,
+            due + DAY + DAY / 4,
+        )
+        .await
+        .unwrap();
+    assert_eq!(aarya.grade, Grade::Scored(90.0));
+    assert_eq!(aarya.late, None);
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    let late = admin.get_late_work().await.unwrap();
+    assert_eq!(late.len(), 1);
+    assert_eq!(late[0].student, "Ben Ortiz (bortiz)");
+    assert_eq!(late[0].grade, Grade::Scored(64.0));
+    assert_eq!(late[0].work.raw, Grade::Scored(80.0));
+    assert_eq!(late[0].work.penalty_percent, 20.0);
+
+    // A grade given by hand has no penalty behind it, so the next sync takes Ben's away
+    john.assign_grade_to_student("Ben Ortiz".to_string(), 85.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    assert!(admin.get_late_work().await.unwrap().is_empty());
+
+    let unknown = john
+        .record_submission("Ben Ortiz".to_string(), "final-demo".to_string(), 80.0, due)
+        .await;
+    assert!(matches!(unknown, Err(ActorError::Refused { .. })));
+}
+
+#[test]
+fn late_policy_forgives_the_grace_period_and_zeroes_work_past_the_maximum() {
+    let deadlines = design_review();
+    let due = deadlines.assessments[0].due.0;
+    let assess = |raw: Grade, at: SystemTime| {
+        deadlines
+            .assess("design-review", "Ben Ortiz", raw, at)
+            .unwrap()
+    };
+
+    assert_eq!(assess(Grade::Scored(80.0), due + DAY / 48).late, None);
+    assert_eq!(
+        assess(Grade::Scored(80.0), due + DAY / 12).grade,
+        Grade::Scored(72.0)
+    );
+    let too_late = assess(Grade::Scored(80.0), due + DAY * 6);
+    assert_eq!(too_late.grade, Grade::Scored(0.0));
+    assert_eq!(too_late.late.unwrap().penalty_percent, 100.0);
+    // Note: only scores are marked down
+    assert_eq!(
+        assess(Grade::Incomplete, due + DAY * 2).grade,
+        Grade::Incomplete
+    );
+
+    let stamp: Timestamp = "2026-10-20 23:59".parse().unwrap();
+    assert_eq!(stamp, deadlines.assessments[0].due);
+    assert_eq!(stamp.to_string(), "2026-10-20T23:59:00Z");
+    assert!("2026-02-30T10:00Z".parse::<Timestamp>().is_err());
+    assert!("2026-10-20".parse::<Timestamp>().is_err());
+}
+
+// ###################################################### //
+// ################## RUBRIC GRADING #################### //
+// ###################################################### //
+
+fn design_review_rubric() -> Rubric {
+    let criterion = |name: &str, levels: &[(&str, f64)]| Criterion {
+        name: name.to_string(),
+        levels: levels
+            .iter()
+            .map(|(name, points)| Level {
+                name: name.to_string(),
+                points: *points,
+            })
+            .collect(),
+    };
+    Rubric {
+        name: "design-review".to_string(),
+        criteria: vec![
+            criterion(
+                "requirements",
+                &[("excellent", 40.0), ("good", 30.0), ("weak", 10.0)],
+            ),
+            criterion(
+                "safety",
+                &[("excellent", 20.0), ("good", 15.0), ("missing", 0.0)],
+            ),
+        ],
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn rubric_breakdown_reaches_admin_next_to_the_grade() {
+    let (john, brightspace, admin) = harness::chain().await;
+    john.set_rubrics(vec![design_review_rubric()])
+        .await
+        .unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+
+    let score = john
+        .grade_with_rubric(
+            "Aarya Patel".to_string(),
+            "design-review".to_string(),
+            vec![
+                Selection::new("requirements", "good"),
+                Selection::new("safety", "excellent"),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!((score.points, score.max_points), (50.0, 60.0));
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    let reports = admin.get_rubric_scores().await.unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].score, score);
+    assert_eq!(reports[0].grade, score.grade());
+    let levels: Vec<&str> = reports[0]
+        .score
+        .criteria
+        .iter()
+        .map(|c| c.level.as_str())
+        .collect();
+    assert_eq!(levels, ["good", "excellent"]);
+
+    // A grade given by hand replaces the rubric score, and the breakdown goes with it
+    john.assign_grade_to_student("Aarya Patel".to_string(), 70.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    assert!(admin.get_rubric_scores().await.unwrap().is_empty());
+}
+
+#[test]
+fn rubric_needs_exactly_one_known_level_of_every_criterion() {
+    let rubric = design_review_rubric();
+    assert!(rubric.validate().is_ok());
+
+    let score = rubric
+        .score(&[
+            "requirements=excellent".parse().unwrap(),
+            "safety=missing".parse().unwrap(),
+        ])
+        .unwrap();
+    assert_eq!(score.grade(), Grade::Scored(40.0 / 60.0 * 100.0));
+
+    let missing = rubric.score(&[Selection::new("requirements", "good")]);
+    assert!(missing.unwrap_err().contains("safety"));
+    let twice = rubric.score(&[
+        Selection::new("requirements", "good"),
+        Selection::new("requirements", "weak"),
+        Selection::new("safety", "good"),
+    ]);
+    assert!(twice.is_err());
+    let unknown = rubric.score(&[
+        Selection::new("requirements", "stellar"),
+        Selection::new("safety", "good"),
+    ]);
+    assert!(unknown.unwrap_err().contains("stellar"));
+    assert!("requirements".parse::<Selection>().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn feedback_reaches_the_lms_export_and_admin_next_to_the_grade() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let bank = CommentBank {
+        templates: BTreeMap::from([(
+            "needs-docs".to_string(),
+            "{name}, your {grade} needs a README.".to_string(),
+        )]),
+    };
+    john.set_comment_bank(bank).await.unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 72.0)
+        .await
+        .unwrap();
+
+    let written = CommentText::Written("Solid test plan.".to_string());
+    let comment = john
+        .comment_on("Aarya Patel".to_string(), written)
+        .await
+        .unwrap();
+    assert_eq!(
+        (comment.grade, comment.template),
+        (Grade::Scored(72.0), None)
+    );
+    let banked = CommentText::Bank("needs-docs".to_string());
+    let comment = john
+        .comment_on("Aarya Patel".to_string(), banked)
+        .await
+        .unwrap();
+    assert_eq!(comment.text, "Aarya Patel, your 72.0 needs a README.");
+    let unknown = CommentText::Bank("great-work".to_string());
+    let refused = john.comment_on("Aarya Patel".to_string(), unknown).await;
+    assert!(matches!(refused, Err(ActorError::Refused { .. })));
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+
+    let expected = ["Solid test plan.", "Aarya Patel, your 72.0 needs a README."];
+    let export = brightspace.lms_export().await.unwrap();
+    assert_eq!(export.len(), 1);
+    assert_eq!(export[0].student, "Aarya Patel (apatel)");
+    assert_eq!(export[0].feedback, expected);
+
+    let reports = admin.get_feedback().await.unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].grade, Grade::Scored(72.0));
+    let texts: Vec<&str> = reports[0]
+        .comments
+        .iter()
+        .map(|c| c.text.as_str())
+        .collect();
+    assert_eq!(texts, expected);
+
+    // Feedback outlives a new grade, it is only gone once John takes it back
+    let sync = async || {
+        john.report_all_students_and_grades_to_brightspace()
+            .await
+            .unwrap();
+        brightspace
+            .report_all_students_and_grades_to_admin()
+            .await
+            .unwrap();
+    };
+    john.assign_grade_to_student("Aarya Patel".to_string(), 80.0)
+        .await
+        .unwrap();
+    sync().await;
+    let reports = admin.get_feedback().await.unwrap();
+    assert_eq!(reports[0].grade, Grade::Scored(80.0));
+    assert_eq!(reports[0].comments.len(), 2);
+
+    john.clear_comments("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    sync().await;
+    assert!(admin.get_feedback().await.unwrap().is_empty());
+    assert!(
+        brightspace.lms_export().await.unwrap()[0]
+            .feedback
+            .is_empty()
+    );
+}
+
+// ###################################################### //
+// ############ ATTENDANCE -> JOHN -> ADMIN ############# //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn participation_reaches_admin_like_any_other_grade() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let mut deadlines = design_review();
+    deadlines.assessments.push(Assessment {
+        name: "participation".to_string(),
+        due: "2026-12-11T23:59:00Z".parse().unwrap(),
+        extensions: BTreeMap::new(),
+    });
+    john.set_deadlines(deadlines).await.unwrap();
+    for name in ["Aarya Patel", "Ben Ortiz", "Cara Lin"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    let attendance = AttendanceHandle::new().await;
+    attendance.set_john(john.clone()).await.unwrap();
+
+    use AttendanceStatus::*;
+    let first: Timestamp = "2026-10-01T18:00:00Z".parse().unwrap();
+    let meetings = [
+        [
+            ("Aarya Patel", Present),
+            ("Ben Ortiz", Late),
+            ("Cara Lin", Excused),
+        ],
+        [
+            ("Aarya Patel", Excused),
+            ("Ben Ortiz", Absent),
+            ("Cara Lin", Excused),
+        ],
+        [
+            ("Aarya Patel", Present),
+            ("Ben Ortiz", Present),
+            ("Cara Lin", Excused),
+        ],
+    ];
+    for (week, marks) in meetings.into_iter().enumerate() {
+        let marks = marks.map(|(name, status)| Mark::new(name, status)).to_vec();
+        let held_at = first.0 + DAY * 7 * week as u32;
+        let meeting = attendance.record_meeting(held_at, marks).await.unwrap();
+        assert_eq!(meeting, week + 1);
+    }
+    // Ben had a note for the second meeting after all
+    attendance
+        .mark(2, Mark::new("Ben Ortiz", Excused))
+        .await
+        .unwrap();
+    let missing = attendance.mark(4, Mark::new("Ben Ortiz", Excused)).await;
+    assert!(matches!(missing, Err(ActorError::Refused { .. })));
+
+    let scores = attendance.submit_participation().await.unwrap();
+    let grades: Vec<(&str, Grade)> = scores
+        .iter()
+        .map(|p| (p.student.as_str(), p.grade))
+        .collect();
+    assert_eq!(
+        grades,
+        [
+            ("Aarya Patel", Grade::Scored(100.0)),
+            ("Ben Ortiz", Grade::Scored(75.0)),
+            ("Cara Lin", Grade::Excused),
+        ]
+    );
+
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    assert_eq!(
+        admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(100.0), Grade::Scored(75.0), Grade::Excused]
+    );
+    // Note: the scores count as handed in at the last meeting, well before participation is due
+    assert!(admin.get_late_work().await.unwrap().is_empty());
+}
+
+#[test]
+fn attendance_policy_and_marks_are_checked() {
+    let mark: Mark = "Aarya Patel=Late".parse().unwrap();
+    assert_eq!(mark, Mark::new("Aarya Patel", AttendanceStatus::Late));
+    assert!("Aarya Patel=sleeping".parse::<Mark>().is_err());
+    assert!("=present".parse::<Mark>().is_err());
+
+    let topology = r#"
+actors = [{ name = "john", kind = "John" }]
+
+[attendance]
+late = 150.0
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(&error, TopologyError::Attendance(reason) if reason.contains("late")));
+}
+
+#[tokio::test(start_paused = true)]
+async fn attendance_refuses_a_policy_that_would_not_load() {
+    let attendance = AttendanceHandle::new().await;
+    for policy in [
+        ParticipationPolicy {
+            late: f64::NAN,
+            ..ParticipationPolicy::default()
+        },
+        ParticipationPolicy {
+            absent: -10.0,
+            ..ParticipationPolicy::default()
+        },
+        ParticipationPolicy {
+            assessment: " ".to_string(),
+            ..ParticipationPolicy::default()
+        },
+    ] {
+        assert!(matches!(
+            attendance.set_policy(policy).await,
+            Err(ActorError::Refused { .. })
+        ));
+    }
+    attendance
+        .set_policy(ParticipationPolicy::default())
+        .await
+        .unwrap();
+}
+
+// ###################################################### //
+// ################## TOPOLOGY CHECKS ################### //
+// ###################################################### //
+
+#[test]
+fn actor_named_twice_is_refused() {
+    let topology = r#"
+actors = [
+    { name = "admin", kind = "Admin" },
+    { name = "admin", kind = "Admin" },
+]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(&error, TopologyError::DuplicateActor { name } if name == "admin"));
+}
+
+#[test]
+fn empty_mailbox_is_refused() {
+    let topology = r#"
+actors = [{ name = "admin", kind = "Admin", mailbox = 0 }]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(&error, TopologyError::ZeroMailbox { name } if name == "admin"));
+}
+
+#[test]
+fn link_listed_twice_is_refused() {
+    let topology = r#"
+actors = [
+    { name = "brightspace", kind = "Brightspace" },
+    { name = "admin", kind = "Admin" },
+]
+links = [
+    { from = "brightspace", to = "admin" },
+    { from = "brightspace", to = "admin" },
+]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(
+        &error,
+        TopologyError::DuplicateLink { from, to } if from == "brightspace" && to == "admin"
+    ));
+}
+
+#[test]
+fn link_to_the_wrong_kind_is_refused() {
+    let topology = r#"
+actors = [
+    { name = "john", kind = "John" },
+    { name = "admin", kind = "Admin" },
+]
+links = [{ from = "john", to = "admin" }]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(
+        &error,
+        TopologyError::WrongLink { from, to, expected: ActorKind::Brightspace }
+            if from == "john" && to == "admin"
+    ));
+}
+
+#[test]
+fn actor_linked_to_two_of_its_kind_is_refused() {
+    let topology = r#"
+actors = [
+    { name = "brightspace", kind = "Brightspace" },
+    { name = "admin", kind = "Admin" },
+    { name = "backup", kind = "Admin" },
+]
+links = [
+    { from = "brightspace", to = "admin" },
+    { from = "brightspace", to = "backup" },
+]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(
+        &error,
+        TopologyError::TooManyLinks { from, expected: ActorKind::Admin } if from == "brightspace"
+    ));
+}
+
+#[test]
+fn actor_without_its_link_is_refused() {
+    let topology = r#"
+actors = [
+    { name = "brightspace", kind = "Brightspace" },
+    { name = "admin", kind = "Admin" },
+]
+"#;
+    let error = Topology::parse(topology).unwrap_err();
+    assert!(matches!(
+        &error,
+        TopologyError::MissingLink { from, expected: ActorKind::Admin } if from == "brightspace"
+    ));
+}
+
+// ###################################################### //
+// ################ ADMIN CHANGE EVENTS ################# //
+// ###################################################### //
+
+/// Every notice waiting for `subscription` right now.
+fn waiting(subscription: &mut Subscription) -> Vec<Notice> {
+    std::iter::from_fn(|| subscription.try_recv()).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn sync_publishes_each_student_once_with_their_grade() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let mut everything = admin.subscribe(EventFilter::default()).await.unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.register_new_student("Ben Ortiz".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 91.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    // Note: Admin answers in mailbox order, so every dump above has been handled by the time this returns
+    admin.get_all_student_names().await.unwrap();
+
+    // Note: the names arrive a message before their grades, but nobody hears about them ungraded in between
+    assert_eq!(
+        waiting(&mut everything),
+        [
+            Notice::Event(AdminEvent::StudentAdded {
+                course: CourseKey::default(),
+                name: "Aarya Patel".to_string(),
+                grade: Grade::Scored(91.0),
+            }),
+            Notice::Event(AdminEvent::StudentAdded {
+                course: CourseKey::default(),
+                name: "Ben Ortiz".to_string(),
+                grade: Grade::Ungraded,
+            }),
+        ]
+    );
+    assert_eq!(everything.missed(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn subscribers_only_hear_what_passes_their_filter() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let failing = EventFilter::default().kinds([EventKind::StudentFellBelowPassing]);
+    let mut failing = admin.subscribe(failing).await.unwrap();
+    // Note: a filter by name also matches the student once Brightspace appends their career ID
+    let mut ben = admin
+        .subscribe(EventFilter::default().student("Ben Ortiz (bortiz)"))
+        .await
+        .unwrap();
+
+    for (name, score) in [("Aarya Patel", 91.0), ("Ben Ortiz", 72.0)] {
+        john.register_new_student(name.to_string()).await.unwrap();
+        john.assign_grade_to_student(name.to_string(), score)
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 40.0)
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    admin.get_all_student_names().await.unwrap();
+
+    assert_eq!(
+        waiting(&mut failing),
+        [Notice::Event(AdminEvent::StudentFellBelowPassing {
+            course: CourseKey::default(),
+            name: "Aarya Patel".to_string(),
+            grade: 40.0,
+        })]
+    );
+    assert_eq!(
+        waiting(&mut ben),
+        [Notice::Event(AdminEvent::StudentAdded {
+            course: CourseKey::default(),
+            name: "Ben Ortiz".to_string(),
+            grade: Grade::Scored(72.0),
+        })]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_subscriber_is_told_how_many_events_it_missed() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let mut slow = admin.subscribe(EventFilter::default()).await.unwrap();
+    let students = EVENT_BUFFER + 6;
+    for n in 0..students {
+        john.register_new_student(format!("Student {:02}", n))
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    admin.get_all_student_names().await.unwrap();
+
+    // Note: the oldest events are the ones dropped, and the newest `EVENT_BUFFER` are still delivered
+    let notices = waiting(&mut slow);
+    assert_eq!(notices[0], Notice::Lagged { missed: 6 });
+    assert_eq!(notices.len(), 1 + EVENT_BUFFER);
+    assert_eq!(
+        notices[1],
+        Notice::Event(AdminEvent::StudentAdded {
+            course: CourseKey::default(),
+            name: "Student 06".to_string(),
+            grade: Grade::Ungraded,
+        })
+    );
+    assert_eq!(slow.missed(), 6);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_student_in_two_courses_keeps_a_grade_in_each() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let mut events = admin.subscribe(EventFilter::default()).await.unwrap();
+    let alerter = AlerterHandle::new().await;
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    let portal = StudentPortalHandle::new().await;
+    portal.watch_admin(&admin.read_only()).await.unwrap();
+    let uav = CourseKey {
+        course: Course::new("VIP-UAV"),
+        ..CourseKey::default()
+    };
+    let rov = CourseKey {
+        course: Course::new("VIP-ROV"),
+        ..CourseKey::default()
+    };
+    for (key, score) in [(&uav, 90.0), (&rov, 50.0)] {
+        let john = john.in_course(key.course.clone());
+        john.register_new_student("Aarya Patel".to_string())
+            .await
+            .unwrap();
+        john.assign_grade_to_student("Aarya Patel".to_string(), score)
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    admin.get_all_student_names().await.unwrap();
+
+    // Note: courses arrive in course order, so ROV first; neither grade is a change from the other
+    let added = |course: &CourseKey, grade| {
+        Notice::Event(AdminEvent::StudentAdded {
+            course: course.clone(),
+            name: "Aarya Patel (apatel)".to_string(),
+            grade,
+        })
+    };
+    assert_eq!(
+        waiting(&mut events),
+        [
+            added(&rov, Grade::Scored(50.0)),
+            Notice::Event(AdminEvent::StudentFellBelowPassing {
+                course: rov.clone(),
+                name: "Aarya Patel (apatel)".to_string(),
+                grade: 50.0,
+            }),
+            added(&uav, Grade::Scored(90.0)),
+        ]
+    );
+
+    // Note: a newcomer in UAV changes nothing for Aarya in either course
+    john.in_course(uav.course.clone())
+        .register_new_student("Ben Ortiz".to_string())
+        .await
+        .unwrap();
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    admin.get_all_student_names().await.unwrap();
+    assert_eq!(
+        waiting(&mut events),
+        [Notice::Event(AdminEvent::StudentAdded {
+            course: uav.clone(),
+            name: "Ben Ortiz (bortiz)".to_string(),
+            grade: Grade::Ungraded,
+        })]
+    );
+
+    alerter.flush().await.unwrap();
+    let alerts = alerter.get_alerts().await.unwrap();
+    let raised: Vec<_> = alerts
+        .iter()
+        .map(|alert| (&alert.course, alert.student.as_str(), &alert.reason))
+        .collect();
+    let below = AlertReason::Below {
+        threshold: PASSING_GRADE,
+    };
+    assert_eq!(
+        raised,
+        [
+            (&rov, "Aarya Patel (apatel)", &below),
+            (&uav, "Ben Ortiz (bortiz)", &AlertReason::NoGrade),
+        ]
+    );
+
+    let record = portal.look_up("apatel").await.unwrap().unwrap();
+    let grades: Vec<_> = record
+        .courses
+        .iter()
+        .map(|c| (&c.course, c.grade, c.letter))
+        .collect();
+    assert_eq!(
+        grades,
+        [
+            (&rov, Grade::Scored(50.0), Some('F')),
+            (&uav, Grade::Scored(90.0), Some('A')),
+        ]
+    );
+}
+
+// ###################################################### //
+// ###################### ALERTER ####################### //
+// ###################################################### //
+
+/// Sends John's rosters through Brightspace to Admin, and waits until Admin has handled them.
+async fn sync(john: &JohnHandle, brightspace: &BrightspaceHandle, admin: &AdminHandle) {
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    admin.get_all_student_names().await.unwrap();
+}
+
+/// Keeps every alert it is sent, as `Display` prints it.
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl Notifier for Recorder {
+    fn describe(&self) -> String {
+        "recorder".to_string()
+    }
+
+    fn notify<'a>(&'a mut self, alert: &'a Alert) -> Notifying<'a> {
+        self.0.lock().unwrap().push(alert.to_string());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn reasons(alerts: &[Alert]) -> Vec<(&str, &AlertReason)> {
+    alerts
+        .iter()
+        .map(|alert| (alert.student.as_str(), &alert.reason))
+        .collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn graded_student_arriving_by_sync_raises_no_no_grade_alert() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+    john.register_new_student("Ben Ortiz".to_string())
+        .await
+        .unwrap();
+    john.assign_grade_to_student("Aarya Patel".to_string(), 91.0)
+        .await
+        .unwrap();
+    sync(&john, &brightspace, &admin).await;
+
+    alerter.flush().await.unwrap();
+    let alerts = alerter.get_alerts().await.unwrap();
+    assert_eq!(reasons(&alerts), [("Ben Ortiz", &AlertReason::NoGrade)]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn alerts_fire_on_crossing_a_threshold_or_dropping_sharply() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    let rules = AlertRules {
+        thresholds: vec![50.0, 70.0],
+        sharp_drop: 15.0,
+    };
+    alerter.set_rules(rules).await.unwrap();
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    john.register_new_student("Aarya Patel".to_string())
+        .await
+        .unwrap();
+
+    // Note: 65 crosses 70 by a sharp drop, 60 stays under 70 without crossing anything new
+    for score in [80.0, 65.0, 60.0, 45.0] {
+        john.assign_grade_to_student("Aarya Patel".to_string(), score)
+            .await
+            .unwrap();
+        sync(&john, &brightspace, &admin).await;
+    }
+
+    let alerts = alerter.get_alerts().await.unwrap();
+    assert_eq!(
+        reasons(&alerts),
+        [
+            ("Aarya Patel", &AlertReason::Below { threshold: 70.0 }),
+            ("Aarya Patel", &AlertReason::SharpDrop { from: 80.0 }),
+            ("Aarya Patel", &AlertReason::Below { threshold: 50.0 }),
+            ("Aarya Patel", &AlertReason::SharpDrop { from: 60.0 }),
+        ]
+    );
+    assert_eq!(
+        alerter.get_at_risk().await.unwrap(),
+        [(
+            CourseKey::default(),
+            "Aarya Patel".to_string(),
+            Grade::Scored(45.0),
+            AlertReason::Below { threshold: 50.0 }
+        )]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn every_notifier_hears_every_alert_even_when_another_fails() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    let heard = Arc::new(Mutex::new(Vec::new()));
+    let path = std::env::temp_dir().join(format!("alerts-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    // Note: a file in a directory that does not exist, so every alert sent to it fails
+    let broken = std::env::temp_dir()
+        .join("no-such-directory")
+        .join("alerts.jsonl");
+    alerter
+        .add_notifier(Box::new(FileNotifier::new(broken)))
+        .await
+        .unwrap();
+    alerter
+        .add_notifier(Box::new(Recorder(heard.clone())))
+        .await
+        .unwrap();
+    alerter
+        .add_notifier(Box::new(FileNotifier::new(path.clone())))
+        .await
+        .unwrap();
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+
+    for (name, score) in [("Aarya Patel", 40.0), ("Ben Ortiz", 91.0)] {
+        john.register_new_student(name.to_string()).await.unwrap();
+        john.assign_grade_to_student(name.to_string(), score)
+            .await
+            .unwrap();
+    }
+    sync(&john, &brightspace, &admin).await;
+    alerter.flush().await.unwrap();
+
+    let alerts = alerter.get_alerts().await.unwrap();
+    assert_eq!(
+        reasons(&alerts),
+        [(
+            "Aarya Patel",
+            &AlertReason::Below {
+                threshold: PASSING_GRADE
+            }
+        )]
+    );
+    let expected: Vec<String> = alerts.iter().map(Alert::to_string).collect();
+    assert_eq!(*heard.lock().unwrap(), expected);
+    let written = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = written
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["student"], "Aarya Patel");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test(start_paused = true)]
+async fn removed_student_leaves_the_at_risk_list() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    let mut removals = admin
+        .subscribe(EventFilter::default().kinds([EventKind::StudentRemoved]))
+        .await
+        .unwrap();
+    for name in ["Aarya Patel", "Ben Ortiz"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    sync(&john, &brightspace, &admin).await;
+    let at_risk = || async {
+        let at_risk = alerter.get_at_risk().await.unwrap();
+        at_risk
+            .into_iter()
+            .map(|(_, name, ..)| name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(at_risk().await, ["Aarya Patel", "Ben Ortiz"]);
+
+    john.remove_student("Ben Ortiz".to_string()).await.unwrap();
+    sync(&john, &brightspace, &admin).await;
+    assert_eq!(
+        waiting(&mut removals),
+        [Notice::Event(AdminEvent::StudentRemoved {
+            course: CourseKey::default(),
+            name: "Ben Ortiz".to_string(),
+        })]
+    );
+    assert_eq!(at_risk().await, ["Aarya Patel"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn alerter_keeps_only_the_latest_alerts() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    // Note: every new student arrives ungraded, which is one alert each; a few at a time, so no event is missed
+    for i in 0..=MAX_ALERTS {
+        john.register_new_student(format!("Student {}", i))
+            .await
+            .unwrap();
+        if i % 25 == 0 {
+            sync(&john, &brightspace, &admin).await;
+        }
+    }
+    sync(&john, &brightspace, &admin).await;
+
+    let alerts = alerter.get_alerts().await.unwrap();
+    assert_eq!(alerts.len(), MAX_ALERTS);
+    assert_eq!(alerts[0].student, "Student 1");
+    assert_eq!(
+        alerts[MAX_ALERTS - 1].student,
+        format!("Student {}", MAX_ALERTS)
+    );
+}
+
+/// Takes an hour to hear each alert, like a webhook that never answers (but without a timeout).
+struct Sluggish;
+
+impl Notifier for Sluggish {
+    fn describe(&self) -> String {
+        "sluggish".to_string()
+    }
+
+    fn notify<'a>(&'a mut self, _: &'a Alert) -> Notifying<'a> {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_notifier_does_not_hold_up_the_alerter() {
+    let (john, brightspace, admin) = harness::chain().await;
+    let alerter = AlerterHandle::new().await;
+    alerter.add_notifier(Box::new(Sluggish)).await.unwrap();
+    alerter.watch_admin(&admin.read_only()).await.unwrap();
+    for name in ["Aarya Patel", "Ben Ortiz"] {
+        john.register_new_student(name.to_string()).await.unwrap();
+    }
+    sync(&john, &brightspace, &admin).await;
+
+    let quick = alerter.with_timeout(Duration::from_secs(1));
+    assert_eq!(quick.get_alerts().await.unwrap().len(), 2);
+    assert_eq!(quick.get_at_risk().await.unwrap().len(), 2);
+    // Note: flushing does wait for the notifier to hear both alerts
+    assert_eq!(
+        quick.flush().await,
+        Err(ActorError::Timeout { actor: "Alerter" })
+    );
+    alerter
+        .with_timeout(Duration::from_secs(3 * 60 * 60))
+        .flush()
+        .await
+        .unwrap();
+}
+
+#[test]
+fn webhook_notifier_needs_a_plain_http_url() {
+    assert!(
+        "webhook:http://127.0.0.1:9000/alerts"
+            .parse::<NotifierSpec>()
+            .is_ok()
+    );
+    assert!(
+        "webhook:https://example.com/alerts"
+            .parse::<NotifierSpec>()
+            .is_err()
+    );
+    assert!("webhook:http:///alerts".parse::<NotifierSpec>().is_err());
+}
+
+// ###################################################### //
+// ################### STUDENT PORTAL ################### //
+// ###################################################### //
+
+/// A portal watching an Admin that got `students` (graded) with the career IDs Brightspace gave them.
+async fn portal_over(students: &[(&str, f64)]) -> StudentPortalHandle {
+    let (john, brightspace, admin) = harness::chain().await;
+    for (name, score) in students {
+        john.register_new_student(name.to_string()).await.unwrap();
+        john.assign_grade_to_student(name.to_string(), *score)
+            .await
+            .unwrap();
+    }
+    john.report_all_students_and_grades_to_brightspace()
+        .await
+        .unwrap();
+    brightspace
+        .generate_and_append_student_career_id()
+        .await
+        .unwrap();
+    brightspace
+        .report_all_students_and_grades_to_admin()
+        .await
+        .unwrap();
+    let portal = StudentPortalHandle::new().await;
+    portal.watch_admin(&admin.read_only()).await.unwrap();
+    portal
+}
+
+#[tokio::test(start_paused = true)]
+async fn students_see_their_own_record_and_nobody_else_s() {
+    let portal = portal_over(&[("Aarya Patel", 91.0), ("Ben Ortiz", 55.0)]).await;
+
+    let aarya = portal.acting_as(Caller::new("apatel", Role::Student));
+    let record = aarya.my_record().await.unwrap().unwrap();
+    assert_eq!(record.name, "Aarya Patel");
+    assert_eq!(record.courses.len(), 1);
+    assert_eq!(record.courses[0].grade, Grade::Scored(91.0));
+    assert_eq!(record.courses[0].letter, Some('A'));
+    let peeking = aarya.look_up("bortiz").await;
+    assert!(matches!(peeking, Err(ActorError::Denied { .. })));
+
+    let ta = portal.acting_as(Caller::new("Dane Hindsley", Role::Ta));
+    let ben = ta.look_up("bortiz").await.unwrap().unwrap();
+    let (grade, letter) = (ben.courses[0].grade, ben.courses[0].letter);
+    assert_eq!((grade, letter), (Grade::Scored(55.0), Some('F')));
+    assert_eq!(ta.look_up("nobody").await.unwrap(), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_career_id_two_students_share_finds_neither() {
+    let students = [
+        ("Aarya Patel", 91.0),
+        ("Arjun Patel", 40.0),
+        ("Ben Ortiz", 72.0),
+    ];
+    let portal = portal_over(&students).await;
+
+    // Note: both are apatel, so a lookup could hand one of them the other's grade
+    assert_eq!(portal.look_up("apatel").await.unwrap(), None);
+    let aarya = portal.acting_as(Caller::new("apatel", Role::Student));
+    assert_eq!(aarya.my_record().await.unwrap(), None);
+    let ben = portal.look_up("bortiz").await.unwrap().unwrap();
+    assert_eq!(ben.name, "Ben Ortiz");
+}
+
+// ###################################################### //
+// ################### ACCESS CONTROL ################### //
+// ###################################################### //
+
+fn denied<T: std::fmt::Debug>(result: Result<T, ActorError>, operation: Operation) -> bool {
+    matches!(result, Err(ActorError::Denied { operation: op, .. }) if op == operation)
+}
+
+#[tokio::test(start_paused = true)]
+async fn each_role_is_denied_what_it_may_not_do() {
+    let (john, _brightspace, admin) = harness::chain().await;
+    let ta = Caller::new("Dane Hindsley", Role::Ta);
+    let student = Caller::new("apatel", Role::Student);
+
+    assert!(
+        john.acting_as(ta.clone())
+            .register_new_student("Aarya Patel".to_string())
+            .await
+            .is_ok()
+    );
+    assert!(denied(
+        john.acting_as(student.clone())
+            .register_new_student("Ben Ortiz".to_string())
+            .await,
+        Operation::RegisterStudent
+    ));
+    assert!(denied(
+        admin.acting_as(ta.clone()).accept_proposal(1).await,
+        Operation::DecideProposal
+    ));
+    assert!(denied(
+        admin.acting_as(ta).approve_booster(),
+        Operation::ApproveBooster
+    ));
+    assert!(denied(
+        admin.acting_as(student).get_all_student_grades().await,
+        Operation::ReadGradebook
+    ));
+    // Note: not even a coordinator may get a feed to write Admin's gradebook directly, only its owner
+    for role in [Role::Coordinator, Role::Ta, Role::Student] {
+        assert!(denied(
+            admin.acting_as(Caller::new("Lee Coordinator", role)).feed(),
+            Operation::WriteGradebook
+        ));
+    }
+    assert!(admin.feed().is_ok());
+}
+
+#[tokio::test(start_paused = true)]
+async fn acting_as_can_only_take_rights_away() {
+    let john = JohnHandle::new().await;
+    let ta = john.acting_as(Caller::new("Dane Hindsley", Role::Ta));
+
+    // Note: a TA handle asked to act as a coordinator stays the TA
+    let escalated = ta.acting_as(Caller::new("Dane Hindsley", Role::Coordinator));
+    assert!(denied(
+        escalated.roll_over("2027-spring".parse().unwrap()).await,
+        Operation::RollOverTerm
+    ));
+    assert!(escalated.get_all_student_names().await.is_ok());
+
+    // Note: narrowing works, a TA handle can be handed on to a student
+    let narrowed = ta.acting_as(Caller::new("apatel", Role::Student));
+    assert!(denied(
+        narrowed.get_all_student_names().await,
+        Operation::ReadGradebook
+    ));
+
+    let portal = StudentPortalHandle::new().await;
+    let aarya = portal.acting_as(Caller::new("apatel", Role::Student));
+    let posing = aarya.acting_as(Caller::new("bortiz", Role::Student));
+    assert!(denied(
+        posing.look_up("bortiz").await,
+        Operation::ViewStudentRecord
+    ));
+}
+
+// ###################################################### //
+// #################### COMMAND LINE #################### //
+// ###################################################### //
+
+// Note: these run on the real clock, since every command reads and writes its gradebook file
+
+/// What the command line `args` parses to, keeping the gradebook in `state`.
+fn command_line(state: &Path, args: &[&str]) -> Cli {
+    let mut line = vec![
+        OsStr::new("feonix-onboarding"),
+        OsStr::new("--state"),
+        state.as_os_str(),
+    ];
+    line.extend(args.iter().map(OsStr::new));
+    Cli::parse_from(line)
+}
+
+/// Runs `args` the way `main` runs a subcommand: from the saved gradebook, saving it again afterwards.
+async fn run_command(state: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let cli = command_line(state, args);
+    cli::run(&cli, cli.command.as_ref().unwrap()).await
+}
+
+#[tokio::test]
+async fn each_command_picks_up_the_gradebook_the_last_one_saved() {
+    let state = std::env::temp_dir().join(format!("cli-gradebook-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&state);
+    let coordinator = ["--role", "coordinator"];
+    for command in [
+        &["register", "Aarya Patel", "Ben Ortiz"][..],
+        &["register", "Aarya Patel"],
+        &["grade", "Aarya Patel", "58"],
+        &["sync"],
+    ] {
+        run_command(&state, &[&coordinator[..], command].concat())
+            .await
+            .unwrap();
+    }
+    // Note: commands run as a student unless told otherwise, and students cannot grade
+    assert!(
+        run_command(&state, &["grade", "Ben Ortiz", "90"])
+            .await
+            .is_err()
+    );
+
+    let session = Session::start(&command_line(&state, &[])).await.unwrap();
+    assert_eq!(
+        session.john.get_all_student_names().await.unwrap(),
+        ["Aarya Patel", "Ben Ortiz"]
+    );
+    assert_eq!(
+        session.admin.get_all_student_grades().await.unwrap(),
+        [Grade::Scored(58.0), Grade::Ungraded]
+    );
+    let _ = std::fs::remove_file(&state);
+}
+
+#[test]
+fn students_print_as_a_table_or_as_json() {
+    let names = ["Aarya Patel".to_string(), "Ben Ortiz".to_string()];
+    let grades = [Grade::Scored(58.0), Grade::Excused];
+
+    assert_eq!(
+        cli::students(false, &names, &grades).unwrap(),
+        "NAME         GRADE\n\
+         -----------  -------\n\
+         Aarya Patel  58.0\n\
+         Ben Ortiz    excused\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&cli::students(true, &names, &grades).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            { "name": "Aarya Patel", "grade": 58.0 },
+            { "name": "Ben Ortiz", "grade": "excused" },
+        ])
+    );
+
+    assert_eq!(
+        cli::listed("registered", &names),
+        "registered Aarya Patel, Ben Ortiz"
+    );
+    assert_eq!(cli::listed("registered", &[]), "nothing registered");
+}
+
+#[test]
+fn command_line_refuses_scores_that_are_not_grades() {
+    for score in ["NaN", "inf", "-1", "100.5", "fifty"] {
+        let line = ["feonix-onboarding", "grade", "Aarya Patel", score];
+        assert!(Cli::try_parse_from(line).is_err(), "{} was taken", score);
+    }
+    for policy in ["add:NaN", "floor:150", "curve:-5"] {
+        let line = ["feonix-onboarding", "boost", "--policy", policy];
+        assert!(Cli::try_parse_from(line).is_err(), "{} was taken", policy);
+    }
+    assert!(Cli::try_parse_from(["feonix-onboarding", "grade", "Aarya Patel", "100"]).is_ok());
+}
+
+#[test]
+fn shell_splits_words_like_a_small_unix_shell() {
+    let words = |line: &str| shell::split_words(line);
+    assert_eq!(
+        words(r#"grade "Aarya Patel" 58"#).unwrap(),
+        ["grade", "Aarya Patel", "58"]
+    );
+    assert_eq!(
+        words("register 'Ben Ortiz'  Dane").unwrap(),
+        ["register", "Ben Ortiz", "Dane"]
+    );
+    // Note: a quote can start or end in the middle of a word, and "" is still a word
+    assert_eq!(words(r#"a"b c"d """#).unwrap(), ["ab cd", ""]);
+
+    assert_eq!(
+        words(r"register Aarya\ Patel O\'Brien").unwrap(),
+        ["register", "Aarya Patel", "O'Brien"]
+    );
+    assert_eq!(
+        words(r#""say \"hi\" \\ \n""#).unwrap(),
+        [r#"say "hi" \ \n"#]
+    );
+    assert_eq!(words(r"'no \escapes'").unwrap(), [r"no \escapes"]);
+
+    assert_eq!(words("").unwrap(), Vec::<String>::new());
+    assert_eq!(words("   \t ").unwrap(), Vec::<String>::new());
+    assert!(words(r#"grade "Aarya Patel 58"#).is_err());
+    assert!(words("grade 'Aarya").is_err());
+    assert!(words(r"grade \").is_err());
+}
+
+// ###################################################### //
+// ###################### HTTP API ###################### //
+// ###################################################### //
+
+// Note: these run on the real clock, since they talk to the server through a real socket
+
+/// Tokens for a coordinator and a TA named "tester" and for Aarya Patel (`apatel`) as a student, each `<role>-token`.
+const TOKENS: &str = r#"
+[[tokens]]
+token = "coordinator-token"
+user = "tester"
+role = "coordinator"
+
+[[tokens]]
+token = "ta-token"
+user = "tester"
+role = "ta"
+
+[[tokens]]
+token = "student-token"
+user = "apatel"
+role = "student"
+"#;
+
+/// Serves `http::router()` on a free local port the way `serve --role <role> --tokens` does with `TOKENS`,
+/// returning its address.
+async fn serving(role: Role) -> SocketAddr {
+    let state = std::env::temp_dir().join(format!("no-gradebook-{}.json", std::process::id()));
+    let cli = Cli::parse_from([
+        "feonix-onboarding".as_ref(),
+        "--state".as_ref(),
+        state.as_os_str(),
+    ]);
+    let session = Session::start(&cli).await.unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tokens = http::Tokens::parse(TOKENS).unwrap();
+    let app = http::router(session.acting_as(Caller::new("server", role)), tokens);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    addr
+}
+
+/// Sends one request with `<role>-token` (no token at all if `None`), returning the status and body.
+async fn request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    role: Option<&str>,
+    body: &str,
+) -> (u16, String) {
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        method, path, addr
+    );
+    // Note: a JSON content type with no body is a malformed request, not a missing one
+    if !body.is_empty() {
+        request.push_str("Content-Type: application/json\r\n");
+    }
+    if let Some(role) = role {
+        request.push_str(&format!("Authorization: Bearer {}-token\r\n", role));
+    }
+    request.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    exchange(addr, &request).await
+}
+
+/// Sends `request` exactly as written, returning the status and body.
+async fn exchange(addr: SocketAddr, request: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap_or_default();
+    (status, body.to_string())
+}
+
+#[tokio::test]
+async fn http_answers_every_outcome_with_its_own_status() {
+    let addr = serving(Role::Coordinator).await;
+    let ta = Some("ta");
+    let coordinator = Some("coordinator");
+    let aarya = r#"{ "name": "Aarya Patel" }"#;
+
+    assert_eq!(
+        request(addr, "POST", "/john/students", ta, aarya).await.0,
+        201
+    );
+    assert_eq!(
+        request(addr, "POST", "/john/students", ta, aarya).await.0,
+        409
+    );
+    let blank = r#"{ "name": "  " }"#;
+    assert_eq!(
+        request(addr, "POST", "/john/students", ta, blank).await.0,
+        422
+    );
+    let grade = "/john/students/Aarya%20Patel/grade";
+    assert_eq!(
+        request(addr, "PUT", grade, ta, r#"{ "grade": 58 }"#)
+            .await
+            .0,
+        204
+    );
+    assert_eq!(
+        request(addr, "PUT", grade, ta, r#"{ "grade": 150 }"#)
+            .await
+            .0,
+        422
+    );
+    let nobody = "/john/students/Nobody/grade";
+    assert_eq!(
+        request(addr, "PUT", nobody, ta, r#"{ "grade": 58 }"#)
+            .await
+            .0,
+        404
+    );
+    // Note: without a token the caller is a student, who only sees statistics
+    assert_eq!(
+        request(addr, "GET", "/admin/students", None, "").await.0,
+        403
+    );
+
+    assert_eq!(request(addr, "POST", "/john/report", ta, "").await.0, 204);
+    assert_eq!(
+        request(addr, "POST", "/brightspace/report", ta, "").await.0,
+        204
+    );
+    let (status, body) = request(addr, "POST", "/booster/boost", coordinator, "").await;
+    assert_eq!(status, 202);
+    let id = serde_json::from_str::<serde_json::Value>(&body).unwrap()["proposal"].clone();
+    let accept = format!("/admin/proposals/{}/accept", id);
+    assert_eq!(request(addr, "POST", &accept, ta, "").await.0, 403);
+    assert_eq!(request(addr, "POST", &accept, coordinator, "").await.0, 204);
+    assert_eq!(request(addr, "POST", &accept, coordinator, "").await.0, 409);
+    let unknown = "/admin/proposals/999/accept";
+    assert_eq!(request(addr, "POST", unknown, coordinator, "").await.0, 404);
+    let unknown = "/admin/proposals/999/reject";
+    assert_eq!(request(addr, "POST", unknown, coordinator, "").await.0, 404);
+}
+
+#[tokio::test]
+async fn only_a_known_token_gives_a_request_more_than_a_student() {
+    let addr = serving(Role::Coordinator).await;
+    let (status, _) = request(addr, "GET", "/admin/students", None, "").await;
+    assert_eq!(status, 403);
+
+    // Note: the old role header proves nothing, so it is no longer read at all
+    let forged = format!(
+        "GET /admin/students HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\
+         X-Part-User: tester\r\nX-Part-Role: coordinator\r\n\r\n",
+        addr
+    );
+    assert_eq!(exchange(addr, &forged).await.0, 403);
+
+    assert_eq!(
+        request(addr, "GET", "/admin/students", Some("forged"), "")
+            .await
+            .0,
+        401
+    );
+    assert_eq!(
+        request(addr, "GET", "/admin/students", Some("ta"), "")
+            .await
+            .0,
+        200
+    );
+    assert!(http::Tokens::parse("[[tokens]]\ntoken = \" \"\nuser = \"x\"\nrole = \"ta\"").is_err());
+    assert!(
+        http::Tokens::parse("[[tokens]]\ntoken = \"t\"\nuser = \"x\"\nrole = \"dean\"").is_err()
+    );
+}
+
+#[tokio::test]
+async fn portal_answers_a_student_only_about_the_career_id_in_their_token() {
+    let addr = serving(Role::Coordinator).await;
+    let student = Some("student");
+
+    assert_eq!(
+        request(addr, "GET", "/portal/apatel", None, "").await.0,
+        401
+    );
+    // Note: naming yourself is not enough, only the token says who a student is
+    let claimed = format!(
+        "GET /portal/dhindsley HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\
+         X-Part-User: dhindsley\r\n\r\n",
+        addr
+    );
+    assert_eq!(exchange(addr, &claimed).await.0, 401);
+    assert_eq!(
+        request(addr, "GET", "/portal/dhindsley", student, "")
+            .await
+            .0,
+        403
+    );
+    assert_eq!(
+        request(addr, "GET", "/portal/apatel", student, "").await.0,
+        404
+    );
+}
+
+#[tokio::test]
+async fn tokens_cannot_go_past_the_role_the_server_runs_as() {
+    let addr = serving(Role::Ta).await;
+    let coordinator = Some("coordinator");
+
+    assert_eq!(
+        request(addr, "POST", "/booster/boost", coordinator, "")
+            .await
+            .0,
+        403
+    );
+    assert_eq!(
+        request(addr, "GET", "/admin/students", coordinator, "")
+            .await
+            .0,
+        200
+    );
+    assert_eq!(
+        request(addr, "GET", "/admin/students", Some("student"), "")
+            .await
+            .0,
+        403
+    );
+}
+
+// ###################################################### //
+// ################# WIRING AND WATCHDOG ################ //
+// ###################################################### //
+
+#[tokio::test(start_paused = true)]
+async fn link_that_would_close_a_loop_is_refused() {
+    let (a, b, c) = (ActorId::next("A"), ActorId::next("B"), ActorId::next("C"));
+    wiring::connect(a, b).unwrap().keep();
+    wiring::connect(b, c).unwrap().keep();
+
+    let result = wiring::connect(c, a).map(|_| ());
+    assert_eq!(
+        result,
+        Err(ActorError::Cycle {
+            path: vec![c, a, b, c]
+        })
+    );
+    assert_eq!(wiring::links_of(c), []);
+
+    // Note: a link that is never kept is undone, so it cannot close a loop later on
+    drop(wiring::connect(c, ActorId::next("D")).unwrap());
+    assert_eq!(wiring::links_of(c), []);
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_send_leaves_no_link_behind() {
+    let (john, mut stepper) = JohnHandle::stepped(1);
+    let first = BrightspaceHandle::new().await;
+    john.set_brightspace(first.clone()).await.unwrap();
+    assert_eq!(stepper.step_until_idle().await, 1);
+    assert_eq!(stepper.kinds(), ["SetBrightspace"]);
+    assert_eq!(wiring::links_of(john.id()), [first.id()]);
+
+    // Note: dropping the stepper drops John's mailbox, so the next handle never reaches John
+    drop(stepper);
+    let second = BrightspaceHandle::new().await;
+    assert_eq!(
+        john.set_brightspace(second).await,
+        Err(ActorError::Closed { actor: "John" })
+    );
+    assert_eq!(wiring::links_of(john.id()), [first.id()]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn watchdog_sees_an_actor_stuck_on_its_admin() {
+    let brightspace = BrightspaceHandle::new().await;
+    let (admin, _stepper) = AdminHandle::stepped(1);
+    brightspace.set_admin(admin.feed().unwrap()).await.unwrap();
+    brightspace
+        .enter_students_into_brightspace(vec!["Aarya Patel".to_string()])
+        .await
+        .unwrap();
+    let stuck_on_admin = || -> Vec<Stall> {
+        wiring::stalls(Duration::from_secs(2))
+            .into_iter()
+            .filter(|stall| stall.waiter == brightspace.id())
+            .collect()
+    };
+
+    let report = tokio::spawn({
+        let brightspace = brightspace.with_timeout(Duration::from_secs(10));
+        async move { brightspace.report_all_students_and_grades_to_admin().await }
+    });
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(stuck_on_admin(), []);
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let stalls = stuck_on_admin();
+    assert_eq!(stalls.len(), 1);
+    assert_eq!(stalls[0].waiting_on, admin.id());
+    assert!(stalls[0].waited >= Duration::from_secs(3));
+    assert!(!stalls[0].in_cycle);
+
+    assert_eq!(
+        report.await.unwrap(),
+        Err(ActorError::Timeout { actor: "Admin" })
+    );
+    assert_eq!(stuck_on_admin(), []);
+}
+
+#[tokio::test(start_paused = true)]
+async fn watchdog_flags_actors_waiting_on_each_other() {
+    let (a, b, c) = (ActorId::next("A"), ActorId::next("B"), ActorId::next("C"));
+    let ours = |stalls: Vec<Stall>| -> Vec<(ActorId, bool)> {
+        stalls
+            .into_iter()
+            .filter(|stall| [a, b, c].contains(&stall.waiter))
+            .map(|stall| (stall.waiter, stall.in_cycle))
+            .collect()
+    };
+
+    let a_on_b = wiring::waiting(a, b);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let b_on_a = wiring::waiting(b, a);
+    let _c_on_a = wiring::waiting(c, a);
+    assert_eq!(ours(wiring::stalls(Duration::from_secs(1))), [(a, true)]);
+
+    // Note: longest waits come first, and C is stuck behind the loop without being part of it
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let stalls = ours(wiring::stalls(Duration::from_secs(1)));
+    assert_eq!(stalls.len(), 3);
+    assert_eq!(stalls[0], (a, true));
+    assert!(stalls.contains(&(b, true)));
+    assert!(stalls.contains(&(c, false)));
+
+    drop(b_on_a);
+    drop(a_on_b);
+    assert_eq!(ours(wiring::stalls(Duration::ZERO)), [(c, false)]);
+}
@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use proptest::prelude::*;
+
+use crate::admin::AdminHandle;
+use crate::booster::{BoostPolicy, BoosterHandle};
+use crate::brightspace::BrightspaceHandle;
+use crate::call::ActorError;
+use crate::course::{Course, CourseKey};
+use crate::events::{AdminEvent, EventFilter, Notice, Subscription, student_of};
+use crate::grade::Grade;
+use crate::harness;
+use crate::john::JohnHandle;
+
+// ###################################################### //
+// ############# INTERLEAVED CLIENTS VS MODEL ########### //
+// ###################################################### //
+
+// Note: every case runs on a fresh paused, single-threaded runtime, so a case always interleaves the same
+//       way; what varies the interleaving is how often each client yields before each call. proptest
+//       shrinks those yields along with the calls, so a failure comes down to a minimal schedule too.
+
+/// How many students each client can register.
+const STUDENTS: usize = 3;
+
+/// The courses clients register students in; a client's `n`th student is the same student in each of them.
+const COURSES: [&str; 2] = ["VIP-ROV", "VIP-UAV"];
+
+/// One call a client makes, through the same handles the CLI uses.
+#[derive(Clone, Debug)]
+enum Op {
+    /// Registers the client's `n`th student with John in course `c`, unless the client already did (as
+    /// `register` does).
+    Register(usize, usize),
+    /// Grades the client's `n`th student in course `c` in John.
+    Grade(usize, usize, Grade),
+    /// Removes the client's `n`th student from course `c` in John, if the client registered them there; they
+    /// can be registered again.
+    Remove(usize, usize),
+    /// Brightspace appends career IDs, renaming every student it holds (Admin hears on the next report).
+    CareerIds,
+    /// John -> Brightspace.
+    ReportToBrightspace,
+    /// Brightspace -> Admin.
+    ReportToAdmin,
+    /// Booster proposes a perfect score for everyone, and the client accepts the proposal straight away.
+    Boost,
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    yields: u8, // How many times the client lets the others run before this call
+    op: Op,
+}
+
+fn grade() -> impl Strategy<Value = Grade> {
+    prop_oneof![
+        4 => (0u8..100).prop_map(|score| Grade::Scored(score.into())),
+        1 => Just(Grade::Ungraded),
+        1 => Just(Grade::Incomplete),
+        1 => Just(Grade::Excused),
+    ]
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    let op = prop_oneof![
+        3 => (0..COURSES.len(), 0..STUDENTS).prop_map(|(c, n)| Op::Register(c, n)),
+        3 => (0..COURSES.len(), 0..STUDENTS, grade()).prop_map(|(c, n, grade)| Op::Grade(c, n, grade)),
+        1 => (0..COURSES.len(), 0..STUDENTS).prop_map(|(c, n)| Op::Remove(c, n)),
+        1 => Just(Op::CareerIds),
+        1 => Just(Op::ReportToBrightspace),
+        1 => Just(Op::ReportToAdmin),
+        1 => Just(Op::Boost),
+    ];
+    (0u8..4, op).prop_map(|(yields, op)| Step { yields, op })
+}
+
+fn student(client: usize, n: usize) -> String {
+    format!("Client{} Student{}", client, n)
+}
+
+fn course(c: usize) -> Course {
+    Course::new(COURSES[c])
+}
+
+/// The sequential reference: what John holds once every client is done, one client's calls at a time.
+///  - Note: clients only ever touch their own students, so the order clients run in does not matter.
+#[derive(Debug, Default)]
+struct Model {
+    grades: BTreeMap<(Course, String), Grade>,
+    // Every grade a student could legitimately have in each course of Admin at some point (see `check_event()`)
+    seen: BTreeMap<(Course, String), BTreeSet<String>>,
+}
+
+impl Model {
+    fn of(clients: &[Vec<Step>]) -> Self {
+        let mut model = Model::default();
+        for (client, steps) in clients.iter().enumerate() {
+            for step in steps {
+                match step.op {
+                    Op::Register(c, n) => {
+                        let key = (course(c), student(client, n));
+                        if !model.grades.contains_key(&key) {
+                            model.grades.insert(key.clone(), Grade::Ungraded);
+                            model.could_be(key, Grade::Ungraded);
+                        }
+                    }
+                    Op::Grade(c, n, grade) => {
+                        let key = (course(c), student(client, n));
+                        if let Some(current) = model.grades.get_mut(&key) {
+                            *current = grade;
+                            model.could_be(key, grade);
+                        }
+                    }
+                    Op::Remove(c, n) => {
+                        model.grades.remove(&(course(c), student(client, n)));
+                    }
+                    Op::ReportToBrightspace | Op::ReportToAdmin | Op::Boost | Op::CareerIds => {}
+                }
+            }
+        }
+        model
+    }
+
+    fn could_be(&mut self, key: (Course, String), grade: Grade) {
+        let seen = self.seen.entry(key).or_default();
+        seen.insert(grade.to_string());
+        // Note: a boost can turn any score into a perfect one before the next report overwrites it again
+        if grade.score().is_some() {
+            seen.insert(Grade::Scored(100.0).to_string());
+        }
+    }
+
+    /// Whether `event` (any event Admin published) gives a student one of their own grades in that course, and
+    /// whether it agrees with `latest`, the grade of every student as the events so far left them.
+    fn check_event(
+        &self,
+        event: &AdminEvent,
+        latest: &mut BTreeMap<(CourseKey, String), Grade>,
+    ) -> Result<(), String> {
+        let name = student_of(event.student()).to_string();
+        let key = (event.course().clone(), name.clone());
+        let Some(seen) = self.seen.get(&(key.0.course.clone(), name.clone())) else {
+            return Err(format!(
+                "{} was never registered in {}",
+                name,
+                event.course()
+            ));
+        };
+        let grade = match event {
+            AdminEvent::StudentAdded { grade, .. } => *grade,
+            AdminEvent::GradeChanged { from, to, .. } => {
+                // Note: every event is delivered, so the grade it changes from is the one the last event left
+                if latest.get(&key) != Some(from) {
+                    return Err(format!(
+                        "{} changed from {}, but had {:?}",
+                        name,
+                        from,
+                        latest.get(&key)
+                    ));
+                }
+                *to
+            }
+            AdminEvent::StudentFellBelowPassing { grade, .. } => {
+                return match latest.get(&key) {
+                    Some(latest) if *latest == Grade::Scored(*grade) && latest.is_failing() => {
+                        Ok(())
+                    }
+                    _ => Err(format!("{} fell below passing with {}", name, grade)),
+                };
+            }
+            AdminEvent::StudentRemoved { .. } => {
+                return match latest.remove(&key) {
+                    Some(_) => Ok(()),
+                    None => Err(format!("{} was removed, but was never added", name)),
+                };
+            }
+        };
+        if !seen.contains(&grade.to_string()) {
+            return Err(format!(
+                "{} has {} in {}, which was never one of their grades there",
+                name,
+                grade,
+                event.course()
+            ));
+        }
+        latest.insert(key, grade);
+        Ok(())
+    }
+
+    /// `names` and `grades` in `course`, as (course, student, grade) text in a fixed order.
+    fn sorted(
+        course: &Course,
+        names: Vec<String>,
+        grades: Vec<Grade>,
+    ) -> Vec<(String, String, String)> {
+        // Note: Brightspace may have appended career IDs, John never has, and they are the same students
+        let mut rows: Vec<_> = names
+            .iter()
+            .map(|name| (course.to_string(), student_of(name).to_string()))
+            .zip(grades.into_iter().map(|grade| grade.to_string()))
+            .map(|((course, name), grade)| (course, name, grade))
+            .collect();
+        rows.sort();
+        rows
+    }
+
+    fn expected(&self) -> Vec<(String, String, String)> {
+        let mut rows: Vec<_> = self
+            .grades
+            .iter()
+            .map(|((course, name), grade)| (course.to_string(), name.clone(), grade.to_string()))
+            .collect();
+        rows.sort();
+        rows
+    }
+}
+
+async fn run_client(
+    client: usize,
+    steps: Vec<Step>,
+    john: JohnHandle,
+    brightspace: BrightspaceHandle,
+    booster: BoosterHandle,
+    admin: AdminHandle,
+) -> Result<(), ActorError> {
+    let mut registered = BTreeSet::new();
+    let john_in = |c: usize| john.in_course(course(c));
+    for step in steps {
+        for _ in 0..step.yields {
+            tokio::task::yield_now().await;
+        }
+        match step.op {
+            Op::Register(c, n) => {
+                if registered.insert((c, n)) {
+                    john_in(c).register_new_student(student(client, n)).await?;
+                }
+            }
+            Op::Grade(c, n, grade) => {
+                john_in(c)
+                    .assign_grade_to_student(student(client, n), grade)
+                    .await?
+            }
+            Op::Remove(c, n) => {
+                if registered.remove(&(c, n)) {
+                    john_in(c).remove_student(student(client, n)).await?;
+                }
+            }
+            Op::CareerIds => brightspace.generate_and_append_student_career_id().await?,
+            Op::ReportToBrightspace => john.report_all_students_and_grades_to_brightspace().await?,
+            Op::ReportToAdmin => {
+                brightspace
+                    .report_all_students_and_grades_to_admin()
+                    .await?
+            }
+            Op::Boost => {
+                let id = booster.boost_grades_with(BoostPolicy::Perfect).await?;
+                // Note: a proposal another client's report made stale is refused, which is the point of staleness
+                match admin.accept_proposal(id).await {
+                    Ok(()) | Err(ActorError::Refused { .. }) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks every event Admin publishes until Admin goes away, returning the first bad one.
+async fn check_events(mut events: Subscription, model: &Model) -> Result<(), String> {
+    let mut latest: BTreeMap<(CourseKey, String), Grade> = events
+        .gradebook()
+        .students()
+        .map(|(course, name, grade)| ((course.clone(), student_of(name).to_string()), grade))
+        .collect();
+    while let Some(notice) = events.recv().await {
+        match notice {
+            Notice::Event(event) => model
+                .check_event(&event, &mut latest)
+                .map_err(|e| format!("{} in {:?}", e, event))?,
+            Notice::Lagged { missed } => {
+                return Err(format!("the checker missed {} event(s)", missed));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Why `check_events()` finished before the clients did.
+fn stopped(result: Result<(), String>) -> String {
+    result
+        .err()
+        .unwrap_or_else(|| "Admin stopped publishing".to_string())
+}
+
+/// What Admin, Brightspace and John hold in every course once the clients are done and John reported through to
+/// Admin one last time, or the first thing that went wrong.
+type Outcome = Result<[Vec<(String, String, String)>; 3], String>;
+
+fn run_interleaved(clients: Vec<Vec<Step>>, model: &Model) -> Outcome {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let (john, brightspace, admin) = harness::chain().await;
+        let booster = BoosterHandle::new().await;
+        booster
+            .set_admin(admin.approve_booster().unwrap())
+            .await
+            .unwrap();
+        let events = admin.subscribe(EventFilter::default()).await.unwrap();
+        let checker = check_events(events, model);
+        tokio::pin!(checker);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (client, steps) in clients.into_iter().enumerate() {
+            tasks.spawn(run_client(
+                client,
+                steps,
+                john.clone(),
+                brightspace.clone(),
+                booster.clone(),
+                admin.clone(),
+            ));
+        }
+        // Note: `biased` keeps `select!` from picking a branch at random, which would make cases unrepeatable
+        loop {
+            tokio::select! {
+                biased;
+                result = &mut checker => return Err(stopped(result)),
+                done = tasks.join_next() => match done {
+                    Some(result) => result.unwrap().map_err(|e| e.to_string())?,
+                    None => break,
+                },
+            }
+        }
+
+        john.report_all_students_and_grades_to_brightspace()
+            .await
+            .map_err(|e| e.to_string())?;
+        brightspace
+            .report_all_students_and_grades_to_admin()
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::select! {
+            biased;
+            result = &mut checker => return Err(stopped(result)),
+            _ = tokio::task::yield_now() => {}
+        }
+
+        let mut held: [Vec<_>; 3] = Default::default();
+        for c in 0..COURSES.len() {
+            let course = course(c);
+            for (i, (names, grades)) in [
+                (
+                    admin
+                        .in_course(course.clone())
+                        .get_all_student_names()
+                        .await,
+                    admin
+                        .in_course(course.clone())
+                        .get_all_student_grades()
+                        .await,
+                ),
+                (
+                    brightspace
+                        .in_course(course.clone())
+                        .get_all_student_names()
+                        .await,
+                    brightspace
+                        .in_course(course.clone())
+                        .get_all_student_grades()
+                        .await,
+                ),
+                (
+                    john.in_course(course.clone()).get_all_student_names().await,
+                    john.in_course(course.clone())
+                        .get_all_student_grades()
+                        .await,
+                ),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let (names, grades) = (names.unwrap(), grades.unwrap());
+                if names.len() != grades.len() {
+                    return Err(format!(
+                        "{} names but {} grades in {}",
+                        names.len(),
+                        grades.len(),
+                        course
+                    ));
+                }
+                held[i].extend(Model::sorted(&course, names, grades));
+            }
+        }
+        for rows in &mut held {
+            rows.sort();
+        }
+        Ok(held)
+    })
+}
+
+proptest! {
+    #[test]
+    fn interleaved_clients_keep_admin_names_and_grades_aligned(
+        clients in prop::collection::vec(prop::collection::vec(step(), 0..12), 1..=3)
+    ) {
+        let model = Model::of(&clients);
+        let [admin, brightspace, john] = run_interleaved(clients, &model).map_err(TestCaseError::fail)?;
+        let expected = model.expected();
+        prop_assert_eq!(&john, &expected, "John");
+        prop_assert_eq!(&brightspace, &expected, "Brightspace");
+        prop_assert_eq!(&admin, &expected, "Admin");
+    }
+}
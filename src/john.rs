@@ -1,20 +1,46 @@
-use tokio::sync::{mpsc, oneshot};
+use std::time::{Duration, SystemTime};
 
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation};
+use crate::call::{self, ActorError};
+use crate::course::{Course, Courses, Roster, Scope, Term};
+use crate::deadline::{Assessed, Deadlines, LateWork};
+use crate::events::student_of;
+use crate::feedback::{Comment, CommentBank, CommentText};
+use crate::grade::Grade;
+use crate::rubric::{Rubric, RubricScore, Selection};
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
 use crate::*;
 
+const ACTOR: &str = "John";
+
+/// Everything John holds at one moment (see `JohnHandle::snapshot()`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JohnSnapshot {
+    pub courses: Courses,
+    /// The Brightspace John reports to, if he has one.
+    pub brightspace: Option<ActorId>,
+}
+
 // ##################################################### //
 // ################### ACTOR BACKEND ################### //
 // ##################################################### //
 
 /// This is our Actor John (which just happens to be the name of PART's VIP Coordinator 🤯🤯🤯)
 struct John {
+    id: ActorId, // Which John this is, so the wiring registry can tell actors apart
     // Actor John receives messages via `receiver`
     //  - Note: mpsc stands for multiple-producer-single-consumer, multiple `Sender<>` can exist for one `Receiver<>`
     receiver: mpsc::Receiver<JohnMessage>,
 
-    underlings: Vec<String>,    // Vector (list) of VIP student names
-    underling_grades: Vec<f64>, // Vector (list) of VIP student grades
+    courses: Courses, // Every course's roster of VIP student names and grades, keyed by course and term
     brightspace: Option<BrightspaceHandle>, // Brightspace Actor's handle
+    deadlines: Deadlines, // When every assessment is due, and how late work is marked down
+    rubrics: Vec<Rubric>, // How work graded on a rubric is scored
+    comment_bank: CommentBank, // The comments coordinators reuse, by key
 }
 
 /// This enum of messages cover all functionality that we might possibly want from our Actor.
@@ -22,87 +48,389 @@ struct John {
 #[derive(Debug)]
 enum JohnMessage {
     AddUnderling {
+        scope: Scope, // Note: which course and term the student joins (see `JohnHandle::in_course()`)
+        name: String,
+    },
+    RemoveUnderling {
+        scope: Scope,
         name: String,
     },
     SetUnderlingGrade {
+        scope: Scope,
+        name: String,
+        grade: Grade,
+    },
+    RecordSubmission {
+        scope: Scope,
+        name: String,
+        assessment: String,
+        raw: Grade,
+        submitted_at: SystemTime,
+        reply_to: oneshot::Sender<Result<Assessed, String>>,
+    },
+    GradeWithRubric {
+        scope: Scope,
+        name: String,
+        rubric: String,
+        selections: Vec<Selection>,
+        reply_to: oneshot::Sender<Result<RubricScore, String>>,
+    },
+    AddComment {
+        scope: Scope,
+        name: String,
+        text: CommentText,
+        reply_to: oneshot::Sender<Result<Comment, String>>,
+    },
+    ClearComments {
+        scope: Scope,
         name: String,
-        grade: f64,
+    },
+    SetDeadlines {
+        deadlines: Deadlines,
+    },
+    SetRubrics {
+        rubrics: Vec<Rubric>,
+    },
+    SetCommentBank {
+        comment_bank: CommentBank,
     },
     SetBrightspace {
         brightspace_handle: BrightspaceHandle,
     },
     SendAllToBrightspace {
-        reply_to: oneshot::Sender<()>,
-    }, // IMPORTANT: `reply_to` IS USED TO CONFIRM WHEN OPERATION IS DONE
+        deadline: Instant,
+        reply_to: oneshot::Sender<Result<(), ActorError>>,
+    }, // IMPORTANT: `reply_to` IS USED TO CONFIRM WHEN OPERATION IS DONE (OR WHY IT FAILED)
+    GetAllStudentNames {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<String>>,
+    }, // Note: here `reply_to` carries the actual answer back, not just "done"
+    GetAllStudentGrades {
+        scope: Scope,
+        reply_to: oneshot::Sender<Vec<Grade>>,
+    },
+    GetCourses {
+        reply_to: oneshot::Sender<Courses>,
+    },
+    LoadCourses {
+        courses: Courses,
+    },
+    CorrectRoster {
+        scope: Scope,
+        roster: Roster,
+    },
+    RollOver {
+        term: Term,
+        reply_to: oneshot::Sender<Result<Term, String>>,
+    },
+    Snapshot {
+        reply_to: oneshot::Sender<JohnSnapshot>,
+    },
+    Restore {
+        snapshot: JohnSnapshot,
+    },
 }
 
 /// Define methods for our Actor John
 ///  - Note: notice how `John` methods are NOT public (no `pub`), only `JohnHandle` methods are public (has `pub`)
 impl John {
-    fn new(receiver: mpsc::Receiver<JohnMessage>) -> Self {
+    fn new(id: ActorId, receiver: mpsc::Receiver<JohnMessage>) -> Self {
         John {
-            receiver: receiver,
+            id,
+            receiver,
             brightspace: None,
-            underlings: Vec::new(),
-            underling_grades: Vec::new(),
+            courses: Courses::default(),
+            deadlines: Deadlines::default(),
+            rubrics: Vec::new(),
+            comment_bank: CommentBank::default(),
+        }
+    }
+
+    /// Gives `name` `grade` in every roster `scope` covers, with the late penalty and rubric score behind it.
+    ///  - Returns an error if no roster in `scope` has `name`.
+    fn grade(
+        &mut self,
+        scope: &Scope,
+        name: &str,
+        grade: Grade,
+        late: Option<LateWork>,
+        rubric: Option<RubricScore>,
+    ) -> Result<(), String> {
+        let student = student_of(name).to_string();
+        let mut found = false;
+        for roster in self.courses.view_mut(scope) {
+            let Some(ind) = roster.names.iter().position(|n| n == name) else {
+                continue;
+            };
+            roster.grades[ind] = grade;
+            match &late {
+                Some(late) => roster.late.insert(student.clone(), late.clone()),
+                None => roster.late.remove(&student),
+            };
+            match &rubric {
+                Some(rubric) => roster.rubrics.insert(student.clone(), rubric.clone()),
+                None => roster.rubrics.remove(&student),
+            };
+            found = true;
+        }
+        match found {
+            true => Ok(()),
+            false => Err(format!("{} is not registered here", name)),
+        }
+    }
+
+    /// Attaches `text` to `name`'s current grade in every roster `scope` covers.
+    ///  - Returns an error if no roster in `scope` has `name`, or `text` is a comment the bank does not have.
+    fn comment(&mut self, scope: &Scope, name: &str, text: CommentText) -> Result<Comment, String> {
+        let view = self.courses.view(scope);
+        let grade = view
+            .names
+            .iter()
+            .position(|n| n == name)
+            .map(|ind| view.grades[ind])
+            .ok_or_else(|| format!("{} is not registered here", name))?;
+        let comment = match text {
+            CommentText::Written(text) => Comment {
+                text,
+                template: None,
+                grade,
+                at: SystemTime::now(),
+            },
+            CommentText::Bank(key) => Comment {
+                text: self.comment_bank.render(&key, name, grade)?,
+                template: Some(key),
+                grade,
+                at: SystemTime::now(),
+            },
+        };
+        for roster in self.courses.view_mut(scope) {
+            if roster.names.iter().any(|n| n == name) {
+                let comments = roster.comments.entry(student_of(name).to_string());
+                comments.or_default().push(comment.clone());
+            }
         }
+        Ok(comment)
     }
 
     async fn handle_message(&mut self, msg: JohnMessage) {
-        println!(
+        trace!(
             "[ACTOR]: John is running handle_message() with new JohnMessage: {:?}",
             msg
         );
 
         match msg {
-            JohnMessage::AddUnderling { name } => {
-                println!("[ACTOR]: John adding a new underling {}", name);
-
-                self.underlings.push(name);
-                self.underling_grades.push(0.0);
+            JohnMessage::AddUnderling { scope, name } => {
+                let key = self.courses.key(&scope);
+                trace!("[ACTOR]: John adding a new underling {} to {}", name, key);
+
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => {
+                        roster.names.push(name);
+                        roster.grades.push(Grade::Ungraded);
+                        // Note: ^ not 0.0, a student nobody has graded yet is not failing
+                    }
+                    Err(term) => trace!("[ACTOR]: John cannot add to archived term {}", term),
+                }
             }
 
-            JohnMessage::SetUnderlingGrade { name, grade } => {
-                println!("[ACTOR]: John setting {} grade to {}", name, grade);
+            JohnMessage::RemoveUnderling { scope, name } => {
+                let key = self.courses.key(&scope);
+                trace!("[ACTOR]: John removing underling {} from {}", name, key);
+
+                match self.courses.roster_mut(&scope) {
+                    Ok(roster) => {
+                        if let Some(ind) = roster.names.iter().position(|n| *n == name) {
+                            roster.names.remove(ind);
+                            roster.grades.remove(ind);
+                            roster.late.remove(student_of(&name));
+                            roster.rubrics.remove(student_of(&name));
+                            roster.comments.remove(student_of(&name));
+                        }
+                    }
+                    Err(term) => trace!("[ACTOR]: John cannot remove from archived term {}", term),
+                }
+            }
 
-                let found_index: Option<usize> = self.underlings.iter().position(|n| *n == name);
-                if let Some(ind) = found_index {
-                    self.underling_grades[ind] = grade;
+            JohnMessage::SetUnderlingGrade { scope, name, grade } => {
+                trace!("[ACTOR]: John setting {} grade to {}", name, grade);
+
+                // Note: without a course, the student gets the grade in every course of the term they are in
+                for roster in self.courses.view_mut(&scope) {
+                    let found_index: Option<usize> = roster.names.iter().position(|n| *n == name);
+                    // Note: vvv this is the "rusty" way of checking and unwrapping an `Option<T>`, it's equivalent to:
+                    //        if found_index.is_some() {
+                    //             let ind = found_index.unwrap();
+                    if let Some(ind) = found_index {
+                        roster.grades[ind] = grade;
+                        // Note: a grade given by hand has no late penalty or rubric score behind it
+                        roster.late.remove(student_of(&name));
+                        roster.rubrics.remove(student_of(&name));
+                    }
                 }
+            }
+
+            JohnMessage::RecordSubmission {
+                scope,
+                name,
+                assessment,
+                raw,
+                submitted_at,
+                reply_to,
+            } => {
+                trace!(
+                    "[ACTOR]: John recording {}'s {} ({})",
+                    name, assessment, raw
+                );
+
+                let result = self
+                    .deadlines
+                    .assess(&assessment, &name, raw, submitted_at)
+                    .and_then(|assessed| {
+                        let late = assessed.late.clone();
+                        self.grade(&scope, &name, assessed.grade, late, None)?;
+                        Ok(assessed)
+                    });
+                let _ = reply_to.send(result);
+            }
+
+            JohnMessage::GradeWithRubric {
+                scope,
+                name,
+                rubric,
+                selections,
+                reply_to,
+            } => {
+                trace!("[ACTOR]: John grading {} on rubric {}", name, rubric);
+
+                let result = self
+                    .rubrics
+                    .iter()
+                    .find(|r| r.name == rubric)
+                    .ok_or_else(|| format!("there is no rubric called `{}`", rubric))
+                    .and_then(|rubric| rubric.score(&selections))
+                    .and_then(|score| {
+                        self.grade(&scope, &name, score.grade(), None, Some(score.clone()))?;
+                        Ok(score)
+                    });
+                let _ = reply_to.send(result);
+            }
+
+            JohnMessage::AddComment {
+                scope,
+                name,
+                text,
+                reply_to,
+            } => {
+                trace!("[ACTOR]: John commenting on {}'s grade", name);
+
+                let _ = reply_to.send(self.comment(&scope, &name, text));
+            }
+
+            JohnMessage::ClearComments { scope, name } => {
+                trace!("[ACTOR]: John clearing the feedback on {}'s grade", name);
 
-                // Note: ^^^ this is the "rusty" way of checking and unwrapping an `Option<T>`, it's equivalent to:
-                //        if found_index.is_some() {
-                //             let ind = found_index.unwrap();
+                for roster in self.courses.view_mut(&scope) {
+                    if roster.names.contains(&name) {
+                        roster.comments.remove(student_of(&name));
+                    }
+                }
             }
 
+            JohnMessage::SetDeadlines { deadlines } => self.deadlines = deadlines,
+
+            JohnMessage::SetRubrics { rubrics } => self.rubrics = rubrics,
+
+            JohnMessage::SetCommentBank { comment_bank } => self.comment_bank = comment_bank,
+
             JohnMessage::SetBrightspace { brightspace_handle } => {
-                println!("[ACTOR]: John initializing Brightspace field with BrightspaceHandle");
+                trace!("[ACTOR]: John initializing Brightspace field with BrightspaceHandle");
 
                 self.brightspace = Some(brightspace_handle);
                 // Note: ^ since `self.brightspace` is an `Option<T>` that can take either `Some(T)` or `None`
             }
 
-            JohnMessage::SendAllToBrightspace { reply_to } => {
-                if let Some(bs) = &self.brightspace {
+            JohnMessage::SendAllToBrightspace {
+                deadline,
+                mut reply_to,
+            } => {
+                let result = if let Some(bs) = &self.brightspace {
                     // Note: ^ this is the "rusty" way of checking and unwrapping an `Option<T>`, it's equivalent to:
                     //        if self.brightspace.is_some() {
                     //             let bs = self.brightspace.unwrap();
 
-                    println!("[ACTOR]: John entering all students and grades to Brightspace");
-
-                    bs.enter_students_into_brightspace(self.underlings.clone())
-                        .await;
-                    bs.enter_student_grades_into_brightspace(self.underling_grades.clone())
-                        .await;
+                    trace!("[ACTOR]: John entering all students and grades to Brightspace");
+
+                    // Note: Brightspace gets (slightly less than) the deadline our caller gave us, so if Brightspace is
+                    //       stuck it times out first and the error our caller sees names Brightspace, not John
+                    let bs = bs.with_deadline(call::downstream(deadline));
+
+                    // Note: while `_waiting` is alive, the watchdog (see `wiring.rs`) knows John is waiting on Brightspace
+                    let _waiting = wiring::waiting(self.id, bs.id());
+                    let enter = async {
+                        // Note: every course John still teaches gets its own course shell in Brightspace, and John's
+                        //       students are merged into it, so other coordinators' students in the same shell stay
+                        for (key, roster) in self.courses.open_rosters() {
+                            let shell = bs.in_course(key.course.clone()).in_term(key.term.clone());
+                            shell
+                                .merge_roster_into_brightspace(self.id, roster.clone())
+                                .await?;
+                        }
+                        Ok(())
+                    };
+
+                    // Note: if our caller stops waiting, `unless_cancelled()` stops `enter` instead of finishing it
+                    call::unless_cancelled(ACTOR, &mut reply_to, enter).await
                 } else {
-                    eprintln!(
+                    trace!(
                         "[ACTOR]: John does not have Brightspace initialized so nothing happened"
                     );
+                    Ok(())
+                };
+
+                // IMPORTANT: WE NEED A CALLBACK TO SEND THE RESULT ACROSS CHANNEL TO TELL JOHNHANDLE "EVERYTHING IS DONE"
+                let _ = reply_to.send(result);
+            }
+
+            JohnMessage::GetAllStudentNames { scope, reply_to } => {
+                let _ = reply_to.send(self.courses.view(&scope).names);
+            }
+
+            JohnMessage::GetAllStudentGrades { scope, reply_to } => {
+                let _ = reply_to.send(self.courses.view(&scope).grades);
+            }
+
+            JohnMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(self.courses.clone());
+            }
+
+            JohnMessage::LoadCourses { courses } => self.courses = courses,
+
+            JohnMessage::CorrectRoster { scope, roster } => {
+                trace!(
+                    "[ACTOR]: John replacing {} after reconciliation",
+                    self.courses.key(&scope)
+                );
+
+                match self.courses.roster_mut(&scope) {
+                    Ok(current) => current.correct(roster),
+                    Err(term) => trace!("[ACTOR]: John cannot correct archived term {}", term),
                 }
+            }
+
+            JohnMessage::RollOver { term, reply_to } => {
+                trace!("[ACTOR]: John starting term {}", term);
+                let _ = reply_to.send(self.courses.roll_over(term));
+            }
 
-                // IMPORTANT: WE NEED A CALLBACK TO SEND AN EMPTY TUPLE `()` ACROSS CHANNEL TO TELL JOHNHANDLE "EVERYTHING IS DONE"
-                let _ = reply_to.send(());
+            JohnMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(JohnSnapshot {
+                    courses: self.courses.clone(),
+                    brightspace: self.brightspace.as_ref().map(|bs| bs.id()),
+                });
             }
+
+            // Note: only the rosters come back, John is re-wired through `set_brightspace()` like any other time
+            JohnMessage::Restore { snapshot } => self.courses = snapshot.courses,
         }
     }
 }
@@ -117,29 +445,128 @@ impl John {
 /// This is the Handle for our Actor John, it's very easily cloned and passed around.
 #[derive(Clone, Debug)]
 pub struct JohnHandle {
+    id: ActorId,
     sender: mpsc::Sender<JohnMessage>,
+    timeout: Duration,         // How long each call may take (see `with_timeout()`)
+    deadline: Option<Instant>, // A fixed point in time all calls must finish by (see `with_deadline()`)
+    caller: Option<Caller>, // Who is using this handle, `None` for whoever started the actor (see `acting_as()`)
+    scope: Scope, // Which course and term calls are about, every course of the current term by default (see `in_course()`)
 }
 
 /// This ASYNC function starts up and runs the actor backend
 ///  - Initially, `receiver` is waiting and blocking until it receives a `JohnMessage`
 ///  - When a `JohnMessage` is received, it runs `handle_message()` and then goes back to waiting and blocking
 async fn run_john_actor(mut actor: John) {
-    println!("[run_john_actor()]: is blocking until a JohnMessage is received...");
+    trace!("[run_john_actor()]: is blocking until a JohnMessage is received...");
     while let Some(msg) = actor.receiver.recv().await {
-        println!(
-            "\n[run_john_actor()]: received a new JohnMessage and calling handle_message()..."
-        );
+        trace!("\n[run_john_actor()]: received a new JohnMessage and calling handle_message()...");
         actor.handle_message(msg).await;
     }
 }
 
+#[cfg(test)]
+impl crate::harness::Steppable for John {
+    fn next_message(&mut self, wait: Duration) -> crate::harness::Step<'_> {
+        Box::pin(async move {
+            let msg = tokio::time::timeout(wait, self.receiver.recv())
+                .await
+                .ok()??;
+            let seen = format!("{:?}", msg);
+            self.handle_message(msg).await;
+            Some(seen)
+        })
+    }
+
+    fn run(self: Box<Self>) {
+        tokio::spawn(run_john_actor(*self));
+    }
+}
+
+/// What a mock John answers (see `JohnHandle::mocked()`).
+///  - Note: the mock ignores scopes, every query is answered from `roster`; writes are only recorded.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct JohnScript {
+    pub roster: crate::course::Roster,
+    /// If set, reporting to Brightspace fails with this error.
+    pub report_error: Option<ActorError>,
+    /// If set, rolling over is refused with this reason.
+    pub refusal: Option<String>,
+}
+
+#[cfg(test)]
+impl JohnScript {
+    fn answer(&mut self, msg: JohnMessage) {
+        match msg {
+            JohnMessage::SendAllToBrightspace { reply_to, .. } => {
+                let _ = reply_to.send(self.report_error.clone().map_or(Ok(()), Err));
+            }
+            JohnMessage::GetAllStudentNames { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.names.clone());
+            }
+            JohnMessage::GetAllStudentGrades { reply_to, .. } => {
+                let _ = reply_to.send(self.roster.grades.clone());
+            }
+            JohnMessage::GetCourses { reply_to } => {
+                let _ = reply_to.send(crate::harness::courses_of(self.roster.clone()));
+            }
+            JohnMessage::RecordSubmission { raw, reply_to, .. } => {
+                let _ = reply_to.send(Ok(Assessed {
+                    grade: raw,
+                    late: None,
+                }));
+            }
+            JohnMessage::GradeWithRubric { reply_to, .. } => {
+                let _ = reply_to.send(Err("the mock has no rubrics".to_string()));
+            }
+            JohnMessage::AddComment { text, reply_to, .. } => {
+                let _ = reply_to.send(match text {
+                    CommentText::Written(text) => Ok(Comment {
+                        text,
+                        template: None,
+                        grade: Grade::Ungraded,
+                        at: SystemTime::now(),
+                    }),
+                    CommentText::Bank(_) => Err("the mock has no comment bank".to_string()),
+                });
+            }
+            JohnMessage::RollOver { term, reply_to } => {
+                let _ = reply_to.send(self.refusal.clone().map_or(Ok(term), Err));
+            }
+            JohnMessage::Snapshot { reply_to } => {
+                let _ = reply_to.send(JohnSnapshot {
+                    courses: crate::harness::courses_of(self.roster.clone()),
+                    brightspace: None,
+                });
+            }
+            JohnMessage::Restore { .. }
+            | JohnMessage::AddUnderling { .. }
+            | JohnMessage::RemoveUnderling { .. }
+            | JohnMessage::SetUnderlingGrade { .. }
+            | JohnMessage::SetDeadlines { .. }
+            | JohnMessage::SetRubrics { .. }
+            | JohnMessage::SetCommentBank { .. }
+            | JohnMessage::ClearComments { .. }
+            | JohnMessage::SetBrightspace { .. }
+            | JohnMessage::LoadCourses { .. }
+            | JohnMessage::CorrectRoster { .. } => {}
+        }
+    }
+}
+
 impl JohnHandle {
     /// ### IMPORTANT METHOD: ###
     /// This is the constructor, return type is `Self` which is identical to having a return type of `JohnHandle`
     ///   - Call constructor with `let john_handle = JohnHandle::new();`
     pub async fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(8); // First, we make the communication channel sender-receiver pair
-        let actor: John = John::new(receiver); // Next, we call the John Actor constructor from HERE ONLY, never anywhere else, and assign the receiver to it
+        JohnHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but John's mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity); // First, we make the communication channel sender-receiver pair
+        let id = ActorId::next(ACTOR);
+        let actor: John = John::new(id, receiver); // Next, we call the John Actor constructor from HERE ONLY, never anywhere else, and assign the receiver to it
 
         // Then, we start running the John Actor (backend) since it now has its `receiver`, it can start listening for messages
         //  - IMPORTANT: WE MAKE `run_john_actor()` RUN AS A SEPARATE `tokio` TASK WITH `tokio::spawn`
@@ -147,38 +574,408 @@ impl JohnHandle {
 
         // Finally, we make and return our John Handle (frontend) with its `sender`, and we can use it to send messages.
         //  - Note: we don't need an explicit `return` if it's the last line and doesn't have a closing semicolon.
-        JohnHandle { sender: sender }
+        JohnHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        }
     }
 
-    pub async fn register_new_student(&self, name: String) {
-        let msg: JohnMessage = JohnMessage::AddUnderling { name: name };
-        let _ = self.sender.send(msg).await;
-        //  ^ rust-analyzer complains when you don't use a returned result, this is jus a way of telling
-        //    it that the returned result doesn't matter
+    /// Same as `with_mailbox()`, but John only handles a message when the test steps him (see `harness::Stepper`).
+    #[cfg(test)]
+    pub(crate) fn stepped(capacity: usize) -> (Self, crate::harness::Stepper) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let id = ActorId::next(ACTOR);
+        let handle = JohnHandle {
+            id,
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        (
+            handle,
+            crate::harness::Stepper::new(John::new(id, receiver)),
+        )
     }
 
-    pub async fn assign_grade_to_student(&self, name: String, grade: f64) {
+    /// Same as `with_mailbox()`, but the handle talks to a mock John that answers from `script` (see `harness::Mock`).
+    #[cfg(test)]
+    pub(crate) fn mocked(
+        capacity: usize,
+        script: JohnScript,
+    ) -> (Self, crate::harness::Mock<JohnScript>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = JohnHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+            scope: Scope::default(),
+        };
+        let mock = crate::harness::Mock::spawn(receiver, script, JohnScript::answer);
+        (handle, mock)
+    }
+
+    /// Which John actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    ///   - e.g. `john_handle.with_timeout(Duration::from_secs(1)).report_all_students_and_grades_to_brightspace()`
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        JohnHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        JohnHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        JohnHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `course` (or one section of it) only.
+    ///   - e.g. `john_handle.in_course("VIP-UAV/02".parse()?).register_new_student(name)`
+    pub fn in_course(&self, course: Course) -> Self {
+        JohnHandle {
+            scope: Scope {
+                course: Some(course),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls are about `term` instead of John's current term.
+    pub fn in_term(&self, term: Term) -> Self {
+        JohnHandle {
+            scope: Scope {
+                term: Some(term),
+                ..self.scope.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Which course and term this handle's calls are about.
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    pub async fn register_new_student(&self, name: String) -> Result<(), ActorError> {
+        self.authorize(Operation::RegisterStudent)?;
+        let msg: JohnMessage = JohnMessage::AddUnderling {
+            scope: self.scope.clone(),
+            name,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+        //  ^ `send()` gives up with `ActorError::Timeout` if John's mailbox stays full past the deadline
+    }
+
+    /// Drops `name` from the course this handle is scoped to, with their grade and everything behind it;
+    /// Brightspace and Admin drop them too on the next sync.
+    pub async fn remove_student(&self, name: String) -> Result<(), ActorError> {
+        self.authorize(Operation::RegisterStudent)?;
+        let msg: JohnMessage = JohnMessage::RemoveUnderling {
+            scope: self.scope.clone(),
+            name,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    pub async fn assign_grade_to_student(
+        &self,
+        name: String,
+        grade: impl Into<Grade>,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        // Note: a plain number like `58.0` becomes `Grade::Scored(58.0)`
+        let grade = grade
+            .into()
+            .checked()
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })?;
         let msg: JohnMessage = JohnMessage::SetUnderlingGrade {
-            name: name,
-            grade: grade,
+            scope: self.scope.clone(),
+            name,
+            grade,
         };
-        let _ = self.sender.send(msg).await;
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
     }
 
-    pub async fn set_brightspace(&self, brightspace_handle: BrightspaceHandle) {
-        let msg: JohnMessage = JohnMessage::SetBrightspace {
-            brightspace_handle: brightspace_handle,
+    /// Records `name`'s work on `assessment`, which scored `raw`, as handed in at `submitted_at`: the student gets
+    /// the grade left after any late penalty, and the penalty is kept next to it (see `deadline.rs`).
+    ///  - Returns `ActorError::Refused` if there is no such assessment or no such student in this handle's scope.
+    pub async fn record_submission(
+        &self,
+        name: String,
+        assessment: String,
+        raw: impl Into<Grade>,
+        submitted_at: SystemTime,
+    ) -> Result<Assessed, ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = JohnMessage::RecordSubmission {
+            scope: self.scope.clone(),
+            name,
+            assessment,
+            raw: raw.into(),
+            submitted_at,
+            reply_to: tx,
         };
-        let _ = self.sender.send(msg).await;
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
     }
 
-    pub async fn report_all_students_and_grades_to_brightspace(&self) {
+    /// Grades `name` on `rubric`, one level of every criterion in `selections`: the student gets the points as a
+    /// grade out of 100, and the breakdown is kept next to it for feedback (see `rubric.rs`).
+    ///  - Returns `ActorError::Refused` if there is no such rubric or student, or `selections` do not pick exactly
+    ///    one level of every criterion.
+    pub async fn grade_with_rubric(
+        &self,
+        name: String,
+        rubric: String,
+        selections: Vec<Selection>,
+    ) -> Result<RubricScore, ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
         let (tx, rx) = oneshot::channel();
 
-        let msg: JohnMessage = JohnMessage::SendAllToBrightspace { reply_to: tx };
-        let _ = self.sender.send(msg).await;
+        let msg = JohnMessage::GradeWithRubric {
+            scope: self.scope.clone(),
+            name,
+            rubric,
+            selections,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Attaches feedback to `name`'s current grade, written out or taken from the comment bank; Brightspace and
+    /// Admin get it with the grade on the next sync (see `feedback.rs`).
+    ///  - Returns `ActorError::Refused` if there is no such student in this handle's scope, or no such comment in
+    ///    the bank.
+    pub async fn comment_on(&self, name: String, text: CommentText) -> Result<Comment, ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = JohnMessage::AddComment {
+            scope: self.scope.clone(),
+            name,
+            text,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Takes back all of the feedback on `name`'s grade.
+    pub async fn clear_comments(&self, name: String) -> Result<(), ActorError> {
+        self.authorize(Operation::AssignGrade)?;
+        let msg = JohnMessage::ClearComments {
+            scope: self.scope.clone(),
+            name,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Tells John which comments coordinators can reuse (see `topology.toml`).
+    pub async fn set_comment_bank(&self, comment_bank: CommentBank) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = JohnMessage::SetCommentBank { comment_bank };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Tells John how every rubric scores its criteria (see `topology.toml`).
+    pub async fn set_rubrics(&self, rubrics: Vec<Rubric>) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = JohnMessage::SetRubrics { rubrics };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Tells John when every assessment is due and how late work is marked down (see `topology.toml`).
+    pub async fn set_deadlines(&self, deadlines: Deadlines) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let msg = JohnMessage::SetDeadlines { deadlines };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Gives John a Brightspace to report to.
+    ///  - Returns `ActorError::Cycle` without changing anything if that Brightspace can already reach this John,
+    ///    because John would then end up waiting on himself.
+    pub async fn set_brightspace(
+        &self,
+        brightspace_handle: BrightspaceHandle,
+    ) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let link = wiring::connect(self.id, brightspace_handle.id())?;
+        let msg: JohnMessage = JohnMessage::SetBrightspace { brightspace_handle };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    pub async fn report_all_students_and_grades_to_brightspace(&self) -> Result<(), ActorError> {
+        self.authorize(Operation::ReportToBrightspace)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::SendAllToBrightspace {
+            deadline,
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        // Note: the outer `?` is for "did John reply in time", the returned value is "did the work itself succeed"
+        call::recv(ACTOR, rx, deadline).await?
+    }
+
+    pub async fn get_all_student_names(&self) -> Result<Vec<String>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::GetAllStudentNames {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    pub async fn get_all_student_grades(&self) -> Result<Vec<Grade>, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::GetAllStudentGrades {
+            scope: self.scope.clone(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Every roster John holds, in every course and term (e.g. to save them).
+    pub async fn get_courses(&self) -> Result<Courses, ActorError> {
+        self.authorize(Operation::ReadGradebook)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::GetCourses { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// Replaces every roster John holds with `courses`, e.g. when loading a saved gradebook.
+    pub async fn load_courses(&self, courses: Courses) -> Result<(), ActorError> {
+        self.authorize(Operation::WriteGradebook)?;
+        let msg: JohnMessage = JohnMessage::LoadCourses { courses };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Replaces the roster of the course this handle is scoped to with `roster` (see `reconcile::fix()`).
+    pub async fn correct_roster(&self, roster: Roster) -> Result<(), ActorError> {
+        self.authorize(Operation::Reconcile)?;
+        let msg = JohnMessage::CorrectRoster {
+            scope: self.scope.clone(),
+            roster,
+        };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
+    }
+
+    /// Archives John's current term and starts `term`, returning the term that was archived.
+    ///  - Returns `ActorError::Refused` if `term` is already current or archived.
+    pub async fn roll_over(&self, term: Term) -> Result<Term, ActorError> {
+        self.authorize(Operation::RollOverTerm)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::RollOver { term, reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline)
+            .await?
+            .map_err(|reason| ActorError::Refused {
+                actor: ACTOR,
+                reason,
+            })
+    }
+
+    /// Everything John holds right now, and which Brightspace he reports to.
+    pub async fn snapshot(&self) -> Result<JohnSnapshot, ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg: JohnMessage = JohnMessage::Snapshot { reply_to: tx };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
 
-        let _ = rx.await;
+    /// Puts John's rosters back the way `snapshot` found them.
+    ///  - Note: this does not re-wire John, give him his Brightspace with `set_brightspace()` (see `snapshot::restore()`).
+    pub async fn restore(&self, snapshot: JohnSnapshot) -> Result<(), ActorError> {
+        self.authorize(Operation::Snapshot)?;
+        let msg: JohnMessage = JohnMessage::Restore { snapshot };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await
     }
 }
 
@@ -1,59 +1,142 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::Parser;
+
 use crate::{
-    admin::AdminHandle, booster::BoosterHandle, brightspace::BrightspaceHandle, john::JohnHandle,
+    access::{Caller, Role},
+    admin::AdminHandle,
+    alerts::AlerterHandle,
+    attendance::AttendanceHandle,
+    booster::BoosterHandle,
+    brightspace::BrightspaceHandle,
+    cli::{Cli, Command},
+    grade::Grade,
+    john::JohnHandle,
+    portal::StudentPortalHandle,
+    topology::Topology,
 };
 
+pub mod access;
 pub mod admin;
+pub mod alerts;
+pub mod attendance;
 pub mod booster; // <<< WORK IN HERE
 pub mod brightspace;
+pub mod call;
+pub mod cli;
+pub mod course;
+pub mod deadline;
+pub mod events;
+pub mod feedback;
+pub mod grade;
+#[cfg(test)]
+mod harness;
+pub mod history;
+pub mod http;
+#[cfg(test)]
+mod integration_tests;
+#[cfg(test)]
+mod interleaving_tests;
 pub mod john;
+pub mod portal;
+pub mod proposal;
+pub mod reconcile;
+pub mod rubric;
+pub mod shell;
+pub mod snapshot;
+pub mod timeline;
+pub mod topology;
+pub mod trace;
+pub mod wiring;
 
 #[tokio::main]
-async fn main() {
-    // Step 1: Construct (which also starts up all backends for) All Actors
-    let john_handle = JohnHandle::new().await;
-    let brightspace_handle = BrightspaceHandle::new().await;
-    let booster_handle = BoosterHandle::new().await;
-    let admin_handle = AdminHandle::new().await;
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    match &cli.command {
+        None | Some(Command::Demo) => demo(&cli.topology).await,
+        Some(Command::Shell) => shell::run(&cli).await,
+        Some(Command::Serve { addr, tokens }) => {
+            trace::set_enabled(cli.verbose);
+            http::serve(&cli, *addr, tokens.as_deref()).await
+        }
+        Some(command) => {
+            trace::set_enabled(cli.verbose);
+            cli::run(&cli, command).await
+        }
+    }
+}
 
+/// The onboarding script: start the actors, push two students through John -> Brightspace -> Admin,
+/// boost them, and print what Admin ends up with.
+async fn demo(topology: &Path) -> anyhow::Result<()> {
+    // Step 1: Construct (which also starts up all backends for) All Actors, and
     // Step 2: Orchestrate Actors
-    john_handle
-        .set_brightspace(brightspace_handle.clone())
-        .await;
-    brightspace_handle.set_admin(admin_handle.clone()).await;
+    //  - Note: the topology file (`topology.toml` unless `--topology` says otherwise) says which actors exist and who gets whose handle, `build()` does the
+    //          `XHandle::with_mailbox()` and `set_brightspace()` / `set_admin()` calls for us
+    //  - Note: every handle call can now fail (e.g. `ActorError::Timeout`), `?` hands that error back to whoever called us
+    let actors = Topology::from_file(topology)?.build().await?;
+    let john_handle = actors
+        .john("john")
+        .context("topology has no actor `john`")?;
+    let brightspace_handle = actors
+        .brightspace("brightspace")
+        .context("topology has no actor `brightspace`")?;
+    let booster_handle = actors
+        .booster("booster")
+        .context("topology has no actor `booster`")?;
+    let admin_handle = actors
+        .admin("admin")
+        .context("topology has no actor `admin`")?;
+    let portal_handle = actors
+        .portal("portal")
+        .context("topology has no actor `portal`")?;
+
+    // Step 2.5: Start the watchdog, which complains on stderr about any actor stuck waiting on another one
+    wiring::spawn_watchdog(Duration::from_secs(1), Duration::from_secs(2));
 
     // Step 3: Use Actors
     john_handle
         .register_new_student("Aarya Patel".to_string())
-        .await;
+        .await?;
     john_handle
         .assign_grade_to_student("Aarya Patel".to_string(), 58.0)
-        .await;
+        .await?;
     john_handle
         .register_new_student("Dane Hindsley".to_string())
-        .await;
+        .await?;
     john_handle
         .assign_grade_to_student("Dane Hindsley".to_string(), 53.0)
-        .await;
+        .await?;
     john_handle
         .report_all_students_and_grades_to_brightspace()
-        .await;
+        .await?;
 
     brightspace_handle
         .generate_and_append_student_career_id()
-        .await;
+        .await?;
     brightspace_handle
         .report_all_students_and_grades_to_admin()
-        .await;
+        .await?;
 
-    booster_handle.set_admin(admin_handle.clone()).await;
-    booster_handle.boost_grades().await;
+    // Note: Booster only PROPOSES the boost, Admin holds it until an approver accepts it
+    let proposal = booster_handle.boost_grades().await?;
+    admin_handle.accept_proposal(proposal).await?;
 
-    let all_student_names: Vec<String> = admin_handle.get_all_student_names().await;
-    let all_student_grades: Vec<f64> = admin_handle.get_all_student_grades().await;
-    let num_failing_students: usize = admin_handle.count_number_of_failing_students().await;
+    let all_student_names: Vec<String> = admin_handle.get_all_student_names().await?;
+    let all_student_grades: Vec<Grade> = admin_handle.get_all_student_grades().await?;
+    let num_failing_students: usize = admin_handle.count_number_of_failing_students().await?;
 
     // Step 4: Print Results
     println!("names of students:  {:?}", all_student_names);
     println!("grades of students: {:?}", all_student_grades);
     println!("number of students failed: {}", num_failing_students);
+
+    // Note: a student only ever sees their own record, looked up by the career ID Brightspace gave them
+    let aarya = Caller::new("apatel", Role::Student);
+    let record = portal_handle.acting_as(aarya).look_up("apatel").await?;
+    println!("apatel sees in the portal: {:?}", record);
+
+    Ok(())
 }
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
+
+use crate::access::{self, Caller, Operation, Role};
+use crate::admin::{AdminReader, GradebookView, PASSING_GRADE};
+use crate::brightspace;
+use crate::call::{self, ActorError};
+use crate::course::CourseKey;
+use crate::grade::Grade;
+use crate::trace::trace;
+use crate::wiring::{self, ActorId};
+
+const ACTOR: &str = "StudentPortal";
+
+/// The letter a grade shows up as in the portal (anything below `PASSING_GRADE` is an F),
+/// or `None` for a grade without a score.
+pub fn letter_grade(grade: Grade) -> Option<char> {
+    let letter = match grade.score()? {
+        g if g >= 90.0 => 'A',
+        g if g >= 80.0 => 'B',
+        g if g >= 70.0 => 'C',
+        g if g >= PASSING_GRADE => 'D',
+        _ => 'F',
+    };
+    Some(letter)
+}
+
+/// Everything the portal will tell a student about themselves, and nothing about anyone else.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StudentRecord {
+    pub name: String,
+    pub career_id: String,
+    /// Every course of the current term they are in, in course order.
+    pub courses: Vec<CourseRecord>,
+}
+
+/// A student's standing in one course.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CourseRecord {
+    pub course: CourseKey,
+    pub grade: Grade,
+    pub letter: Option<char>,
+    /// The written feedback on their grade in this course, oldest first.
+    pub feedback: Vec<String>,
+}
+
+// ##################################################### //
+// ################### ACTOR BACKEND ################### //
+// ##################################################### //
+
+struct StudentPortal {
+    receiver: mpsc::Receiver<PortalMessage>,
+
+    records: HashMap<String, StudentRecord>, // Keyed by career ID, rebuilt whenever Admin's gradebook changes
+    updates: Option<watch::Receiver<GradebookView>>,
+}
+
+#[derive(Debug)]
+enum PortalMessage {
+    WatchAdmin {
+        updates: watch::Receiver<GradebookView>,
+    },
+    LookUp {
+        career_id: String,
+        reply_to: oneshot::Sender<Option<StudentRecord>>,
+    },
+}
+
+impl StudentPortal {
+    fn new(receiver: mpsc::Receiver<PortalMessage>) -> Self {
+        StudentPortal {
+            receiver,
+            records: HashMap::new(),
+            updates: None,
+        }
+    }
+
+    /// Re-reads Admin's latest gradebook, keeping only the students Brightspace gave a career ID.
+    ///  - Note: two students can end up with the same career ID (Aarya and Arjun Patel are both apatel); the portal
+    ///    cannot tell which one is asking, so it serves neither.
+    fn refresh(&mut self) {
+        let Some(updates) = &mut self.updates else {
+            return;
+        };
+        let view = updates.borrow_and_update();
+        let mut records: HashMap<String, StudentRecord> = HashMap::new();
+        let mut ambiguous = HashSet::new();
+        for (course, entry, grade) in view.students() {
+            let Some((name, career_id)) = brightspace::split_career_id(entry) else {
+                continue;
+            };
+            let record = records
+                .entry(career_id.to_string())
+                .or_insert_with(|| StudentRecord {
+                    name: name.to_string(),
+                    career_id: career_id.to_string(),
+                    courses: Vec::new(),
+                });
+            if record.name != name {
+                ambiguous.insert(career_id.to_string());
+                continue;
+            }
+            let comments = view.rosters[course].comments.get(name);
+            record.courses.push(CourseRecord {
+                course: course.clone(),
+                grade,
+                letter: letter_grade(grade),
+                feedback: comments
+                    .into_iter()
+                    .flatten()
+                    .map(|c| c.text.clone())
+                    .collect(),
+            });
+        }
+        for career_id in ambiguous {
+            trace!(
+                "[ACTOR] StudentPortal not serving {}, more than one student has that career ID",
+                career_id
+            );
+            records.remove(&career_id);
+        }
+        self.records = records;
+        trace!(
+            "[ACTOR] StudentPortal now serves {} student(s)",
+            self.records.len()
+        );
+    }
+
+    async fn handle_message(&mut self, msg: PortalMessage) {
+        trace!(
+            "[Actor] StudentPortal is running handle_message() with new PortalMessage: {:?}",
+            msg
+        );
+        match msg {
+            PortalMessage::WatchAdmin { updates } => {
+                self.updates = Some(updates);
+                self.refresh();
+            }
+            PortalMessage::LookUp {
+                career_id,
+                reply_to,
+            } => {
+                // Note: an update can be waiting behind this message, so catch up before answering
+                if self
+                    .updates
+                    .as_ref()
+                    .is_some_and(|u| u.has_changed().unwrap_or(false))
+                {
+                    self.refresh();
+                }
+                let _ = reply_to.send(self.records.get(&career_id).cloned());
+            }
+        }
+    }
+}
+
+/// Waits for Admin's next change, or forever if there is no Admin to watch (any more).
+async fn next_update(updates: &mut Option<watch::Receiver<GradebookView>>) -> Option<()> {
+    updates.as_mut()?.changed().await.ok()
+}
+
+async fn run_portal_actor(mut actor: StudentPortal) {
+    loop {
+        tokio::select! {
+            msg = actor.receiver.recv() => {
+                let Some(msg) = msg else { break };
+                actor.handle_message(msg).await;
+            }
+            Some(()) = next_update(&mut actor.updates) => actor.refresh(),
+        }
+    }
+}
+
+// ###################################################### //
+// ################### ACTOR FRONTEND ################### //
+// ###################################################### //
+
+#[derive(Clone, Debug)]
+pub struct StudentPortalHandle {
+    id: ActorId,
+    sender: mpsc::Sender<PortalMessage>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    caller: Option<Caller>,
+}
+
+impl StudentPortalHandle {
+    pub async fn new() -> Self {
+        StudentPortalHandle::with_mailbox(8).await
+    }
+
+    /// Same as `new()`, but the mailbox holds up to `capacity` messages before senders have to wait.
+    pub async fn with_mailbox(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let actor = StudentPortal::new(receiver);
+        tokio::spawn(run_portal_actor(actor));
+
+        StudentPortalHandle {
+            id: ActorId::next(ACTOR),
+            sender,
+            timeout: call::DEFAULT_TIMEOUT,
+            deadline: None,
+            caller: None,
+        }
+    }
+
+    /// Which StudentPortal actor this handle talks to.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a copy of this handle whose calls each get `timeout` to finish.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        StudentPortalHandle {
+            timeout,
+            deadline: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle whose calls must all finish by `deadline`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        StudentPortalHandle {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this handle that can only do what `caller`'s role allows.
+    ///  - Note: only ever narrows, a handle already acting for someone keeps them unless `caller` may do less.
+    ///  - Note: a student's name is taken to be their career ID, so they can only look themselves up.
+    pub fn acting_as(&self, caller: Caller) -> Self {
+        StudentPortalHandle {
+            caller: Some(access::narrow(self.caller.as_ref(), caller)),
+            ..self.clone()
+        }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + self.timeout)
+    }
+
+    fn authorize(&self, op: Operation) -> Result<(), ActorError> {
+        access::authorize(self.caller.as_ref(), ACTOR, op)
+    }
+
+    /// Subscribes this portal to `admin`'s gradebook, so it always serves the latest grades.
+    pub async fn watch_admin(&self, admin: &AdminReader) -> Result<(), ActorError> {
+        self.authorize(Operation::Wire)?;
+        let link = wiring::connect(self.id, admin.id())?;
+        let updates = admin.watch_gradebook().await?;
+        let msg = PortalMessage::WatchAdmin { updates };
+        call::send(ACTOR, &self.sender, msg, self.deadline()).await?;
+        link.keep();
+        Ok(())
+    }
+
+    /// The name, and the grade and letter grade in every course, of the student with `career_id`, or `None` if
+    /// Admin has no such student (or more than one).
+    ///  - Returns `ActorError::Denied` if a student asks about any career ID but their own.
+    pub async fn look_up(&self, career_id: &str) -> Result<Option<StudentRecord>, ActorError> {
+        self.authorize(Operation::ViewStudentRecord)?;
+        if let Some(caller) = &self.caller
+            && caller.role == Role::Student
+            && caller.name != career_id
+        {
+            eprintln!(
+                "[ACCESS]: denied {} looking up another student in {}",
+                caller, ACTOR
+            );
+            return Err(ActorError::Denied {
+                actor: ACTOR,
+                caller: caller.to_string(),
+                operation: Operation::ViewStudentRecord,
+            });
+        }
+        let deadline = self.deadline();
+        let (tx, rx) = oneshot::channel();
+
+        let msg = PortalMessage::LookUp {
+            career_id: career_id.to_string(),
+            reply_to: tx,
+        };
+        call::send(ACTOR, &self.sender, msg, deadline).await?;
+
+        call::recv(ACTOR, rx, deadline).await
+    }
+
+    /// The caller's own record, taking their name as their career ID.
+    pub async fn my_record(&self) -> Result<Option<StudentRecord>, ActorError> {
+        let Some(caller) = &self.caller else {
+            return Err(ActorError::Refused {
+                actor: ACTOR,
+                reason: "only a handle scoped with acting_as() has a career ID to look up"
+                    .to_string(),
+            });
+        };
+        self.look_up(&caller.name).await
+    }
+}
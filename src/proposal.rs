@@ -0,0 +1,120 @@
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::CourseKey;
+use crate::grade::Grade;
+
+// ###################################################### //
+// ############### PENDING GRADE PROPOSALS ############## //
+// ###################################################### //
+
+/// How long a proposal waits for an approver before Admin throws it away.
+pub const DEFAULT_PROPOSAL_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+pub type ProposalId = u64;
+
+/// One student's grade in one course going `from` one value `to` another.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradeChange {
+    /// Proposals saved before changes had a course are about the default course.
+    #[serde(default)]
+    pub course: CourseKey,
+    pub name: String,
+    pub from: Grade,
+    pub to: Grade,
+}
+
+/// A set of grade changes (e.g. a Booster curve) waiting in Admin for someone to accept or reject it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proposal {
+    pub id: ProposalId,
+    pub proposed_by: String,
+    pub description: String,
+    pub changes: Vec<GradeChange>,
+    pub submitted_at: SystemTime,
+    pub expires_at: SystemTime,
+}
+
+/// What finally happened to a proposal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    Accepted { by: String },
+    Rejected { by: String, reason: String },
+    Expired,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub proposal: Proposal,
+    pub outcome: Outcome,
+    pub decided_at: SystemTime,
+}
+
+/// Admin's record of every proposal: the ones still waiting and the ones already decided.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProposalBook {
+    pub pending: Vec<Proposal>,
+    pub decided: Vec<Decision>,
+    pub next_id: ProposalId,
+}
+
+impl ProposalBook {
+    pub(crate) fn submit(
+        &mut self,
+        proposed_by: String,
+        description: String,
+        changes: Vec<GradeChange>,
+        ttl: Duration,
+    ) -> ProposalId {
+        self.next_id += 1;
+        let now = SystemTime::now();
+        self.pending.push(Proposal {
+            id: self.next_id,
+            proposed_by,
+            description,
+            changes,
+            submitted_at: now,
+            expires_at: now + ttl,
+        });
+        self.next_id
+    }
+
+    /// Takes proposal `id` out of the pending list, or says why it can't.
+    pub(crate) fn take(&mut self, id: ProposalId) -> Result<Proposal, String> {
+        match self.pending.iter().position(|p| p.id == id) {
+            Some(index) => Ok(self.pending.remove(index)),
+            None => match self.decided.iter().find(|d| d.proposal.id == id) {
+                Some(Decision {
+                    outcome: Outcome::Expired,
+                    ..
+                }) => Err(format!("proposal #{} has expired", id)),
+                Some(_) => Err(format!("proposal #{} was already decided", id)),
+                None => Err(format!("there is no proposal #{}", id)),
+            },
+        }
+    }
+
+    pub(crate) fn record(&mut self, proposal: Proposal, outcome: Outcome) {
+        self.decided.push(Decision {
+            proposal,
+            outcome,
+            decided_at: SystemTime::now(),
+        });
+    }
+
+    /// Moves every proposal past its `expires_at` into the decided list, returning how many there were.
+    pub(crate) fn expire(&mut self) -> usize {
+        let now = SystemTime::now();
+        let (expired, pending): (Vec<Proposal>, Vec<Proposal>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|p| p.expires_at <= now);
+        self.pending = pending;
+
+        let count = expired.len();
+        for proposal in expired {
+            self.record(proposal, Outcome::Expired);
+        }
+        count
+    }
+}
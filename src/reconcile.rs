@@ -0,0 +1,468 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::admin::AdminHandle;
+use crate::brightspace::{self, BrightspaceHandle};
+use crate::call::ActorError;
+use crate::course::{CourseKey, Courses, Roster};
+use crate::events::student_of;
+use crate::grade::Grade;
+use crate::john::JohnHandle;
+use crate::wiring::ActorId;
+
+// ###################################################### //
+// ####### RECONCILING JOHN, BRIGHTSPACE AND ADMIN ###### //
+// ###################################################### //
+
+// Note: John keeps plain names, Brightspace (and so Admin) may have appended career IDs to them, and Admin
+//       changes on its own when a proposal is accepted. So students are matched by career ID, never by the
+//       exact name, and only the current term is compared: archived terms can no longer be fixed anyway.
+//       Two students with the same career ID (e.g. Aarya and Arjun Patel) are one student to this job, as
+//       they are to the StudentPortal.
+
+/// One of the three actors that hold a roster.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum Side {
+    John,
+    Brightspace,
+    Admin,
+}
+
+impl FromStr for Side {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "john" => Ok(Side::John),
+            "brightspace" => Ok(Side::Brightspace),
+            "admin" => Ok(Side::Admin),
+            _ => Err(format!(
+                "unknown side `{}`, expected john, brightspace or admin",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::John => write!(f, "John"),
+            Side::Brightspace => write!(f, "Brightspace"),
+            Side::Admin => write!(f, "Admin"),
+        }
+    }
+}
+
+impl Side {
+    pub const ALL: [Side; 3] = [Side::John, Side::Brightspace, Side::Admin];
+}
+
+/// Which side is right when the rosters disagree: `students` decides who is in each course and what they are
+/// called, `grades` what grade they have, e.g. `john:admin` keeps John's students with Admin's (boosted) grades.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct TruthRule {
+    pub students: Side,
+    pub grades: Side,
+}
+
+impl TruthRule {
+    /// `side` is right about everything.
+    pub fn trust(side: Side) -> Self {
+        TruthRule {
+            students: side,
+            grades: side,
+        }
+    }
+}
+
+impl Default for TruthRule {
+    /// John, since that is where students are registered and graded.
+    fn default() -> Self {
+        TruthRule::trust(Side::John)
+    }
+}
+
+impl FromStr for TruthRule {
+    type Err = String;
+
+    /// `<side>` or `<students side>:<grades side>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((students, grades)) => Ok(TruthRule {
+                students: students.parse()?,
+                grades: grades.parse()?,
+            }),
+            None => s.parse().map(TruthRule::trust),
+        }
+    }
+}
+
+impl fmt::Display for TruthRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.students == self.grades {
+            write!(f, "{} is right", self.students)
+        } else {
+            write!(
+                f,
+                "{}'s students with {}'s grades",
+                self.students, self.grades
+            )
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+    /// Some sides have the student and others do not.
+    MissingStudent,
+    GradeDifference,
+    /// The sides call the student differently (beyond Brightspace appending a career ID).
+    NameDifference,
+}
+
+impl fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchKind::MissingStudent => write!(f, "missing student"),
+            MismatchKind::GradeDifference => write!(f, "grade difference"),
+            MismatchKind::NameDifference => write!(f, "name difference"),
+        }
+    }
+}
+
+/// One way the three rosters disagree about one student.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Mismatch {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    /// The career ID the student was matched by, or their name if Brightspace cannot make one.
+    pub student: String,
+    pub kind: MismatchKind,
+    /// What each side holds for the student (their name, or their grade for a grade difference), `None`
+    /// where the student is missing.
+    pub john: Option<String>,
+    pub brightspace: Option<String>,
+    pub admin: Option<String>,
+}
+
+/// The actors a reconciliation pulls from (and writes back to).
+pub struct Sides<'a> {
+    /// Every coordinator's John, by topology name.
+    pub johns: &'a BTreeMap<String, JohnHandle>,
+    /// The John that gets a student no coordinator has, unless Brightspace says whose they are.
+    pub lead: &'a JohnHandle,
+    pub brightspace: &'a BrightspaceHandle,
+    pub admin: &'a AdminHandle,
+}
+
+/// A student as one side holds them.
+#[derive(Clone, Debug)]
+struct Entry {
+    name: String,
+    grade: Grade,
+}
+
+/// One side's students in one course, by the key they are matched by, in roster order.
+#[derive(Debug, Default)]
+struct Held {
+    order: Vec<String>,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Held {
+    fn add(&mut self, name: &str, grade: Grade) {
+        let key = match_key(name);
+        if !self.entries.contains_key(&key) {
+            self.order.push(key.clone());
+        }
+        let entry = Entry {
+            name: name.to_string(),
+            grade,
+        };
+        self.entries.insert(key, entry);
+    }
+
+    fn of(roster: Option<&Roster>) -> Self {
+        let mut held = Held::default();
+        for (i, name) in roster.iter().flat_map(|r| r.names.iter().enumerate()) {
+            let grade = roster
+                .and_then(|r| r.grades.get(i))
+                .copied()
+                .unwrap_or(Grade::Ungraded);
+            held.add(name, grade);
+        }
+        held
+    }
+}
+
+/// The career ID a name (with or without one appended) is matched by, or the name itself.
+fn match_key(name: &str) -> String {
+    let name = student_of(name);
+    brightspace::career_id(name).unwrap_or_else(|| name.to_string())
+}
+
+/// Everything the three sides hold right now.
+struct Pulled {
+    johns: BTreeMap<String, (ActorId, Courses)>,
+    brightspace: Courses,
+    admin: Courses,
+    owners: HashMap<(CourseKey, String), ActorId>,
+}
+
+impl Pulled {
+    async fn from(sides: &Sides<'_>) -> Result<Self, ActorError> {
+        let mut johns = BTreeMap::new();
+        for (name, john) in sides.johns {
+            johns.insert(name.clone(), (john.id(), john.get_courses().await?));
+        }
+        let owners = sides.brightspace.get_owners().await?;
+        Ok(Pulled {
+            johns,
+            brightspace: sides.brightspace.get_courses().await?,
+            admin: sides.admin.get_courses().await?,
+            owners: owners
+                .into_iter()
+                .map(|(key, student, owner)| ((key, student), owner))
+                .collect(),
+        })
+    }
+
+    /// Every course of the current term any side has.
+    fn courses(&self) -> BTreeSet<CourseKey> {
+        let current = |courses: &Courses| {
+            let term = courses.term().clone();
+            courses
+                .rosters()
+                .filter(move |(key, _)| key.term == term)
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>()
+        };
+        (self
+            .johns
+            .values()
+            .flat_map(|(_, courses)| current(courses)))
+        .chain(current(&self.brightspace))
+        .chain(current(&self.admin))
+        .collect()
+    }
+
+    /// Every coordinator's students in `key` as one roster; a student several coordinators have is taken
+    /// from the one Brightspace says they belong to.
+    fn john(&self, key: &CourseKey) -> Held {
+        let mut held = Held::default();
+        for (id, courses) in self.johns.values() {
+            let Some(roster) = courses.roster(key) else {
+                continue;
+            };
+            let grades = roster
+                .grades
+                .iter()
+                .chain(std::iter::repeat(&Grade::Ungraded));
+            for (name, grade) in roster.names.iter().zip(grades) {
+                let owner = self.owners.get(&(key.clone(), name.clone()));
+                if !held.entries.contains_key(&match_key(name)) || owner == Some(id) {
+                    held.add(name, *grade);
+                }
+            }
+        }
+        held
+    }
+
+    /// What every side holds for `key`.
+    fn held(&self, key: &CourseKey) -> BTreeMap<Side, Held> {
+        let of = |side| match side {
+            Side::John => self.john(key),
+            Side::Brightspace => Held::of(self.brightspace.roster(key)),
+            Side::Admin => Held::of(self.admin.roster(key)),
+        };
+        Side::ALL.into_iter().map(|side| (side, of(side))).collect()
+    }
+}
+
+/// Every way the three sides disagree about the students in `course`.
+fn compare(course: &CourseKey, held: &BTreeMap<Side, Held>) -> Vec<Mismatch> {
+    let (john, bs, admin) = (
+        &held[&Side::John],
+        &held[&Side::Brightspace],
+        &held[&Side::Admin],
+    );
+    let mut students: Vec<&String> = Vec::new();
+    for key in john.order.iter().chain(&bs.order).chain(&admin.order) {
+        if !students.contains(&key) {
+            students.push(key);
+        }
+    }
+
+    let mut mismatches = Vec::new();
+    for student in students {
+        let held = [john, bs, admin].map(|side| side.entries.get(student));
+        let mut push = |kind: MismatchKind, show: fn(&Entry) -> String| {
+            let [john, brightspace, admin] = held.map(|entry| entry.map(show));
+            mismatches.push(Mismatch {
+                course: course.clone(),
+                student: student.clone(),
+                kind,
+                john,
+                brightspace,
+                admin,
+            });
+        };
+        let present: Vec<&Entry> = held.iter().flatten().copied().collect();
+
+        if present.len() < 3 {
+            push(MismatchKind::MissingStudent, |e| e.name.clone());
+        }
+        if present.iter().any(|e| e.grade != present[0].grade) {
+            push(MismatchKind::GradeDifference, |e| e.grade.to_string());
+        }
+        // Note: John never has career IDs, so John is only compared with the others' names without theirs
+        let [j, b, a] = held;
+        let renamed = |plain: Option<&Entry>, other: Option<&Entry>| match (plain, other) {
+            (Some(plain), Some(other)) => plain.name != student_of(&other.name),
+            _ => false,
+        };
+        let differs = match (b, a) {
+            (Some(b), Some(a)) => b.name != a.name || renamed(j, Some(b)),
+            _ => renamed(j, b.or(a)),
+        };
+        if differs {
+            push(MismatchKind::NameDifference, |e| e.name.clone());
+        }
+    }
+    mismatches
+}
+
+/// Pulls every roster of the current term from John, Brightspace and Admin and reports every mismatch.
+///  - Note: nothing is changed; see `fix()` for that.
+pub async fn check(sides: &Sides<'_>) -> Result<Vec<Mismatch>, ActorError> {
+    let pulled = Pulled::from(sides).await?;
+    let mut mismatches = Vec::new();
+    for key in pulled.courses() {
+        mismatches.extend(compare(&key, &pulled.held(&key)));
+    }
+    Ok(mismatches)
+}
+
+/// Same as `check()`, then rewrites every roster that disagrees with what `rule` says is right, returning
+/// the mismatches it found (and fixed).
+///  - Note: Admin's corrections are one entry per course in its history, so `undo` takes them back.
+///  - Note: the rosters are read first and written after, so run it while nobody else is changing them.
+pub async fn fix(sides: &Sides<'_>, rule: TruthRule) -> Result<Vec<Mismatch>, ActorError> {
+    let pulled = Pulled::from(sides).await?;
+    let mut mismatches = Vec::new();
+    for key in pulled.courses() {
+        let held = pulled.held(&key);
+        let found = compare(&key, &held);
+        if found.is_empty() {
+            continue;
+        }
+
+        let (students, grades) = (&held[&rule.students], &held[&rule.grades]);
+        let mut target = Roster::default();
+        for student in &students.order {
+            let entry = &students.entries[student];
+            // Note: a name John has is written the way Brightspace or Admin already write it, career ID and all
+            let name = match rule.students {
+                Side::John => [Side::Brightspace, Side::Admin]
+                    .iter()
+                    .filter_map(|side| held[side].entries.get(student))
+                    .map(|e| e.name.clone())
+                    .find(|name| student_of(name) == entry.name)
+                    .unwrap_or_else(|| entry.name.clone()),
+                _ => entry.name.clone(),
+            };
+            target.names.push(name);
+            target
+                .grades
+                .push(grades.entries.get(student).unwrap_or(entry).grade);
+        }
+
+        write(sides, &pulled, &key, &target, rule, found.len()).await?;
+        mismatches.extend(found);
+    }
+    Ok(mismatches)
+}
+
+/// Writes `target` to every side whose roster for `key` is not already `target`.
+async fn write(
+    sides: &Sides<'_>,
+    pulled: &Pulled,
+    key: &CourseKey,
+    target: &Roster,
+    rule: TruthRule,
+    found: usize,
+) -> Result<(), ActorError> {
+    let unchanged = |courses: &Courses, roster: &Roster| {
+        let mut current = courses.roster(key).cloned().unwrap_or_default();
+        current.grades.resize(current.names.len(), Grade::Ungraded);
+        // Note: late penalties, rubric scores and feedback are not compared, correcting a roster keeps them
+        //       (see `Roster::correct()`)
+        current.late.clear();
+        current.rubrics.clear();
+        current.comments.clear();
+        current == *roster
+    };
+
+    if !unchanged(&pulled.brightspace, target) {
+        let shell = sides
+            .brightspace
+            .in_course(key.course.clone())
+            .in_term(key.term.clone());
+        shell
+            .enter_students_into_brightspace(target.names.clone())
+            .await?;
+        shell
+            .enter_student_grades_into_brightspace(target.grades.clone())
+            .await?;
+    }
+    if !unchanged(&pulled.admin, target) {
+        let description = format!("reconciled {} mismatch(es) in {} ({})", found, key, rule);
+        sides
+            .admin
+            .in_course(key.course.clone())
+            .in_term(key.term.clone())
+            .correct_roster(target.clone(), description)
+            .await?;
+    }
+
+    // Note: every coordinator keeps (corrected) only the students they already had; a student none of them has
+    //       goes to whoever Brightspace says they belong to, or else to the lead
+    let holds = |courses: &Courses, student: &str| {
+        Held::of(courses.roster(key))
+            .entries
+            .contains_key(&match_key(student))
+    };
+    for (name, (id, courses)) in &pulled.johns {
+        let mut roster = Roster::default();
+        for (entry, grade) in target.names.iter().zip(&target.grades) {
+            let student = student_of(entry);
+            let held_by_anyone = pulled.johns.values().any(|(_, c)| holds(c, student));
+            let owner = pulled.owners.get(&(key.clone(), student.to_string()));
+            let theirs = if held_by_anyone {
+                holds(courses, student)
+            } else {
+                match owner.filter(|owner| pulled.johns.values().any(|(id, _)| id == *owner)) {
+                    Some(owner) => owner == id,
+                    None => *id == sides.lead.id(),
+                }
+            };
+            if theirs {
+                roster.names.push(student.to_string());
+                roster.grades.push(*grade);
+            }
+        }
+        if !unchanged(courses, &roster) {
+            sides.johns[name]
+                .in_course(key.course.clone())
+                .in_term(key.term.clone())
+                .correct_roster(roster)
+                .await?;
+        }
+    }
+    Ok(())
+}
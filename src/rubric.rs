@@ -0,0 +1,224 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::CourseKey;
+use crate::grade::Grade;
+
+// ###################################################### //
+// ################## RUBRIC GRADING #################### //
+// ###################################################### //
+
+/// One level a criterion can be met at, e.g. "excellent" for 40 points.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub points: f64,
+}
+
+/// One thing a rubric scores, and the levels it can be met at.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Criterion {
+    pub name: String,
+    pub levels: Vec<Level>,
+}
+
+impl Criterion {
+    /// The most this criterion can add to a score.
+    pub fn max_points(&self) -> f64 {
+        self.levels.iter().map(|l| l.points).fold(0.0, f64::max)
+    }
+}
+
+/// A `[[rubrics]]` entry, e.g. how a design review is scored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rubric {
+    pub name: String,
+    pub criteria: Vec<Criterion>,
+}
+
+/// Which level a student met one criterion at, written `criterion=level` on the command line.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    pub criterion: String,
+    pub level: String,
+}
+
+impl Selection {
+    pub fn new(criterion: impl Into<String>, level: impl Into<String>) -> Self {
+        Selection {
+            criterion: criterion.into(),
+            level: level.into(),
+        }
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.criterion, self.level)
+    }
+}
+
+/// Parses `CRITERION=LEVEL`.
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((criterion, level))
+                if !criterion.trim().is_empty() && !level.trim().is_empty() =>
+            {
+                Ok(Selection::new(criterion.trim(), level.trim()))
+            }
+            _ => Err(format!(
+                "`{}` is not a selection, expected CRITERION=LEVEL",
+                s
+            )),
+        }
+    }
+}
+
+/// What one criterion added to a score.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub criterion: String,
+    pub level: String,
+    pub points: f64,
+    pub max_points: f64,
+}
+
+/// A student's rubric score, criterion by criterion, kept next to their grade for feedback.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RubricScore {
+    pub rubric: String,
+    pub criteria: Vec<CriterionScore>,
+    pub points: f64,
+    pub max_points: f64,
+}
+
+impl RubricScore {
+    /// The points as a grade out of 100, like every other score.
+    pub fn grade(&self) -> Grade {
+        Grade::Scored(self.points / self.max_points * 100.0)
+    }
+}
+
+/// One rubric score in Admin, and the grade it gave the student (see `AdminHandle::get_rubric_scores()`).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RubricReport {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub grade: Grade,
+    pub score: RubricScore,
+}
+
+impl Rubric {
+    pub fn max_points(&self) -> f64 {
+        self.criteria.iter().map(Criterion::max_points).sum()
+    }
+
+    /// The first problem with this rubric, e.g. a criterion without levels or a negative number of points.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.criteria.is_empty() {
+            return Err(format!("rubric `{}` has no criteria", self.name));
+        }
+        let mut criteria = BTreeSet::new();
+        for criterion in &self.criteria {
+            if !criteria.insert(&criterion.name) {
+                return Err(format!(
+                    "rubric `{}` has criterion `{}` more than once",
+                    self.name, criterion.name
+                ));
+            }
+            if criterion.levels.is_empty() {
+                return Err(format!(
+                    "criterion `{}` of rubric `{}` has no levels",
+                    criterion.name, self.name
+                ));
+            }
+            let mut levels = BTreeSet::new();
+            for level in &criterion.levels {
+                if !levels.insert(&level.name) {
+                    return Err(format!(
+                        "criterion `{}` of rubric `{}` has level `{}` more than once",
+                        criterion.name, self.name, level.name
+                    ));
+                }
+                if !(level.points >= 0.0 && level.points.is_finite()) {
+                    return Err(format!(
+                        "level `{}` of criterion `{}` must be worth at least 0 points",
+                        level.name, criterion.name
+                    ));
+                }
+            }
+        }
+        if self.max_points() <= 0.0 {
+            return Err(format!("rubric `{}` is worth no points", self.name));
+        }
+        Ok(())
+    }
+
+    /// Scores `selections`, which must pick exactly one level of every criterion.
+    pub fn score(&self, selections: &[Selection]) -> Result<RubricScore, String> {
+        if let Some(selection) = selections
+            .iter()
+            .find(|s| !self.criteria.iter().any(|c| c.name == s.criterion))
+        {
+            return Err(format!(
+                "rubric `{}` has no criterion `{}`",
+                self.name, selection.criterion
+            ));
+        }
+
+        let mut criteria = Vec::new();
+        for criterion in &self.criteria {
+            let level = match &selections
+                .iter()
+                .filter(|s| s.criterion == criterion.name)
+                .collect::<Vec<_>>()[..]
+            {
+                [selection] => &selection.level,
+                [] => return Err(format!("no level selected for `{}`", criterion.name)),
+                _ => {
+                    return Err(format!(
+                        "more than one level selected for `{}`",
+                        criterion.name
+                    ));
+                }
+            };
+            let points = criterion
+                .levels
+                .iter()
+                .find(|l| l.name == *level)
+                .ok_or_else(|| {
+                    format!(
+                        "`{}` is not a level of `{}`, expected one of {}",
+                        level,
+                        criterion.name,
+                        criterion
+                            .levels
+                            .iter()
+                            .map(|l| l.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?
+                .points;
+            criteria.push(CriterionScore {
+                criterion: criterion.name.clone(),
+                level: level.clone(),
+                points,
+                max_points: criterion.max_points(),
+            });
+        }
+        Ok(RubricScore {
+            rubric: self.name.clone(),
+            points: criteria.iter().map(|c| c.points).sum(),
+            max_points: self.max_points(),
+            criteria,
+        })
+    }
+}
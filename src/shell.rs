@@ -0,0 +1,246 @@
+use std::io::Write;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::task::JoinHandle;
+
+use crate::cli::{self, Cli, Command, Session};
+use crate::events::{EventFilter, EventKind, Notice};
+use crate::{trace, wiring};
+
+// ###################################################### //
+// ################## INTERACTIVE SHELL ################# //
+// ###################################################### //
+
+/// One line typed into the shell.
+#[derive(Debug, Parser)]
+#[command(name = "part>", no_binary_name = true, disable_version_flag = true)]
+struct ShellLine {
+    #[command(subcommand)]
+    command: ShellCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ShellCommand {
+    #[command(flatten)]
+    Gradebook(Command),
+    /// Show what one actor is holding right now.
+    Show {
+        #[arg(value_enum)]
+        actor: ShownActor,
+    },
+    /// Turn the live trace of every message the actors handle on or off.
+    Watch {
+        #[arg(value_enum)]
+        mode: Toggle,
+    },
+    /// Print Admin's change events as they happen, replacing any earlier subscription.
+    Subscribe {
+        /// Only these kinds of event (repeat for more; default: all).
+        #[arg(long, value_enum)]
+        kind: Vec<EventKind>,
+        /// Only events about this student.
+        #[arg(long)]
+        student: Option<String>,
+    },
+    /// Stop printing Admin's change events.
+    Unsubscribe,
+    /// Save the gradebook to the state file now.
+    Save,
+    /// Save the gradebook and leave the shell.
+    #[command(alias = "exit")]
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ShownActor {
+    John,
+    Brightspace,
+    Admin,
+    Booster,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Toggle {
+    On,
+    Off,
+}
+
+/// Splits a line into words like a (very small) Unix shell: spaces separate words,
+/// and '...' or "..." keep spaces inside one word (so `grade "Aarya Patel" 58` works).
+/// A backslash keeps the next character as it is (`Aarya\ Patel`), except inside '...'; inside "..." it
+/// only escapes `"` and `\`.
+pub(crate) fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                word.extend(chars.next());
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\\') => {
+                let escaped = chars.next().ok_or("nothing after \\ at the end")?;
+                word.push(escaped);
+                in_word = true;
+            }
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("missing closing {}", q));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+async fn show(session: &Session, actor: ShownActor, json: bool) -> anyhow::Result<()> {
+    match actor {
+        ShownActor::John => {
+            let names = session.john.get_all_student_names().await?;
+            let grades = session.john.get_all_student_grades().await?;
+            cli::print_students(json, &names, &grades)
+        }
+        ShownActor::Brightspace => {
+            let names = session.brightspace.get_all_student_names().await?;
+            let grades = session.brightspace.get_all_student_grades().await?;
+            cli::print_students(json, &names, &grades)
+        }
+        ShownActor::Admin => {
+            let names = session.admin.get_all_student_names().await?;
+            let grades = session.admin.get_all_student_grades().await?;
+            cli::print_students(json, &names, &grades)
+        }
+        ShownActor::Booster => {
+            let links: Vec<String> = wiring::links_of(session.booster.id())
+                .iter()
+                .map(|id| id.to_string())
+                .collect();
+            if json {
+                cli::print_json(&serde_json::json!({ "boosts": links }))
+            } else {
+                println!("{} boosts: {}", session.booster.id(), links.join(", "));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Subscribes to Admin and prints every event that passes the filter in the background.
+async fn subscribe(
+    session: &Session,
+    kinds: Vec<EventKind>,
+    student: Option<String>,
+    json: bool,
+) -> anyhow::Result<JoinHandle<()>> {
+    let mut filter = EventFilter::default().kinds(kinds);
+    if let Some(student) = student {
+        filter = filter.student(student);
+    }
+    let mut subscription = session.admin.subscribe(filter).await?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(notice) = subscription.recv().await {
+            match notice {
+                Notice::Event(event) if json => match serde_json::to_string(&event) {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => eprintln!("error: {}", e),
+                },
+                Notice::Event(event) => println!("[EVENT]: {}", event),
+                Notice::Lagged { missed } => eprintln!(
+                    "[EVENT]: fell behind and missed {} event(s) ({} so far)",
+                    missed,
+                    subscription.missed()
+                ),
+            }
+        }
+    }))
+}
+
+/// Starts the actors, then reads commands from stdin until `quit` or end of input.
+pub async fn run(cli: &Cli) -> anyhow::Result<()> {
+    trace::set_enabled(cli.verbose);
+    let owner = Session::start(cli).await?;
+    let session = owner.acting_as(cli.caller()).scoped(&cli.scope());
+
+    let mut subscriber: Option<JoinHandle<()>> = None;
+
+    println!("PART gradebook shell, type `help` for commands and `quit` to leave.");
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("part> ");
+        std::io::stdout().flush()?;
+
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        let words = match split_words(&line) {
+            Ok(words) if words.is_empty() => continue,
+            Ok(words) => words,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let command = match ShellLine::try_parse_from(words) {
+            Ok(parsed) => parsed.command,
+            Err(e) => {
+                // Note: clap reports `help` and `--help` as "errors" too, rendering them prints the help text
+                let _ = e.print();
+                continue;
+            }
+        };
+
+        let result = match command {
+            ShellCommand::Gradebook(command) => cli::execute(&session, &command, cli.json).await,
+            ShellCommand::Show { actor } => show(&session, actor, cli.json).await,
+            ShellCommand::Watch { mode } => {
+                trace::set_enabled(matches!(mode, Toggle::On));
+                Ok(())
+            }
+            ShellCommand::Subscribe { kind, student } => {
+                subscribe(&session, kind, student, cli.json)
+                    .await
+                    .map(|task| {
+                        if let Some(old) = subscriber.replace(task) {
+                            old.abort();
+                        }
+                    })
+            }
+            ShellCommand::Unsubscribe => {
+                if let Some(old) = subscriber.take() {
+                    old.abort();
+                }
+                Ok(())
+            }
+            ShellCommand::Save => owner.persist(cli).await,
+            ShellCommand::Quit => break,
+        };
+        if let Err(e) = result {
+            eprintln!("error: {:#}", e);
+        }
+    }
+
+    trace::set_enabled(false);
+    owner.persist(cli).await
+}
@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::admin::AdminSnapshot;
+use crate::brightspace::{BrightspaceSnapshot, Conflict};
+use crate::call::ActorError;
+use crate::cli::Ownership;
+use crate::course::{CourseKey, Courses};
+use crate::grade::Grade;
+use crate::history::History;
+use crate::john::JohnSnapshot;
+use crate::proposal::ProposalBook;
+use crate::timeline::Timelines;
+use crate::topology::{ActorKind, Actors, LinkSpec, Topology};
+use crate::wiring::ActorId;
+
+// ###################################################### //
+// ################# SNAPSHOT AND RESTORE ############### //
+// ###################################################### //
+
+/// The snapshot format `take()` writes; `restore()` refuses snapshots from a newer format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Every John, Brightspace, Admin and Booster in a topology at one point in time, by their topology names.
+///  - Note: actor ids only mean something while the actors run, so the file names actors (and who is wired
+///    to whom) the way the topology does; `restore()` turns the names back into the new actors' handles.
+///  - Note: the actors are asked one after the other, so take a snapshot while nothing else is changing them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    pub version: u32,
+    pub taken_at: SystemTime,
+    #[serde(default)]
+    pub johns: BTreeMap<String, JohnState>,
+    #[serde(default)]
+    pub brightspaces: BTreeMap<String, BrightspaceState>,
+    #[serde(default)]
+    pub admins: BTreeMap<String, AdminState>,
+    #[serde(default)]
+    pub boosters: BTreeMap<String, BoosterState>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JohnState {
+    pub courses: Courses,
+    /// The Brightspace John reports to.
+    pub brightspace: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BrightspaceState {
+    pub shells: Courses,
+    #[serde(default)]
+    pub owners: Vec<Ownership>,
+    #[serde(default)]
+    pub conflicts: Vec<ConflictRecord>,
+    /// The Admin Brightspace reports to.
+    pub admin: Option<String>,
+}
+
+/// A `Conflict` with the coordinators called by their topology names.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConflictRecord {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub student: String,
+    pub owner: String,
+    pub owner_grade: Grade,
+    pub rival: String,
+    pub rival_grade: Grade,
+    pub raised_at: SystemTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminState {
+    pub courses: Courses,
+    #[serde(default)]
+    pub proposals: ProposalBook,
+    pub proposal_ttl: Duration,
+    #[serde(default)]
+    pub history: History,
+    #[serde(default)]
+    pub timelines: Timelines,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoosterState {
+    /// The Admin Booster proposes to.
+    pub admin: Option<String>,
+}
+
+/// Every actor's topology name, by its id.
+fn names_of(actors: &Actors) -> HashMap<ActorId, String> {
+    let johns = actors.johns.iter().map(|(name, h)| (h.id(), name.clone()));
+    let brightspaces = actors
+        .brightspaces
+        .iter()
+        .map(|(name, h)| (h.id(), name.clone()));
+    let admins = actors.admins.iter().map(|(name, h)| (h.id(), name.clone()));
+    let boosters = actors
+        .boosters
+        .iter()
+        .map(|(name, h)| (h.id(), name.clone()));
+    johns
+        .chain(brightspaces)
+        .chain(admins)
+        .chain(boosters)
+        .collect()
+}
+
+/// Asks every John, Brightspace, Admin and Booster in `actors` for a snapshot.
+pub async fn take(actors: &Actors) -> Result<SystemSnapshot, ActorError> {
+    let names = names_of(actors);
+    // Note: an id that is not one of `actors` (e.g. a coordinator wired in by hand) is kept as the id itself
+    let name = |id: ActorId| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+
+    let mut snapshot = SystemSnapshot {
+        version: SNAPSHOT_VERSION,
+        taken_at: SystemTime::now(),
+        johns: BTreeMap::new(),
+        brightspaces: BTreeMap::new(),
+        admins: BTreeMap::new(),
+        boosters: BTreeMap::new(),
+    };
+    for (john_name, john) in &actors.johns {
+        let JohnSnapshot {
+            courses,
+            brightspace,
+        } = john.snapshot().await?;
+        let state = JohnState {
+            courses,
+            brightspace: brightspace.map(name),
+        };
+        snapshot.johns.insert(john_name.clone(), state);
+    }
+    for (bs_name, bs) in &actors.brightspaces {
+        let BrightspaceSnapshot {
+            shells,
+            owners,
+            conflicts,
+            admin,
+        } = bs.snapshot().await?;
+        let owners = owners
+            .into_iter()
+            .map(|(course, student, owner)| Ownership {
+                course,
+                student,
+                coordinator: name(owner),
+            })
+            .collect();
+        let conflicts = conflicts
+            .into_iter()
+            .map(|c| ConflictRecord {
+                course: c.course,
+                student: c.student,
+                owner: name(c.owner),
+                owner_grade: c.owner_grade,
+                rival: name(c.rival),
+                rival_grade: c.rival_grade,
+                raised_at: c.raised_at,
+            })
+            .collect();
+        let state = BrightspaceState {
+            shells,
+            owners,
+            conflicts,
+            admin: admin.map(name),
+        };
+        snapshot.brightspaces.insert(bs_name.clone(), state);
+    }
+    for (admin_name, admin) in &actors.admins {
+        let AdminSnapshot {
+            courses,
+            proposals,
+            proposal_ttl,
+            history,
+            timelines,
+        } = admin.snapshot().await?;
+        let state = AdminState {
+            courses,
+            proposals,
+            proposal_ttl,
+            history,
+            timelines,
+        };
+        snapshot.admins.insert(admin_name.clone(), state);
+    }
+    for (booster_name, booster) in &actors.boosters {
+        let state = BoosterState {
+            admin: booster.snapshot().await?.admin.map(name),
+        };
+        snapshot.boosters.insert(booster_name.clone(), state);
+    }
+    Ok(snapshot)
+}
+
+/// Loads `snapshot` into freshly started `actors` and wires them the way they were wired when it was taken.
+///  - Every actor in the snapshot must be in `actors` (i.e. in the topology they were started from).
+///  - Actors the snapshot does not cover (a StudentPortal, an Alerter) are wired as `topology` says.
+pub async fn restore(
+    topology: &Topology,
+    actors: &Actors,
+    snapshot: &SystemSnapshot,
+) -> anyhow::Result<()> {
+    if snapshot.version > SNAPSHOT_VERSION {
+        anyhow::bail!(
+            "snapshot version {} is newer than this build understands ({})",
+            snapshot.version,
+            SNAPSHOT_VERSION
+        );
+    }
+    let missing = |kind: ActorKind, name: &str| {
+        anyhow::anyhow!(
+            "the snapshot has a {:?} `{}` the topology does not",
+            kind,
+            name
+        )
+    };
+
+    // Note: everything is loaded before anything is wired, so restored grades do not look like new changes
+    for (name, state) in &snapshot.johns {
+        let john = actors
+            .john(name)
+            .ok_or_else(|| missing(ActorKind::John, name))?;
+        let restored = JohnSnapshot {
+            courses: state.courses.clone(),
+            brightspace: None,
+        };
+        john.restore(restored).await?;
+    }
+    for (name, state) in &snapshot.brightspaces {
+        let bs = actors
+            .brightspace(name)
+            .ok_or_else(|| missing(ActorKind::Brightspace, name))?;
+        let john_id = |name: &str| actors.john(name).map(|john| john.id());
+        // Note: a coordinator that is no longer in the topology gives up its students and conflicts
+        let owners = state
+            .owners
+            .iter()
+            .filter_map(|o| {
+                Some((
+                    o.course.clone(),
+                    o.student.clone(),
+                    john_id(&o.coordinator)?,
+                ))
+            })
+            .collect();
+        let conflicts = state
+            .conflicts
+            .iter()
+            .filter_map(|c| {
+                Some(Conflict {
+                    course: c.course.clone(),
+                    student: c.student.clone(),
+                    owner: john_id(&c.owner)?,
+                    owner_grade: c.owner_grade,
+                    rival: john_id(&c.rival)?,
+                    rival_grade: c.rival_grade,
+                    raised_at: c.raised_at,
+                })
+            })
+            .collect();
+        let restored = BrightspaceSnapshot {
+            shells: state.shells.clone(),
+            owners,
+            conflicts,
+            admin: None,
+        };
+        bs.restore(restored).await?;
+    }
+    for (name, state) in &snapshot.admins {
+        let admin = actors
+            .admin(name)
+            .ok_or_else(|| missing(ActorKind::Admin, name))?;
+        let restored = AdminSnapshot {
+            courses: state.courses.clone(),
+            proposals: state.proposals.clone(),
+            proposal_ttl: state.proposal_ttl,
+            history: state.history.clone(),
+            timelines: state.timelines.clone(),
+        };
+        admin.restore(restored).await?;
+    }
+    for name in snapshot.boosters.keys() {
+        actors
+            .booster(name)
+            .ok_or_else(|| missing(ActorKind::Booster, name))?;
+    }
+
+    // Note: the snapshot's wiring replaces the topology's links for the actors it covers
+    let covered = |name: &String| {
+        snapshot.johns.contains_key(name)
+            || snapshot.brightspaces.contains_key(name)
+            || snapshot.boosters.contains_key(name)
+    };
+    let mut links: Vec<LinkSpec> = topology
+        .links
+        .iter()
+        .filter(|link| !covered(&link.from))
+        .cloned()
+        .collect();
+    let johns = snapshot
+        .johns
+        .iter()
+        .map(|(n, s)| (n, &s.brightspace, ActorKind::Brightspace));
+    let brightspaces = snapshot
+        .brightspaces
+        .iter()
+        .map(|(n, s)| (n, &s.admin, ActorKind::Admin));
+    let boosters = snapshot
+        .boosters
+        .iter()
+        .map(|(n, s)| (n, &s.admin, ActorKind::Admin));
+    for (from, to, kind) in johns.chain(brightspaces).chain(boosters) {
+        let Some(to) = to else { continue };
+        let exists = match kind {
+            ActorKind::Brightspace => actors.brightspace(to).is_some(),
+            _ => actors.admin(to).is_some(),
+        };
+        if !exists {
+            return Err(missing(kind, to));
+        }
+        links.push(LinkSpec {
+            from: from.clone(),
+            to: to.clone(),
+        });
+    }
+    let rewired = Topology {
+        links,
+        ..topology.clone()
+    };
+    rewired.wire(actors).await?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> anyhow::Result<SystemSnapshot> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("{} is not a snapshot", path.display()))
+}
+
+pub fn write(path: &Path, snapshot: &SystemSnapshot) -> anyhow::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(snapshot)?)
+        .with_context(|| format!("could not write {}", path.display()))
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::brightspace;
+use crate::course::{CourseKey, Courses, Scope, Term};
+use crate::events::student_of;
+use crate::grade::Grade;
+use crate::proposal::ProposalId;
+
+// ###################################################### //
+// ############## PER-STUDENT GRADE TIMELINES ########### //
+// ###################################################### //
+
+const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How far apart the first and last score must be before a per-week slope means anything.
+const MIN_TREND_SPAN: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Who gave a student a grade in Admin.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GradeSource {
+    /// John's grade, brought over by a Brightspace sync.
+    #[serde(alias = "Sync")]
+    John,
+    /// A grade changed in Brightspace itself (e.g. an undo pushed back to it), brought over by a sync.
+    BrightspaceEdit,
+    /// An accepted proposal, e.g. a Booster run.
+    Proposal {
+        id: ProposalId,
+        proposed_by: String,
+    },
+    /// `reconcile --fix` (see `reconcile.rs`).
+    Reconciliation,
+    Undo,
+    Redo,
+}
+
+impl fmt::Display for GradeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GradeSource::John => write!(f, "John (Brightspace sync)"),
+            GradeSource::BrightspaceEdit => write!(f, "Brightspace edit"),
+            GradeSource::Proposal { id, proposed_by } => {
+                write!(f, "{} (proposal #{})", proposed_by, id)
+            }
+            GradeSource::Reconciliation => write!(f, "reconciliation"),
+            GradeSource::Undo => write!(f, "undo"),
+            GradeSource::Redo => write!(f, "redo"),
+        }
+    }
+}
+
+/// One value a student's grade held, from `at` until the next point.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradePoint {
+    #[serde(flatten)]
+    pub course: CourseKey,
+    pub grade: Grade,
+    pub at: SystemTime,
+    pub set_by: GradeSource,
+}
+
+/// How a student's scores moved: only scores count, ungraded, incomplete and excused are skipped.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Trend {
+    /// The latest score minus the first one.
+    pub change: f64,
+    /// The least-squares slope of the scores over time, in points per week (`None` until the scores span at
+    /// least a day).
+    pub per_week: Option<f64>,
+}
+
+/// How a student's scores moved in one course.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CourseTrend {
+    pub course: CourseKey,
+    #[serde(flatten)]
+    pub trend: Trend,
+}
+
+impl Trend {
+    /// The trend of every scored point, or `None` with fewer than two.
+    ///  - Note: the points should all be in one course, a score in one course says nothing about another
+    ///    (see `Trend::per_course()`).
+    pub fn of(points: &[GradePoint]) -> Option<Self> {
+        let scores: Vec<(SystemTime, f64)> = points
+            .iter()
+            .filter_map(|p| Some((p.at, p.grade.score()?)))
+            .collect();
+        if scores.len() < 2 {
+            return None;
+        }
+        let ((first_at, first), (last_at, last)) = (scores[0], scores[scores.len() - 1]);
+        let span = last_at.duration_since(first_at).unwrap_or_default();
+
+        let weeks: Vec<(f64, f64)> = scores
+            .iter()
+            .map(|(at, score)| {
+                let since = at.duration_since(first_at).unwrap_or_default();
+                (since.as_secs_f64() / WEEK.as_secs_f64(), *score)
+            })
+            .collect();
+        let n = weeks.len() as f64;
+        let mean_x = weeks.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = weeks.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let spread: f64 = weeks.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let covariance: f64 = weeks.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        Some(Trend {
+            change: last - first,
+            per_week: (span >= MIN_TREND_SPAN && spread > 0.0).then(|| covariance / spread),
+        })
+    }
+
+    /// The trend in each course `points` has at least two scores in.
+    pub fn per_course(points: &[GradePoint]) -> Vec<CourseTrend> {
+        let mut courses: BTreeMap<&CourseKey, Vec<GradePoint>> = BTreeMap::new();
+        for point in points {
+            courses
+                .entry(&point.course)
+                .or_default()
+                .push(point.clone());
+        }
+        courses
+            .into_iter()
+            .filter_map(|(course, points)| {
+                Some(CourseTrend {
+                    course: course.clone(),
+                    trend: Trend::of(&points)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A student's grades over time, oldest first, in every course `grade_history()` was asked about.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GradeHistory {
+    pub student: String,
+    pub points: Vec<GradePoint>,
+    pub trends: Vec<CourseTrend>,
+}
+
+/// Every value every student's grade has held in Admin, by student name (without any career ID).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timelines {
+    pub students: BTreeMap<String, Vec<GradePoint>>,
+}
+
+impl Timelines {
+    /// Adds a point for every student whose grade in an open roster is new or different in `after`.
+    pub(crate) fn record(&mut self, before: &Courses, after: &Courses, set_by: &GradeSource) {
+        self.record_with(before, after, |_, _| set_by.clone());
+    }
+
+    /// Like `record()` for a Brightspace sync of `course`, where the grades of the students in `edited` were
+    /// changed in Brightspace itself and every other grade is John's.
+    pub(crate) fn record_sync(
+        &mut self,
+        before: &Courses,
+        after: &Courses,
+        course: &CourseKey,
+        edited: &BTreeSet<String>,
+    ) {
+        self.record_with(before, after, |key, student| {
+            match key == course && edited.contains(student) {
+                true => GradeSource::BrightspaceEdit,
+                false => GradeSource::John,
+            }
+        });
+    }
+
+    fn record_with(
+        &mut self,
+        before: &Courses,
+        after: &Courses,
+        set_by: impl Fn(&CourseKey, &str) -> GradeSource,
+    ) {
+        let at = SystemTime::now();
+        for (key, roster) in after.open_rosters() {
+            let old = before.roster(key);
+            for (i, name) in roster.names.iter().enumerate() {
+                let grade = roster.grades.get(i).copied().unwrap_or(Grade::Ungraded);
+                let student = student_of(name);
+                let was = old.and_then(|old| {
+                    let index = old.names.iter().position(|n| student_of(n) == student)?;
+                    Some(old.grades.get(index).copied().unwrap_or(Grade::Ungraded))
+                });
+                if was == Some(grade) {
+                    continue;
+                }
+                self.students
+                    .entry(student.to_string())
+                    .or_default()
+                    .push(GradePoint {
+                        course: key.clone(),
+                        grade,
+                        at,
+                        set_by: set_by(key, student),
+                    });
+            }
+        }
+    }
+
+    /// `student`'s points in the courses `scope` covers, with their trend; `student` can also be a career ID.
+    pub(crate) fn history(&self, student: &str, scope: &Scope, current: &Term) -> GradeHistory {
+        let student = student_of(student);
+        let student = match self.students.contains_key(student) {
+            true => student,
+            false => self
+                .students
+                .keys()
+                .find(|name| brightspace::career_id(name).as_deref() == Some(student))
+                .map_or(student, String::as_str),
+        };
+        let points: Vec<GradePoint> = self
+            .students
+            .get(student)
+            .into_iter()
+            .flatten()
+            .filter(|p| scope.includes(&p.course, current))
+            .cloned()
+            .collect();
+        GradeHistory {
+            student: student.to_string(),
+            trends: Trend::per_course(&points),
+            points,
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::access::Caller;
+use crate::alerts::{AlertRules, NotifierSpec};
+use crate::attendance::ParticipationPolicy;
+use crate::call::ActorError;
+use crate::deadline::Deadlines;
+use crate::feedback::CommentBank;
+use crate::rubric::Rubric;
+use crate::*;
+
+// ###################################################### //
+// ################## TOPOLOGY BUILDER ################## //
+// ###################################################### //
+
+/// Mailbox size used for actors whose `[[actors]]` entry has no `mailbox`.
+const DEFAULT_MAILBOX: usize = 8;
+
+/// The kinds of actor a topology file can ask for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ActorKind {
+    John,
+    Brightspace,
+    Admin,
+    Booster,
+    StudentPortal,
+    Alerter,
+    Attendance,
+}
+
+impl ActorKind {
+    /// The kind of actor this kind must hold a handle to, if any (e.g. John reports to Brightspace).
+    fn downstream(self) -> Option<ActorKind> {
+        match self {
+            ActorKind::John => Some(ActorKind::Brightspace),
+            ActorKind::Brightspace => Some(ActorKind::Admin),
+            ActorKind::Booster => Some(ActorKind::Admin),
+            ActorKind::StudentPortal => Some(ActorKind::Admin),
+            ActorKind::Alerter => Some(ActorKind::Admin),
+            ActorKind::Attendance => Some(ActorKind::John),
+            ActorKind::Admin => None,
+        }
+    }
+}
+
+/// One `[[actors]]` entry.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ActorSpec {
+    pub name: String,
+    pub kind: ActorKind,
+    pub mailbox: Option<usize>,
+}
+
+/// One `[[links]]` entry: actor `from` gets a handle to actor `to`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct LinkSpec {
+    pub from: String,
+    pub to: String,
+}
+
+/// The `[alerts]` table: what every Alerter flags and where it sends the alerts.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AlertConfig {
+    #[serde(flatten)]
+    pub rules: AlertRules,
+    /// `log`, `file:<path>` or `webhook:<http url>`.
+    #[serde(default)]
+    pub notify: Vec<NotifierSpec>,
+}
+
+/// A parsed topology file (see `topology.toml` at the repo root for an example).
+#[derive(Clone, Debug, Deserialize)]
+pub struct Topology {
+    #[serde(default)]
+    pub actors: Vec<ActorSpec>,
+    #[serde(default)]
+    pub links: Vec<LinkSpec>,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    /// The `[deadlines]` table: every John marks late work down by it.
+    #[serde(default)]
+    pub deadlines: Deadlines,
+    /// Every `[[rubrics]]` entry: how every John scores work graded on a rubric.
+    #[serde(default)]
+    pub rubrics: Vec<Rubric>,
+    /// The `[comments]` table: the comments every John can attach to a grade by key.
+    #[serde(default)]
+    pub comments: CommentBank,
+    /// The `[attendance]` table: how every Attendance actor scores participation.
+    #[serde(default)]
+    pub attendance: ParticipationPolicy,
+}
+
+/// Everything that can be wrong with a topology file.
+#[derive(Debug)]
+pub enum TopologyError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    DuplicateActor {
+        name: String,
+    },
+    ZeroMailbox {
+        name: String,
+    },
+    UnknownActor {
+        name: String,
+    },
+    DuplicateLink {
+        from: String,
+        to: String,
+    },
+    WrongLink {
+        from: String,
+        to: String,
+        expected: ActorKind,
+    },
+    UnexpectedLink {
+        from: String,
+    },
+    TooManyLinks {
+        from: String,
+        expected: ActorKind,
+    },
+    MissingLink {
+        from: String,
+        expected: ActorKind,
+    },
+    Deadlines(String),
+    Rubrics(String),
+    Attendance(String),
+    Wiring(ActorError),
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyError::Io(e) => write!(f, "could not read topology file: {}", e),
+            TopologyError::Parse(e) => write!(f, "could not parse topology file: {}", e),
+            TopologyError::DuplicateActor { name } => {
+                write!(f, "actor `{}` is declared more than once", name)
+            }
+            TopologyError::ZeroMailbox { name } => {
+                write!(f, "actor `{}` needs a mailbox of at least 1", name)
+            }
+            TopologyError::UnknownActor { name } => {
+                write!(f, "link mentions actor `{}` which is not declared", name)
+            }
+            TopologyError::DuplicateLink { from, to } => {
+                write!(f, "link `{}` -> `{}` is declared more than once", from, to)
+            }
+            TopologyError::WrongLink { from, to, expected } => write!(
+                f,
+                "link `{}` -> `{}` is not allowed, `{}` can only link to a {:?}",
+                from, to, from, expected
+            ),
+            TopologyError::UnexpectedLink { from } => {
+                write!(f, "actor `{}` does not link to anything", from)
+            }
+            TopologyError::TooManyLinks { from, expected } => {
+                write!(f, "actor `{}` links to more than one {:?}", from, expected)
+            }
+            TopologyError::MissingLink { from, expected } => {
+                write!(f, "actor `{}` needs a link to a {:?}", from, expected)
+            }
+            TopologyError::Deadlines(reason) => write!(f, "bad [deadlines] table: {}", reason),
+            TopologyError::Rubrics(reason) => write!(f, "bad [[rubrics]] entry: {}", reason),
+            TopologyError::Attendance(reason) => write!(f, "bad [attendance] table: {}", reason),
+            TopologyError::Wiring(e) => write!(f, "could not wire actors: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+impl From<ActorError> for TopologyError {
+    fn from(e: ActorError) -> Self {
+        TopologyError::Wiring(e)
+    }
+}
+
+/// Every actor a topology started, looked up by the name it was given in the file.
+#[derive(Clone, Debug, Default)]
+pub struct Actors {
+    pub johns: BTreeMap<String, JohnHandle>,
+    pub brightspaces: BTreeMap<String, BrightspaceHandle>,
+    pub admins: BTreeMap<String, AdminHandle>,
+    pub boosters: BTreeMap<String, BoosterHandle>,
+    pub portals: BTreeMap<String, StudentPortalHandle>,
+    pub alerters: BTreeMap<String, AlerterHandle>,
+    pub attendances: BTreeMap<String, AttendanceHandle>,
+}
+
+impl Actors {
+    pub fn john(&self, name: &str) -> Option<&JohnHandle> {
+        self.johns.get(name)
+    }
+
+    pub fn brightspace(&self, name: &str) -> Option<&BrightspaceHandle> {
+        self.brightspaces.get(name)
+    }
+
+    pub fn admin(&self, name: &str) -> Option<&AdminHandle> {
+        self.admins.get(name)
+    }
+
+    pub fn booster(&self, name: &str) -> Option<&BoosterHandle> {
+        self.boosters.get(name)
+    }
+
+    pub fn portal(&self, name: &str) -> Option<&StudentPortalHandle> {
+        self.portals.get(name)
+    }
+
+    pub fn alerter(&self, name: &str) -> Option<&AlerterHandle> {
+        self.alerters.get(name)
+    }
+
+    pub fn attendance(&self, name: &str) -> Option<&AttendanceHandle> {
+        self.attendances.get(name)
+    }
+
+    /// The same actors, but every call through them is checked against what `caller` may do.
+    pub fn acting_as(&self, caller: Caller) -> Actors {
+        fn each<H>(handles: &BTreeMap<String, H>, f: impl Fn(&H) -> H) -> BTreeMap<String, H> {
+            handles
+                .iter()
+                .map(|(name, h)| (name.clone(), f(h)))
+                .collect()
+        }
+        Actors {
+            johns: each(&self.johns, |h| h.acting_as(caller.clone())),
+            brightspaces: each(&self.brightspaces, |h| h.acting_as(caller.clone())),
+            admins: each(&self.admins, |h| h.acting_as(caller.clone())),
+            boosters: each(&self.boosters, |h| h.acting_as(caller.clone())),
+            portals: each(&self.portals, |h| h.acting_as(caller.clone())),
+            alerters: each(&self.alerters, |h| h.acting_as(caller.clone())),
+            attendances: each(&self.attendances, |h| h.acting_as(caller.clone())),
+        }
+    }
+}
+
+impl Topology {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TopologyError> {
+        let text = std::fs::read_to_string(path).map_err(TopologyError::Io)?;
+        Topology::parse(&text)
+    }
+
+    /// Parses and validates a topology written in TOML.
+    pub fn parse(text: &str) -> Result<Self, TopologyError> {
+        let topology: Topology = toml::from_str(text).map_err(TopologyError::Parse)?;
+        topology.validate()?;
+        Ok(topology)
+    }
+
+    fn kind_of(&self, name: &str) -> Result<ActorKind, TopologyError> {
+        self.actors
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.kind)
+            .ok_or_else(|| TopologyError::UnknownActor {
+                name: name.to_string(),
+            })
+    }
+
+    /// Checks that every actor is declared once, that every actor but Admin links to exactly
+    /// one actor of the kind it reports to, and that the deadlines, rubrics and attendance policy make sense.
+    pub fn validate(&self) -> Result<(), TopologyError> {
+        self.deadlines
+            .validate()
+            .map_err(TopologyError::Deadlines)?;
+        for (i, rubric) in self.rubrics.iter().enumerate() {
+            if self.rubrics[..i].iter().any(|r| r.name == rubric.name) {
+                return Err(TopologyError::Rubrics(format!(
+                    "rubric `{}` is declared more than once",
+                    rubric.name
+                )));
+            }
+            rubric.validate().map_err(TopologyError::Rubrics)?;
+        }
+        self.attendance
+            .validate()
+            .map_err(TopologyError::Attendance)?;
+
+        for (i, actor) in self.actors.iter().enumerate() {
+            if self.actors[..i].iter().any(|a| a.name == actor.name) {
+                return Err(TopologyError::DuplicateActor {
+                    name: actor.name.clone(),
+                });
+            }
+            if actor.mailbox == Some(0) {
+                return Err(TopologyError::ZeroMailbox {
+                    name: actor.name.clone(),
+                });
+            }
+        }
+
+        for (i, link) in self.links.iter().enumerate() {
+            if self.links[..i].contains(link) {
+                return Err(TopologyError::DuplicateLink {
+                    from: link.from.clone(),
+                    to: link.to.clone(),
+                });
+            }
+
+            let from = self.kind_of(&link.from)?;
+            let to = self.kind_of(&link.to)?;
+            match from.downstream() {
+                None => {
+                    return Err(TopologyError::UnexpectedLink {
+                        from: link.from.clone(),
+                    });
+                }
+                Some(expected) if expected != to => {
+                    return Err(TopologyError::WrongLink {
+                        from: link.from.clone(),
+                        to: link.to.clone(),
+                        expected,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        for actor in &self.actors {
+            if let Some(expected) = actor.kind.downstream() {
+                let mut outgoing = self.links.iter().filter(|l| l.from == actor.name);
+                match (outgoing.next(), outgoing.next()) {
+                    (None, _) => {
+                        return Err(TopologyError::MissingLink {
+                            from: actor.name.clone(),
+                            expected,
+                        });
+                    }
+                    (Some(_), Some(_)) => {
+                        return Err(TopologyError::TooManyLinks {
+                            from: actor.name.clone(),
+                            expected,
+                        });
+                    }
+                    (Some(_), None) => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts every declared actor, wires them as the links say, and hands back all their handles.
+    pub async fn build(&self) -> Result<Actors, TopologyError> {
+        let actors = self.start().await?;
+        self.wire(&actors).await?;
+        Ok(actors)
+    }
+
+    /// Starts every declared actor without linking any of them yet (see `wire()`).
+    pub async fn start(&self) -> Result<Actors, TopologyError> {
+        self.validate()?;
+
+        let mut actors = Actors::default();
+        for spec in &self.actors {
+            let mailbox = spec.mailbox.unwrap_or(DEFAULT_MAILBOX);
+            let name = spec.name.clone();
+            match spec.kind {
+                ActorKind::John => {
+                    let john = JohnHandle::with_mailbox(mailbox).await;
+                    john.set_deadlines(self.deadlines.clone()).await?;
+                    john.set_rubrics(self.rubrics.clone()).await?;
+                    john.set_comment_bank(self.comments.clone()).await?;
+                    actors.johns.insert(name, john);
+                }
+                ActorKind::Brightspace => {
+                    actors
+                        .brightspaces
+                        .insert(name, BrightspaceHandle::with_mailbox(mailbox).await);
+                }
+                ActorKind::Admin => {
+                    actors
+                        .admins
+                        .insert(name, AdminHandle::with_mailbox(mailbox).await);
+                }
+                ActorKind::Booster => {
+                    actors
+                        .boosters
+                        .insert(name, BoosterHandle::with_mailbox(mailbox).await);
+                }
+                ActorKind::StudentPortal => {
+                    actors
+                        .portals
+                        .insert(name, StudentPortalHandle::with_mailbox(mailbox).await);
+                }
+                ActorKind::Alerter => {
+                    let alerter = AlerterHandle::with_mailbox(mailbox).await;
+                    alerter.set_rules(self.alerts.rules.clone()).await?;
+                    for spec in &self.alerts.notify {
+                        alerter.add_notifier(spec.open()).await?;
+                    }
+                    actors.alerters.insert(name, alerter);
+                }
+                ActorKind::Attendance => {
+                    let attendance = AttendanceHandle::with_mailbox(mailbox).await;
+                    attendance.set_policy(self.attendance.clone()).await?;
+                    actors.attendances.insert(name, attendance);
+                }
+            }
+        }
+        Ok(actors)
+    }
+
+    /// Hands every actor in `actors` the handle its link says it reports to.
+    ///  - Note: anything loaded into the actors before this (e.g. a saved gradebook) is their starting point,
+    ///    so a StudentPortal serves it and an Alerter does not raise alerts about it.
+    pub async fn wire(&self, actors: &Actors) -> Result<(), TopologyError> {
+        // Note: `validate()` already made sure every name exists and every link has the right kinds
+        for link in &self.links {
+            if let Some(john) = actors.john(&link.from) {
+                john.set_brightspace(actors.brightspaces[&link.to].clone())
+                    .await?;
+            } else if let Some(bs) = actors.brightspace(&link.from) {
+                bs.set_admin(actors.admins[&link.to].feed()?).await?;
+            } else if let Some(booster) = actors.booster(&link.from) {
+                let approval = actors.admins[&link.to].approve_booster()?;
+                booster.set_admin(approval).await?;
+            } else if let Some(portal) = actors.portal(&link.from) {
+                portal
+                    .watch_admin(&actors.admins[&link.to].read_only())
+                    .await?;
+            } else if let Some(alerter) = actors.alerter(&link.from) {
+                alerter
+                    .watch_admin(&actors.admins[&link.to].read_only())
+                    .await?;
+            } else if let Some(attendance) = actors.attendance(&link.from) {
+                attendance.set_john(actors.johns[&link.to].clone()).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// ###################################################### //
+// ################## ACTOR TRACE LOGS ################## //
+// ###################################################### //
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns the `[ACTOR]` / `[run_x_actor()]` trace lines on or off for the whole program.
+///  - Note: they are on by default, which is what you want while learning how messages flow,
+///    and off in the CLI so that e.g. `--json` output is not mixed with trace lines.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Exactly like `println!`, except nothing is printed while tracing is turned off.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled() {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use trace;
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::call::ActorError;
+
+// ###################################################### //
+// ################## WIRING REGISTRY ################### //
+// ###################################################### //
+
+/// Names one running actor, e.g. `Brightspace#2`.
+///  - Note: every `XHandle::new()` makes a brand new actor, so every handle gets a brand new `ActorId`
+///    (clones of a handle share the id of the actor they talk to).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ActorId {
+    kind: &'static str,
+    serial: u64,
+}
+
+impl ActorId {
+    pub(crate) fn next(kind: &'static str) -> Self {
+        static NEXT_SERIAL: AtomicU64 = AtomicU64::new(1);
+        ActorId {
+            kind,
+            serial: NEXT_SERIAL.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.kind, self.serial)
+    }
+}
+
+/// One actor sitting inside `handle_message()` waiting for another actor to answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stall {
+    pub waiter: ActorId,
+    pub waiting_on: ActorId,
+    pub waited: Duration,
+    pub in_cycle: bool, // true when `waiting_on` is (directly or not) also waiting on `waiter`
+}
+
+#[derive(Default)]
+struct Registry {
+    // Who holds whose handle: `links[John#1]["Brightspace"] == Brightspace#2` after `john.set_brightspace(..)`
+    links: HashMap<ActorId, HashMap<&'static str, ActorId>>,
+    // Who is waiting on whom right now, keyed by the token of the `Waiting` guard
+    waits: HashMap<u64, (ActorId, ActorId, Instant)>,
+    next_token: u64,
+}
+
+fn registry() -> MutexGuard<'static, Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Follows `edges` from `start` and returns the path to `goal`, if there is one.
+fn find_path(
+    start: ActorId,
+    goal: ActorId,
+    edges: &dyn Fn(ActorId) -> Vec<ActorId>,
+) -> Option<Vec<ActorId>> {
+    let mut stack = vec![vec![start]];
+    let mut seen = vec![start];
+    while let Some(path) = stack.pop() {
+        let last = *path.last().unwrap();
+        if last == goal {
+            return Some(path);
+        }
+        for next in edges(last) {
+            if !seen.contains(&next) {
+                seen.push(next);
+                let mut longer = path.clone();
+                longer.push(next);
+                stack.push(longer);
+            }
+        }
+    }
+    None
+}
+
+/// Records that `from` now holds a handle to `to`, replacing any earlier handle of the same kind.
+///  - Returns `ActorError::Cycle` (and records nothing) if `to` can already reach `from`, since a
+///    request travelling around that loop would end up waiting on itself.
+///  - Note: the link is undone when the returned `Link` is dropped, unless `Link::keep()` is called once
+///    the handle has actually been handed over; a failed send must not leave a link behind.
+pub(crate) fn connect(from: ActorId, to: ActorId) -> Result<Link, ActorError> {
+    let mut reg = registry();
+
+    let links = &reg.links;
+    let edges = |id: ActorId| -> Vec<ActorId> {
+        links
+            .get(&id)
+            .map(|by_kind| by_kind.values().copied().collect())
+            .unwrap_or_default()
+    };
+    if let Some(mut path) = find_path(to, from, &edges) {
+        path.insert(0, from);
+        return Err(ActorError::Cycle { path });
+    }
+
+    let replaced = reg.links.entry(from).or_default().insert(to.kind, to);
+    Ok(Link {
+        from,
+        to,
+        replaced,
+        kept: false,
+    })
+}
+
+/// A link recorded by `connect()` that has not been confirmed yet.
+pub(crate) struct Link {
+    from: ActorId,
+    to: ActorId,
+    replaced: Option<ActorId>,
+    kept: bool,
+}
+
+impl Link {
+    /// Keeps the link for good, e.g. once the actor has been sent the handle.
+    pub(crate) fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let mut reg = registry();
+        let Some(by_kind) = reg.links.get_mut(&self.from) else {
+            return;
+        };
+        // Only undo our own link, a later `connect()` of the same kind may have replaced it already
+        if by_kind.get(self.to.kind) != Some(&self.to) {
+            return;
+        }
+        match self.replaced {
+            Some(earlier) => {
+                by_kind.insert(self.to.kind, earlier);
+            }
+            None => {
+                by_kind.remove(self.to.kind);
+                if by_kind.is_empty() {
+                    reg.links.remove(&self.from);
+                }
+            }
+        }
+    }
+}
+
+/// Every actor that `id` currently holds a handle to.
+pub fn links_of(id: ActorId) -> Vec<ActorId> {
+    registry()
+        .links
+        .get(&id)
+        .map(|by_kind| by_kind.values().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Marks `waiter` as waiting on `waiting_on` until the returned guard is dropped.
+///  - Use it around the downstream calls an actor makes from inside `handle_message()`.
+pub(crate) fn waiting(waiter: ActorId, waiting_on: ActorId) -> Waiting {
+    let mut reg = registry();
+    let token = reg.next_token;
+    reg.next_token += 1;
+    reg.waits
+        .insert(token, (waiter, waiting_on, Instant::now()));
+    Waiting { token }
+}
+
+pub(crate) struct Waiting {
+    token: u64,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        registry().waits.remove(&self.token);
+    }
+}
+
+/// Every actor that has been waiting on another actor for at least `threshold`.
+pub fn stalls(threshold: Duration) -> Vec<Stall> {
+    let reg = registry();
+    let now = Instant::now();
+
+    let waits: Vec<(ActorId, ActorId, Instant)> = reg.waits.values().copied().collect();
+    let edges = |id: ActorId| -> Vec<ActorId> {
+        waits
+            .iter()
+            .filter(|(waiter, _, _)| *waiter == id)
+            .map(|(_, waiting_on, _)| *waiting_on)
+            .collect()
+    };
+
+    let mut stalls: Vec<Stall> = waits
+        .iter()
+        .filter(|(_, _, since)| now.duration_since(*since) >= threshold)
+        .map(|(waiter, waiting_on, since)| Stall {
+            waiter: *waiter,
+            waiting_on: *waiting_on,
+            waited: now.duration_since(*since),
+            in_cycle: find_path(*waiting_on, *waiter, &edges).is_some(),
+        })
+        .collect();
+    stalls.sort_by_key(|stall| std::cmp::Reverse(stall.waited));
+    stalls
+}
+
+/// Starts a background task that checks for stalls every `every` and reports the ones older than `threshold`.
+pub fn spawn_watchdog(every: Duration, threshold: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            for stall in stalls(threshold) {
+                eprintln!(
+                    "[WATCHDOG]: {} has been waiting on {} for {:?}{}",
+                    stall.waiter,
+                    stall.waiting_on,
+                    stall.waited,
+                    if stall.in_cycle {
+                        " (DEADLOCK: they are waiting on each other)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+    })
+}